@@ -0,0 +1,66 @@
+//! Example: Serving metrics and health checks on separate ports.
+//!
+//! Metrics go on an internal port while health and readiness checks are
+//! served on a public one. Both servers share one registry and run in a
+//! `JoinSet`, so the process exits as soon as either of them stops.
+//!
+//! Run with:
+//! ```bash
+//! cargo run --example multi-port --features "prometheus standalone"
+//! ```
+
+#[cfg(all(feature = "prometheus", feature = "standalone"))]
+use observability_kit::prelude::*;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(all(feature = "prometheus", feature = "standalone"))]
+    {
+        use tokio::task::JoinSet;
+
+        let internal = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(9090)
+            .build();
+
+        // Same registry, but only health and readiness are exposed publicly
+        let public = StandaloneServer::<PrometheusBackend>::builder()
+            .host("0.0.0.0")
+            .port(8080)
+            .serve_metrics(false)
+            .registry(internal.registry())
+            .build();
+
+        let requests = internal
+            .registry()
+            .write()
+            .unwrap()
+            .counter("http_requests_total", "Total HTTP requests received")?;
+        requests.inc();
+
+        println!("Metrics:   http://127.0.0.1:9090/metrics");
+        println!("Health:    http://0.0.0.0:8080/health");
+        println!("Readiness: http://0.0.0.0:8080/ready");
+
+        let mut servers = JoinSet::new();
+        servers.spawn(async move { ("internal", internal.run().await) });
+        servers.spawn(async move { ("public", public.run().await) });
+
+        // Supervise both servers: neither should ever stop, so the first one
+        // that does takes the process down with it.
+        if let Some(stopped) = servers.join_next().await {
+            servers.abort_all();
+            match stopped? {
+                (name, Ok(())) => return Err(format!("{name} server stopped").into()),
+                (name, Err(e)) => return Err(format!("{name} server failed: {e}").into()),
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "prometheus", feature = "standalone")))]
+    {
+        println!("Run with: cargo run --example multi-port --features \"prometheus standalone\"");
+    }
+
+    Ok(())
+}
@@ -26,7 +26,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Get the registry and create some metrics
         {
             let registry_handle = server.registry();
-            let mut registry = registry_handle.write().await;
+            let mut registry = registry_handle.write().unwrap();
 
             let requests =
                 registry.counter("http_requests_total", "Total HTTP requests received")?;
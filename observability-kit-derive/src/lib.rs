@@ -0,0 +1,136 @@
+//! Derive macros for `observability-kit`.
+//!
+//! Use these through the `derive` feature of `observability-kit` rather than
+//! depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derive registration for a struct whose fields are metrics.
+///
+/// Generates `fn register(registry: &mut ObservabilityRegistry<B>) -> Result<Self, DeserializeError>`,
+/// which registers every field and returns the populated struct. Each field
+/// takes a `#[metric(name = "...", help = "...")]` attribute; `name` defaults
+/// to the field name.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Metrics)]
+/// struct ServerMetrics {
+///     #[metric(name = "requests_total", help = "Requests served")]
+///     requests: PrometheusCounter,
+///     #[metric(help = "Open connections")]
+///     connections: PrometheusGauge,
+/// }
+///
+/// let metrics = ServerMetrics::register(&mut registry)?;
+/// metrics.requests.inc();
+/// ```
+#[proc_macro_derive(Metrics, attributes(metric))]
+pub fn derive_metrics(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_metrics(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// A field to register, with its exposition name and help text.
+struct MetricField<'a> {
+    ident: &'a syn::Ident,
+    ty: &'a syn::Type,
+    name: LitStr,
+    help: LitStr,
+}
+
+fn expand_metrics(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Metrics can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Metrics can only be derived for structs with named fields",
+        ));
+    };
+
+    let fields = fields
+        .named
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let bounds = fields.iter().map(|field| {
+        let ty = field.ty;
+        quote! { #ty: ::observability_kit::core::registry::Register<B> }
+    });
+    let registrations = fields.iter().map(|field| {
+        let MetricField {
+            ident,
+            ty,
+            name,
+            help,
+        } = field;
+        quote! {
+            #ident: <#ty as ::observability_kit::core::registry::Register<B>>::register(
+                registry, #name, #help,
+            )?
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Register every metric field in `registry` and return the populated struct.
+            pub fn register<B>(
+                registry: &mut ::observability_kit::core::registry::ObservabilityRegistry<B>,
+            ) -> ::std::result::Result<Self, ::observability_kit::core::deserialise::DeserializeError>
+            where
+                B: ::observability_kit::core::registry::MetricBackend,
+                #(#bounds,)*
+            {
+                ::std::result::Result::Ok(Self {
+                    #(#registrations,)*
+                })
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<MetricField<'_>> {
+    let ident = field.ident.as_ref().expect("named fields have identifiers");
+    let mut name = None;
+    let mut help = None;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("metric")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("help") {
+                help = Some(meta.value()?.parse::<LitStr>()?);
+            } else {
+                return Err(meta.error("expected `name` or `help`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let help = help.ok_or_else(|| {
+        syn::Error::new_spanned(
+            ident,
+            "missing help text: add #[metric(help = \"...\")] to this field",
+        )
+    })?;
+
+    Ok(MetricField {
+        ident,
+        ty: &field.ty,
+        name: name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span())),
+        help,
+    })
+}
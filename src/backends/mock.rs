@@ -3,7 +3,9 @@
 //! This module provides lightweight metric implementations using atomics,
 //! perfect for unit testing without needing a real metrics backend.
 
-use crate::core::metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
+use crate::core::metrics::{
+    CounterTrait, FloatGaugeTrait, GaugeTrait, HistogramStatsTrait, HistogramTrait, Metric,
+};
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -101,6 +103,54 @@ impl GaugeTrait for MockGauge {
     fn get(&self) -> i64 {
         self.0.load(Ordering::Relaxed)
     }
+
+    fn swap(&self, value: i64) -> i64 {
+        self.0.swap(value, Ordering::Relaxed)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// MockFloatGauge
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A mock float gauge for testing purposes.
+///
+/// Stores the bits of the value in an atomic, like [`MockGauge`].
+#[derive(Clone, Default, Debug)]
+pub struct MockFloatGauge(Arc<AtomicU64>);
+
+impl MockFloatGauge {
+    /// Create a new mock float gauge starting at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new mock float gauge with an initial value.
+    pub fn with_value(value: f64) -> Self {
+        Self(Arc::new(AtomicU64::new(value.to_bits())))
+    }
+}
+
+impl FloatGaugeTrait for MockFloatGauge {
+    fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    fn inc_by(&self, value: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+
+    fn dec_by(&self, value: f64) {
+        self.inc_by(-value);
+    }
+
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -146,6 +196,27 @@ impl HistogramTrait for MockHistogram {
     fn observe(&self, value: f64) {
         self.observations.lock().unwrap().push(value);
     }
+
+    fn observe_weighted(&self, value: f64, count: u64) {
+        let mut observations = self.observations.lock().unwrap();
+        observations.extend(std::iter::repeat_n(value, count as usize));
+    }
+}
+
+impl HistogramStatsTrait for MockHistogram {
+    /// The mock keeps raw observations rather than buckets, so it reports a
+    /// single `+Inf` bucket holding every observation.
+    fn bucket_counts(&self) -> Vec<(f64, u64)> {
+        vec![(f64::INFINITY, HistogramStatsTrait::count(self))]
+    }
+
+    fn sum(&self) -> f64 {
+        MockHistogram::sum(self)
+    }
+
+    fn count(&self) -> u64 {
+        MockHistogram::count(self) as u64
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -158,6 +229,9 @@ pub type TestCounter = Metric<MockCounter>;
 /// A mock gauge metric with metadata.
 pub type TestGauge = Metric<MockGauge>;
 
+/// A mock float gauge metric with metadata.
+pub type TestFloatGauge = Metric<MockFloatGauge>;
+
 /// A mock histogram metric with metadata.
 pub type TestHistogram = Metric<MockHistogram>;
 
@@ -175,6 +249,11 @@ pub fn test_gauge(name: impl Into<String>, description: impl Into<String>) -> Te
     Metric::new(name, description, MockGauge::new())
 }
 
+/// Create a new mock float gauge for testing.
+pub fn test_float_gauge(name: impl Into<String>, description: impl Into<String>) -> TestFloatGauge {
+    Metric::new(name, description, MockFloatGauge::new())
+}
+
 /// Create a new mock histogram for testing.
 pub fn test_histogram(name: impl Into<String>, description: impl Into<String>) -> TestHistogram {
     Metric::new(name, description, MockHistogram::new())
@@ -206,6 +285,17 @@ mod tests {
         assert_eq!(gauge.get_gauge(), 70);
     }
 
+    #[test]
+    fn test_mock_float_gauge() {
+        let gauge = test_float_gauge("test_float_gauge", "A test float gauge");
+
+        assert_eq!(gauge.get_gauge_f64(), 0.0);
+        gauge.set_f64(1.5);
+        gauge.inc_by_f64(0.25);
+        assert_eq!(gauge.get_gauge_f64(), 1.75);
+        assert_eq!(MockFloatGauge::with_value(-2.0).get(), -2.0);
+    }
+
     #[test]
     fn test_mock_histogram() {
         let histogram = test_histogram("test_histogram", "A test histogram");
@@ -216,6 +306,9 @@ mod tests {
 
         assert_eq!(histogram.inner().count(), 3);
         assert!((histogram.inner().sum() - 0.6).abs() < 0.001);
+
+        histogram.observe_weighted(1.0, 2);
+        assert_eq!(histogram.inner().observations()[3..], [1.0, 1.0]);
     }
 
     #[test]
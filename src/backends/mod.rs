@@ -6,7 +6,7 @@
 #[cfg(feature = "prometheus")]
 pub mod prometheus;
 
-#[cfg(feature = "mock")]
+#[cfg(any(test, feature = "mock"))]
 pub mod mock;
 
 // Re-exports for convenience
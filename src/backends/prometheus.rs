@@ -16,7 +16,8 @@
 //! }
 //!
 //! let latency: Family<HttpLabels, Histogram> = Family::new_with_constructor(|| {
-//!     Histogram::new([0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0].into_iter())
+//!     Histogram::with_buckets("latency", vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0])
+//!         .expect("buckets are sorted and finite")
 //! });
 //!
 //! // Observe a value with specific labels
@@ -25,14 +26,46 @@
 //!     status: 200,
 //! }).observe(0.042);
 //! ```
+//!
+//! Label values with a fixed set of options can be declared as an enum
+//! deriving `EncodeLabelValue`, so an invalid value fails to compile. The
+//! variant name is used as the label value.
+//!
+//! ```ignore
+//! use observability_kit::backends::prometheus::{EncodeLabelSet, EncodeLabelValue};
+//!
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
+//! enum Method {
+//!     Get,
+//!     Post,
+//! }
+//!
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+//! struct RequestLabels {
+//!     method: Method,
+//! }
+//!
+//! let requests: LabeledCounter<RequestLabels> = labeled_counter();
+//! requests.get_or_create(&RequestLabels { method: Method::Get }).inc();
+//! ```
 
-use crate::core::metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
-use crate::core::registry::{MetricBackend, ObservabilityRegistry};
-use prometheus_client::metrics::{counter::Counter, gauge::Gauge, histogram::Histogram};
+use crate::core::deserialise::{
+    normalize_and_validate_buckets, DeserializeError, DEFAULT_MAX_BUCKETS,
+};
+use crate::core::labeled::Labeled;
+use crate::core::metrics::{
+    CounterTrait, FloatCounterTrait, FloatGaugeTrait, GaugeTrait, HistogramStatsTrait,
+    HistogramTrait, LabeledGaugeTrait, LabeledHistogramTrait, Metric, MetricError,
+};
+use crate::core::registry::{MetricBackend, ObservabilityRegistry, Register};
+use prometheus_client::encoding::{EncodeMetric, MetricEncoder, NoLabelSet};
+use prometheus_client::metrics::{counter::Counter, gauge::Gauge, MetricType, TypedMetric};
 use prometheus_client::registry::Registry;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 // Re-export key types for labeled metrics
-pub use prometheus_client::encoding::EncodeLabelSet;
+pub use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
 pub use prometheus_client::metrics::family::Family;
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -53,6 +86,20 @@ impl CounterTrait for Counter<u64> {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// FloatCounterTrait implementation for prometheus-client Counter<f64>
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl FloatCounterTrait for Counter<f64, AtomicU64> {
+    fn inc_by(&self, value: f64) {
+        Counter::inc_by(self, value);
+    }
+
+    fn get(&self) -> f64 {
+        Counter::get(self)
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // GaugeTrait implementation for prometheus-client Gauge
 // ═══════════════════════════════════════════════════════════════════════════
@@ -81,15 +128,157 @@ impl GaugeTrait for Gauge<i64> {
     fn get(&self) -> i64 {
         Gauge::get(self)
     }
+
+    fn swap(&self, value: i64) -> i64 {
+        // `prometheus-client` sets with an atomic swap
+        Gauge::set(self, value)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// FloatGaugeTrait implementation for prometheus-client Gauge<f64>
+// ═══════════════════════════════════════════════════════════════════════════
+
+// The value is stored as its bit pattern in an AtomicU64, so `get` returns
+// exactly what was `set`, including subnormals, signed zero and NaN payloads.
+impl FloatGaugeTrait for Gauge<f64, AtomicU64> {
+    fn set(&self, value: f64) {
+        Gauge::set(self, value);
+    }
+
+    fn inc_by(&self, value: f64) {
+        Gauge::inc_by(self, value);
+    }
+
+    fn dec_by(&self, value: f64) {
+        Gauge::dec_by(self, value);
+    }
+
+    fn get(&self) -> f64 {
+        Gauge::get(self)
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
 // HistogramTrait implementation for prometheus-client Histogram
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// A histogram whose bucket counts and sum can be read back.
+///
+/// Encodes the same as `prometheus-client`'s histogram, which keeps its
+/// storage private; holding the counts here lets [`HistogramStatsTrait`]
+/// read them directly. Values above every finite bound, including `+Inf`
+/// and `NaN`, land in the `+Inf` bucket.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    inner: Arc<Mutex<HistogramInner>>,
+}
+
+#[derive(Debug)]
+struct HistogramInner {
+    sum: f64,
+    count: u64,
+    /// Upper bound and count of each bucket, not cumulative. The last bound
+    /// is `f64::MAX`, which the encoder writes as `+Inf`.
+    buckets: Vec<(f64, u64)>,
+}
+
+impl Histogram {
+    /// Create a histogram with the given finite upper bounds, in ascending
+    /// order. The `+Inf` bucket is added.
+    pub(crate) fn new(buckets: impl IntoIterator<Item = f64>) -> Self {
+        let buckets = buckets
+            .into_iter()
+            .chain(std::iter::once(f64::MAX))
+            .map(|bound| (bound, 0))
+            .collect();
+        Self {
+            inner: Arc::new(Mutex::new(HistogramInner {
+                sum: 0.0,
+                count: 0,
+                buckets,
+            })),
+        }
+    }
+
+    /// Create a histogram with custom buckets for use outside a registry,
+    /// e.g. as the series of a [`Family`].
+    ///
+    /// Buckets are checked with [`normalize_and_validate_buckets`] against
+    /// [`DEFAULT_MAX_BUCKETS`]; `name` is only used in the error.
+    pub fn with_buckets(name: &str, buckets: Vec<f64>) -> Result<Self, DeserializeError> {
+        let buckets = normalize_and_validate_buckets(name, buckets, DEFAULT_MAX_BUCKETS)?;
+        Ok(Self::new(buckets))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HistogramInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add `count` observations of `value` to its bucket, the count and the sum.
+    fn record(&self, value: f64, count: u64) {
+        let mut inner = self.lock();
+        inner.sum += value * count as f64;
+        inner.count = inner.count.saturating_add(count);
+        let last = inner.buckets.len() - 1;
+        let index = inner
+            .buckets
+            .iter()
+            .position(|(bound, _)| value <= *bound)
+            .unwrap_or(last);
+        let bucket = &mut inner.buckets[index].1;
+        *bucket = bucket.saturating_add(count);
+    }
+}
+
 impl HistogramTrait for Histogram {
     fn observe(&self, value: f64) {
-        Histogram::observe(self, value);
+        self.record(value, 1);
+    }
+
+    fn observe_weighted(&self, value: f64, count: u64) {
+        self.record(value, count);
+    }
+}
+
+impl HistogramStatsTrait for Histogram {
+    fn bucket_counts(&self) -> Vec<(f64, u64)> {
+        let inner = self.lock();
+        let last = inner.buckets.len() - 1;
+        let mut cumulative = 0u64;
+        inner
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, (bound, count))| {
+                cumulative = cumulative.saturating_add(*count);
+                let bound = if i == last { f64::INFINITY } else { *bound };
+                (bound, cumulative)
+            })
+            .collect()
+    }
+
+    fn sum(&self) -> f64 {
+        self.lock().sum
+    }
+
+    fn count(&self) -> u64 {
+        self.lock().count
+    }
+}
+
+impl TypedMetric for Histogram {
+    const TYPE: MetricType = MetricType::Histogram;
+}
+
+impl EncodeMetric for Histogram {
+    fn encode(&self, mut encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+        let inner = self.lock();
+        encoder.encode_histogram::<NoLabelSet>(inner.sum, inner.count, &inner.buckets, None)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
     }
 }
 
@@ -98,10 +287,15 @@ impl HistogramTrait for Histogram {
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Error type for Prometheus registration operations.
+///
+/// Registry methods report [`DeserializeError`]; it converts into this type,
+/// so code propagating registration errors as `PrometheusError` keeps working.
 #[derive(Debug, thiserror::Error)]
 pub enum PrometheusError {
     #[error("Failed to register metric: {0}")]
     RegistrationError(String),
+    #[error(transparent)]
+    InvalidDefinition(#[from] DeserializeError),
 }
 
 /// Prometheus backend marker type.
@@ -113,8 +307,11 @@ pub struct PrometheusBackend;
 impl MetricBackend for PrometheusBackend {
     type Registry = Registry;
     type Counter = Counter<u64>;
+    type FloatCounter = Counter<f64, AtomicU64>;
     type Gauge = Gauge<i64>;
     type Histogram = Histogram;
+    type LabeledHistogram = HistogramFamily;
+    type LabeledGauge = GaugeFamily;
     type Error = PrometheusError;
 
     fn create_registry() -> Self::Registry {
@@ -131,6 +328,16 @@ impl MetricBackend for PrometheusBackend {
         Ok(counter)
     }
 
+    fn register_float_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self::FloatCounter, Self::Error> {
+        let counter = Counter::default();
+        registry.register(name, help, counter.clone());
+        Ok(counter)
+    }
+
     fn register_gauge(
         registry: &mut Self::Registry,
         name: &str,
@@ -147,10 +354,102 @@ impl MetricBackend for PrometheusBackend {
         help: &str,
         buckets: Vec<f64>,
     ) -> Result<Self::Histogram, Self::Error> {
-        let histogram = Histogram::new(buckets);
+        let histogram = Histogram::new(finite_buckets(buckets));
         registry.register(name, help, histogram.clone());
         Ok(histogram)
     }
+
+    fn register_labeled_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        _label_names: &[String],
+        buckets: Vec<f64>,
+    ) -> Result<Self::LabeledHistogram, Self::Error> {
+        let family =
+            Family::new_with_constructor(HistogramBuckets(finite_buckets(buckets).collect()));
+        registry.register(name, help, family.clone());
+        Ok(family)
+    }
+
+    fn register_labeled_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        _label_names: &[String],
+    ) -> Result<Self::LabeledGauge, Self::Error> {
+        let family = GaugeFamily::default();
+        registry.register(name, help, family.clone());
+        Ok(family)
+    }
+
+    fn register_existing_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        counter: &Self::Counter,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, counter.clone());
+        Ok(())
+    }
+
+    fn register_existing_float_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        counter: &Self::FloatCounter,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, counter.clone());
+        Ok(())
+    }
+
+    fn register_existing_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        gauge: &Self::Gauge,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, gauge.clone());
+        Ok(())
+    }
+
+    fn register_existing_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        histogram: &Self::Histogram,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, histogram.clone());
+        Ok(())
+    }
+
+    fn register_existing_labeled_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        family: &Self::LabeledHistogram,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, family.clone());
+        Ok(())
+    }
+
+    fn register_existing_labeled_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        family: &Self::LabeledGauge,
+    ) -> Result<(), Self::Error> {
+        registry.register(name, help, family.clone());
+        Ok(())
+    }
+}
+
+/// Drop explicit `+Inf` bounds from a bucket list.
+///
+/// `prometheus-client` always appends the implicit `+Inf` bucket, so passing
+/// it through would render a second, malformed `le="inf"` bucket.
+fn finite_buckets(buckets: impl IntoIterator<Item = f64>) -> impl Iterator<Item = f64> {
+    buckets.into_iter().filter(|bound| *bound != f64::INFINITY)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -182,12 +481,58 @@ pub type PrometheusRegistry = ObservabilityRegistry<PrometheusBackend>;
 /// A Prometheus counter metric with metadata.
 pub type PrometheusCounter = Metric<Counter<u64>>;
 
+/// A Prometheus floating point counter metric with metadata.
+pub type PrometheusFloatCounter = Metric<Counter<f64, AtomicU64>>;
+
 /// A Prometheus gauge metric with metadata.
 pub type PrometheusGauge = Metric<Gauge<i64>>;
 
 /// A Prometheus histogram metric with metadata.
 pub type PrometheusHistogram = Metric<Histogram>;
 
+/// A Prometheus floating point gauge metric with metadata.
+pub type PrometheusFloatGauge = Metric<Gauge<f64, AtomicU64>>;
+
+impl Register<PrometheusBackend> for PrometheusCounter {
+    fn register(
+        registry: &mut PrometheusRegistry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self, DeserializeError> {
+        registry.counter(name, help)
+    }
+}
+
+impl Register<PrometheusBackend> for PrometheusFloatCounter {
+    fn register(
+        registry: &mut PrometheusRegistry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self, DeserializeError> {
+        registry.float_counter(name, help)
+    }
+}
+
+impl Register<PrometheusBackend> for PrometheusGauge {
+    fn register(
+        registry: &mut PrometheusRegistry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self, DeserializeError> {
+        registry.gauge(name, help)
+    }
+}
+
+impl Register<PrometheusBackend> for PrometheusHistogram {
+    fn register(
+        registry: &mut PrometheusRegistry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self, DeserializeError> {
+        registry.histogram(name, help)
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Helper functions for creating metrics
 // ═══════════════════════════════════════════════════════════════════════════
@@ -197,11 +542,27 @@ pub fn counter(name: impl Into<String>, description: impl Into<String>) -> Prome
     Metric::new(name, description, Counter::default())
 }
 
+/// Create a new Prometheus floating point counter.
+pub fn float_counter(
+    name: impl Into<String>,
+    description: impl Into<String>,
+) -> PrometheusFloatCounter {
+    Metric::new(name, description, Counter::default())
+}
+
 /// Create a new Prometheus gauge.
 pub fn gauge(name: impl Into<String>, description: impl Into<String>) -> PrometheusGauge {
     Metric::new(name, description, Gauge::default())
 }
 
+/// Create a new Prometheus floating point gauge.
+pub fn float_gauge(
+    name: impl Into<String>,
+    description: impl Into<String>,
+) -> PrometheusFloatGauge {
+    Metric::new(name, description, Gauge::default())
+}
+
 /// Default general-purpose histogram buckets.
 /// Exponential buckets covering a wide range: `[0.001, 0.01, 0.1, 1.0, 10.0, 100.0, 1000.0]`
 pub const DEFAULT_BUCKETS: [f64; 7] = [0.001, 0.01, 0.1, 1.0, 10.0, 100.0, 1000.0];
@@ -241,7 +602,7 @@ pub const DEFAULT_SIZE_BUCKETS: [f64; 10] = [
 /// metric.observe(0.5);
 /// ```
 pub fn histogram(name: impl Into<String>, description: impl Into<String>) -> PrometheusHistogram {
-    histogram_with_buckets(name, description, DEFAULT_BUCKETS.into_iter())
+    Metric::new(name, description, Histogram::new(DEFAULT_BUCKETS))
 }
 
 /// Create a new Prometheus histogram optimized for latency measurements.
@@ -258,25 +619,32 @@ pub fn histogram_for_latency(
     name: impl Into<String>,
     description: impl Into<String>,
 ) -> PrometheusHistogram {
-    histogram_with_buckets(name, description, DEFAULT_LATENCY_BUCKETS.into_iter())
+    Metric::new(name, description, Histogram::new(DEFAULT_LATENCY_BUCKETS))
 }
 
 /// Create a new Prometheus histogram with custom buckets.
 ///
+/// Buckets are checked with [`normalize_and_validate_buckets`] against
+/// [`DEFAULT_MAX_BUCKETS`], as registry histograms are. The `+Inf` bucket is
+/// always present in the output; an explicit final `f64::INFINITY` bound is
+/// accepted and not duplicated.
+///
 /// # Example
 /// ```ignore
 /// let latency = histogram_with_buckets(
 ///     "request_duration_seconds",
 ///     "Request latency",
 ///     [0.01, 0.05, 0.1, 0.5, 1.0, 5.0].into_iter(),
-/// );
+/// )?;
 /// ```
 pub fn histogram_with_buckets(
     name: impl Into<String>,
     description: impl Into<String>,
     buckets: impl Iterator<Item = f64>,
-) -> PrometheusHistogram {
-    Metric::new(name, description, Histogram::new(buckets))
+) -> Result<PrometheusHistogram, DeserializeError> {
+    let name = name.into();
+    let histogram = Histogram::with_buckets(&name, buckets.collect())?;
+    Ok(Metric::new(name, description, histogram))
 }
 
 /// Create a histogram suitable for measuring byte sizes.
@@ -286,7 +654,7 @@ pub fn histogram_for_bytes(
     name: impl Into<String>,
     description: impl Into<String>,
 ) -> PrometheusHistogram {
-    histogram_with_buckets(name, description, DEFAULT_SIZE_BUCKETS.into_iter())
+    Metric::new(name, description, Histogram::new(DEFAULT_SIZE_BUCKETS))
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -313,6 +681,9 @@ pub fn histogram_for_bytes(
 pub type LabeledHistogram<L> = Family<L, Histogram>;
 
 /// A labeled counter family type alias.
+///
+/// `get_or_create` with a label set that already has a series returns that
+/// series, so every handle to one label set counts into the same series.
 pub type LabeledCounter<L> = Family<L, Counter<u64>>;
 
 /// A labeled gauge family type alias.
@@ -398,6 +769,123 @@ where
     Family::default()
 }
 
+/// Label set given as `(name, value)` pairs, for labels only known at runtime.
+pub type DynamicLabels = Vec<(String, String)>;
+
+/// Creates each series of a [`HistogramFamily`] with the family's buckets.
+#[derive(Debug, Clone)]
+pub struct HistogramBuckets(Arc<[f64]>);
+
+impl prometheus_client::metrics::family::MetricConstructor<Histogram> for HistogramBuckets {
+    fn new_metric(&self) -> Histogram {
+        Histogram::new(self.0.iter().copied())
+    }
+}
+
+/// A histogram family with runtime label names and shared custom buckets.
+///
+/// This is what labeled histograms from config are registered as; see
+/// [`ObservabilityRegistry::labeled_histogram_with_buckets`].
+pub type HistogramFamily = Family<DynamicLabels, Histogram, HistogramBuckets>;
+
+impl LabeledHistogramTrait for HistogramFamily {
+    type Series = Histogram;
+
+    fn observe(&self, labels: &[(&str, &str)], value: f64) {
+        self.get_or_create(&dynamic_labels(labels)).observe(value);
+    }
+
+    fn series(&self, labels: &[(&str, &str)]) -> Histogram {
+        self.get_or_create(&dynamic_labels(labels)).clone()
+    }
+
+    fn contains(&self, labels: &[(&str, &str)]) -> bool {
+        self.get(&dynamic_labels(labels)).is_some()
+    }
+}
+
+/// A gauge family with runtime label names.
+///
+/// This is what labeled gauges from config are registered as; see
+/// [`ObservabilityRegistry::labeled_gauge`].
+pub type GaugeFamily = Family<DynamicLabels, Gauge>;
+
+impl LabeledGaugeTrait for GaugeFamily {
+    type Series = Gauge;
+
+    fn set(&self, labels: &[(&str, &str)], value: i64) {
+        self.get_or_create(&dynamic_labels(labels)).set(value);
+    }
+
+    fn inc(&self, labels: &[(&str, &str)]) {
+        self.get_or_create(&dynamic_labels(labels)).inc();
+    }
+
+    fn dec(&self, labels: &[(&str, &str)]) {
+        self.get_or_create(&dynamic_labels(labels)).dec();
+    }
+
+    fn get(&self, labels: &[(&str, &str)]) -> i64 {
+        self.get_or_create(&dynamic_labels(labels)).get()
+    }
+
+    fn series(&self, labels: &[(&str, &str)]) -> Gauge {
+        self.get_or_create(&dynamic_labels(labels)).clone()
+    }
+
+    fn contains(&self, labels: &[(&str, &str)]) -> bool {
+        self.get(&dynamic_labels(labels)).is_some()
+    }
+}
+
+/// Copy borrowed `(name, value)` pairs into an owned label set.
+fn dynamic_labels(labels: &[(&str, &str)]) -> DynamicLabels {
+    labels
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Pre-create series on a labeled family.
+///
+/// The first observation of a new label set allocates its series. Creating
+/// the expected series at startup keeps that cost off the hot path and makes
+/// them show up (at zero) before anything is recorded.
+///
+/// # Example
+/// ```ignore
+/// let workers = registry.labeled_gauge("workers", "Workers", vec!["pool".into()])?;
+/// workers.init_labels(&[&[("pool", "io")], &[("pool", "cpu")]])?;
+/// ```
+pub trait InitLabels {
+    /// Create the series for each label set without recording anything.
+    ///
+    /// Label sets name every label of the family, in any order. Fails before
+    /// creating any series if one doesn't, and like any new label set once
+    /// the registry is frozen.
+    fn init_labels(&self, label_sets: &[&[(&str, &str)]]) -> Result<(), MetricError>;
+}
+
+impl InitLabels for Labeled<GaugeFamily> {
+    fn init_labels(&self, label_sets: &[&[(&str, &str)]]) -> Result<(), MetricError> {
+        self.init_series(
+            label_sets,
+            |labels| self.inner().contains(labels),
+            |labels| drop(LabeledGaugeTrait::series(self.inner(), labels)),
+        )
+    }
+}
+
+impl InitLabels for Labeled<HistogramFamily> {
+    fn init_labels(&self, label_sets: &[&[(&str, &str)]]) -> Result<(), MetricError> {
+        self.init_series(
+            label_sets,
+            |labels| self.inner().contains(labels),
+            |labels| drop(LabeledHistogramTrait::series(self.inner(), labels)),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -429,57 +917,719 @@ mod tests {
     }
 
     #[test]
-    fn test_prometheus_histogram_default() {
-        let metric = histogram("general_metric", "A general purpose histogram");
+    fn test_prometheus_float_gauge_round_trips_exactly() {
+        let gauge = float_gauge("ratio", "A ratio");
+
+        let extremes = [
+            1e308,
+            -1e308,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::MIN_POSITIVE / 2.0, // subnormal
+            f64::from_bits(1),       // smallest subnormal
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            0.1 + 0.2,
+        ];
+        for value in extremes {
+            gauge.set_f64(value);
+            assert_eq!(gauge.get_gauge_f64().to_bits(), value.to_bits());
+        }
 
-        // Observe values across the default bucket range
-        metric.observe(0.005);
-        metric.observe(0.5);
-        metric.observe(50.0);
-        metric.observe(500.0);
+        gauge.set_f64(f64::NAN);
+        assert_eq!(gauge.get_gauge_f64().to_bits(), f64::NAN.to_bits());
 
-        assert_eq!(metric.name(), "general_metric");
-        assert_eq!(metric.description(), "A general purpose histogram");
+        gauge.set_f64(1.5);
+        gauge.inc_by_f64(0.25);
+        gauge.dec_by_f64(1.0);
+        assert_eq!(gauge.get_gauge_f64(), 0.75);
     }
 
     #[test]
-    fn test_prometheus_histogram_for_latency() {
-        let latency =
-            histogram_for_latency("request_duration_seconds", "Request latency in seconds");
+    fn test_tracking_gauge_renders_min_and_max() {
+        let mut registry = PrometheusRegistry::new();
+        let depth = registry
+            .tracking_gauge("queue_depth", "Queue depth")
+            .unwrap();
 
-        // Observe some values
-        latency.observe(0.001); // 1ms
-        latency.observe(0.042); // 42ms
-        latency.observe(0.5); // 500ms
-        latency.observe(2.0); // 2s
+        for value in [5, 2, 9] {
+            depth.set(value);
+        }
 
-        // Verify metadata
-        assert_eq!(latency.name(), "request_duration_seconds");
-        assert_eq!(latency.description(), "Request latency in seconds");
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("\nqueue_depth 9\n"));
+        assert!(text.contains("\nqueue_depth_min 2\n"));
+        assert!(text.contains("\nqueue_depth_max 9\n"));
+        assert!(text.contains("# HELP queue_depth_min Queue depth (minimum)."));
+
+        let long_name = "q".repeat(crate::core::deserialise::DEFAULT_MAX_NAME_LENGTH - 2);
+        assert!(registry.tracking_gauge(&long_name, "Too long").is_err());
+        assert!(!registry.contains(&long_name));
+
+        // A taken sibling name leaves the other two unregistered
+        registry.gauge("pool_size_max", "Taken").unwrap();
+        assert!(matches!(
+            registry.tracking_gauge("pool_size", "Pool size"),
+            Err(DeserializeError::DuplicateMetricName(name)) if name == "pool_size_max"
+        ));
+        assert!(!registry.contains("pool_size"));
+        assert!(!registry.contains("pool_size_min"));
+
+        registry.freeze();
+        assert!(matches!(
+            registry.tracking_gauge("conn_count", "Connections"),
+            Err(DeserializeError::RegistryFrozen(_))
+        ));
+        assert!(!registry.contains("conn_count_min"));
     }
 
     #[test]
-    fn test_prometheus_histogram_custom_buckets() {
-        let custom_buckets = [0.1, 0.5, 1.0, 5.0, 10.0];
-        let latency = histogram_with_buckets(
-            "custom_duration_seconds",
-            "Custom latency histogram",
-            custom_buckets.into_iter(),
-        );
+    fn test_prometheus_registry_series_count() {
+        use crate::core::collector::SeriesInfo;
 
-        latency.observe(0.25);
-        latency.observe(0.75);
-        latency.observe(3.0);
+        let mut registry = PrometheusRegistry::new();
+        assert_eq!(registry.series_count(), 0);
 
-        assert_eq!(latency.name(), "custom_duration_seconds");
+        let requests: LabeledCounter<DynamicLabels> = labeled_counter();
+        let family = requests.clone();
+        registry
+            .register_external("requests", move |inner| {
+                inner.register("requests", "Requests", family.clone())
+            })
+            .unwrap();
+        for route in ["/", "/users", "/orders", "/users"] {
+            requests
+                .get_or_create(&vec![("route".to_string(), route.to_string())])
+                .inc();
+        }
+        registry.counter("jobs", "Jobs").unwrap();
+        registry.gauge("depth", "Depth").unwrap();
+        assert_eq!(registry.series_count(), 5);
+
+        // Buckets (plus +Inf), _sum and _count
+        registry
+            .histogram_with_buckets("latency_seconds", "Latency", vec![0.1, 1.0])
+            .unwrap();
+        assert_eq!(registry.series_count(), 10);
+
+        // Counting doesn't render, so a one-shot update is still exported
+        let evicted = registry.gauge("cache_evicted", "Evicted").unwrap();
+        registry.set_one_shot("cache_evicted").unwrap();
+        evicted.set(3);
+        assert_eq!(registry.series_count(), 11);
+        assert!(registry
+            .render_text()
+            .unwrap()
+            .contains("cache_evicted 3\n"));
+
+        // Labeled gauge series and collector series count too
+        let in_flight = registry
+            .labeled_gauge("in_flight", "In flight", vec!["route".into()])
+            .unwrap();
+        in_flight.inc(&["/"]).unwrap();
+        registry.register_collector(|| {
+            vec![
+                SeriesInfo::gauge("pool_idle", "Idle connections", 2.0),
+                SeriesInfo::gauge("pool_busy", "Busy connections", 1.0),
+            ]
+        });
+        assert_eq!(registry.series_count(), 14);
     }
 
     #[test]
-    fn test_prometheus_histogram_for_bytes() {
-        let response_size =
-            histogram_for_bytes("http_response_size_bytes", "HTTP response size in bytes");
+    fn test_parse_exposition_round_trips_render_output() {
+        use crate::core::exposition::parse_exposition;
 
-        response_size.observe(512.0); // 512 bytes
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("jobs", "Jobs").unwrap().inc_by(42);
+        registry.counter("idle_total", "Never touched").unwrap();
+        registry
+            .gauge("temperature", "Temperature")
+            .unwrap()
+            .set(-7);
+        registry
+            .histogram_with_buckets("latency_seconds", "Latency", vec![0.1, 1.0])
+            .unwrap()
+            .observe(0.5);
+        registry
+            .labeled_gauge("pool_size", "Pool size", vec!["pool".into()])
+            .unwrap();
+
+        let text = String::from_utf8(registry.render().unwrap().into_bytes()).unwrap();
+        let parsed = parse_exposition(&text).unwrap();
+
+        let mut expected = registry.snapshot();
+        for metric in &mut expected.metrics {
+            metric.last_updated = None;
+        }
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_validate_output_catches_clashing_series() {
+        use crate::core::check_exposition;
+        use crate::core::collector::SeriesInfo;
+
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("jobs", "Jobs processed").unwrap().inc();
+        registry
+            .histogram("latency", "Latency")
+            .unwrap()
+            .observe(0.2);
+        registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .unwrap()
+            .set(&["io"], 2)
+            .unwrap();
+        assert_eq!(registry.validate_output(), Ok(()));
+
+        // The same counter as a 0.0.4 exporter names it, family and all
+        let classic = registry
+            .render_text()
+            .unwrap()
+            .replace("# HELP jobs ", "# HELP jobs_total ")
+            .replace("# TYPE jobs ", "# TYPE jobs_total ");
+        assert!(classic.contains("# TYPE jobs_total counter\njobs_total 1\n"));
+        assert_eq!(check_exposition(&classic), Ok(()));
+
+        // A collector reporting a family the registry already renders
+        registry.register_collector(|| vec![SeriesInfo::gauge("jobs", "Jobs", 1.0)]);
+        let err = registry.validate_output().unwrap_err();
+        assert!(
+            err.contains("family 'jobs' appears more than once"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_register_family_at_runtime() {
+        use crate::core::family::FamilyDescriptor;
+
+        let mut registry = PrometheusRegistry::new();
+        let events = registry
+            .register_family(FamilyDescriptor::Counter {
+                name: "plugin_events".into(),
+                description: "Plugin events".into(),
+                aliases: vec!["events".into()],
+            })
+            .unwrap();
+        events.as_counter().unwrap().inc_by(2);
+        assert!(events.as_gauge().is_none());
+
+        let workers = registry
+            .register_family(FamilyDescriptor::Gauge {
+                name: "plugin_workers".into(),
+                description: "Plugin workers".into(),
+                labels: vec!["pool".into()],
+                aliases: vec![],
+            })
+            .unwrap();
+        workers.as_labeled_gauge().unwrap().set(&["io"], 3).unwrap();
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("plugin_events_total 2\n"));
+        assert!(text.contains("events_total 2\n"));
+        assert!(text.contains("plugin_workers{pool=\"io\"} 3\n"));
+
+        // A taken alias fails before anything is registered
+        let err = registry
+            .register_family(FamilyDescriptor::Counter {
+                name: "plugin_errors".into(),
+                description: "Plugin errors".into(),
+                aliases: vec!["events".into()],
+            })
+            .err()
+            .unwrap();
+        assert!(matches!(err, DeserializeError::DuplicateMetricName(name) if name == "events"));
+        assert!(!registry.contains("plugin_errors"));
+    }
+
+    #[test]
+    fn test_lazy_metrics_render_only_once_used() {
+        use crate::core::lazy::LazyMetric;
+        use std::sync::RwLock;
+
+        let registry = Arc::new(RwLock::new(PrometheusRegistry::new()));
+        let retries = LazyMetric::counter(&registry, "retries", "Retried requests").unwrap();
+        let timeouts = LazyMetric::counter(&registry, "timeouts", "Timed out requests").unwrap();
+        let long_name = "r".repeat(crate::core::deserialise::DEFAULT_MAX_NAME_LENGTH + 1);
+        assert!(LazyMetric::counter(&registry, long_name, "Too long").is_err());
+
+        let output = registry.read().unwrap().render().unwrap();
+        assert!(!output.as_str().unwrap().contains("retries"));
+        assert_eq!(retries.get_counter(), 0);
+        assert!(!retries.is_registered());
+
+        retries.inc();
+        retries.inc_by(2);
+        let output = registry.read().unwrap().render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("retries_total 3\n"));
+        assert!(!text.contains("timeouts"));
+        assert!(!timeouts.is_registered());
+
+        // A second handle for the same name shares the registered counter
+        let again = LazyMetric::counter(&registry, "retries", "Retried requests").unwrap();
+        again.inc();
+        assert_eq!(retries.get_counter(), 4);
+        let output = registry.read().unwrap().render().unwrap();
+        assert_eq!(
+            output.as_str().unwrap().matches("# TYPE retries").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_current_value_reads_any_kind_as_f64() {
+        use crate::core::metrics::MetricKind;
+
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .counter("jobs", "Jobs processed")
+            .unwrap()
+            .inc_by(7);
+        registry.gauge("balance", "Balance").unwrap().set(-3);
+        let latency = registry.histogram("latency", "Latency").unwrap();
+        latency.observe(0.25);
+        latency.observe(0.5);
+
+        assert_eq!(
+            registry.current_value("jobs", MetricKind::Counter),
+            Some(7.0)
+        );
+        assert_eq!(
+            registry.current_value("balance", MetricKind::Gauge),
+            Some(-3.0)
+        );
+        assert_eq!(
+            registry.current_value("latency", MetricKind::Histogram),
+            Some(0.75)
+        );
+        // Looked up by kind as well as name
+        assert_eq!(registry.current_value("jobs", MetricKind::Gauge), None);
+        assert_eq!(registry.current_value("missing", MetricKind::Counter), None);
+    }
+
+    #[test]
+    fn test_sorted_labels_render_alphabetically() {
+        let labels = vec!["b".to_string(), "a".to_string(), "c".to_string()];
+        let mut registry = PrometheusRegistry::new();
+        let unsorted = registry
+            .labeled_gauge("workers", "Workers", labels.clone())
+            .unwrap();
+        unsorted.set(&["2", "1", "3"], 5).unwrap();
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("workers{b=\"2\",a=\"1\",c=\"3\"} 5\n"));
+
+        let mut registry = PrometheusRegistry::new().with_sorted_labels();
+        let sorted = registry
+            .labeled_gauge("workers", "Workers", labels)
+            .unwrap();
+        sorted.set(&["2", "1", "3"], 5).unwrap();
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("workers{a=\"1\",b=\"2\",c=\"3\"} 5\n"));
+    }
+
+    #[test]
+    fn test_collector_series_are_read_at_render() {
+        use crate::core::collector::SeriesInfo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("jobs", "Jobs processed").unwrap();
+        registry.register_collector({
+            let calls = Arc::clone(&calls);
+            move || {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                vec![
+                    SeriesInfo::gauge("open_files", "Open files", 17.0),
+                    SeriesInfo::counter("cache_evictions", "Cache evictions", call as f64)
+                        .with_label("cache", "users"),
+                ]
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("# TYPE open_files gauge\nopen_files 17\n"));
+        assert!(text.contains("cache_evictions_total{cache=\"users\"} 1\n"));
+        assert!(text.trim_end().ends_with("# EOF"));
+        assert!(text.contains("jobs_total 0"));
+
+        // Read again on the next render
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("cache_evictions_total{cache=\"users\"} 2\n"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_before_scrape_hooks_run_in_order_at_render() {
+        use std::sync::Mutex;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = PrometheusRegistry::new();
+        let queue_depth = registry.gauge("queue_depth", "Jobs waiting").unwrap();
+        registry.on_before_scrape({
+            let order = Arc::clone(&order);
+            move || {
+                order.lock().unwrap().push("poll");
+                queue_depth.gauge_inc_by(3);
+            }
+        });
+        registry.on_before_scrape({
+            let order = Arc::clone(&order);
+            move || order.lock().unwrap().push("log")
+        });
+        assert!(order.lock().unwrap().is_empty());
+
+        let output = registry.render().unwrap();
+        assert!(output.as_str().unwrap().contains("queue_depth 3\n"));
+        assert_eq!(*order.lock().unwrap(), ["poll", "log"]);
+
+        let output = registry.render().unwrap();
+        assert!(output.as_str().unwrap().contains("queue_depth 6\n"));
+        assert_eq!(*order.lock().unwrap(), ["poll", "log", "poll", "log"]);
+    }
+
+    #[test]
+    fn test_one_shot_gauge_is_rendered_once_per_update() {
+        let mut registry = PrometheusRegistry::new();
+        let evicted = registry
+            .gauge("cache_evicted", "Entries evicted in the last sweep")
+            .unwrap();
+        registry
+            .gauge("cache_size", "Entries cached")
+            .unwrap()
+            .set(40);
+        registry.set_one_shot("cache_evicted").unwrap();
+        assert!(registry.is_one_shot("cache_evicted"));
+        assert!(registry.set_one_shot("cache_missing").is_err());
+
+        let render = |registry: &PrometheusRegistry| {
+            String::from_utf8(registry.render().unwrap().into_bytes()).unwrap()
+        };
+        evicted.set(12);
+        // Only a scrape takes the update; other views leave it pending
+        assert!(registry
+            .render_text()
+            .unwrap()
+            .contains("cache_evicted 12\n"));
+        assert!(registry
+            .render_sorted()
+            .unwrap()
+            .contains("cache_evicted 12\n"));
+        assert!(registry.validate_output().is_ok());
+        assert_eq!(evicted.get_gauge(), 12);
+        let first = render(&registry);
+        assert!(first.contains("cache_evicted 12\n"));
+        let second = render(&registry);
+        assert!(!second
+            .lines()
+            .any(|line| line.starts_with("cache_evicted ")));
+        assert!(second.contains("# TYPE cache_evicted gauge\n"));
+        assert!(
+            second.contains("cache_size 40\n"),
+            "other gauges are unaffected"
+        );
+        assert_eq!(evicted.get_gauge(), 0);
+
+        // Exported again once re-set
+        evicted.gauge_inc_by(3);
+        assert!(render(&registry).contains("cache_evicted 3\n"));
+        assert!(!render(&registry).contains("cache_evicted 0"));
+    }
+
+    #[test]
+    fn test_frozen_registry_rejects_new_metrics_and_label_sets() {
+        use crate::core::deserialise::DeserializeError;
+        use crate::core::metrics::MetricError;
+
+        let mut registry = PrometheusRegistry::new();
+        let jobs = registry.counter("jobs", "Jobs processed").unwrap();
+        let in_flight = registry
+            .labeled_gauge("in_flight", "In-flight requests", vec!["route".into()])
+            .unwrap();
+        let latency = registry
+            .labeled_histogram("latency_seconds", "Latency", vec!["route".into()])
+            .unwrap();
+        in_flight.set(&["/users"], 1).unwrap();
+        latency.observe(&["/users"], 0.1).unwrap();
+
+        registry.freeze();
+        assert!(registry.is_frozen());
+
+        let error = registry.gauge("queue_depth", "Jobs waiting").unwrap_err();
+        assert!(
+            matches!(error, DeserializeError::RegistryFrozen(ref name) if name == "queue_depth")
+        );
+        assert!(registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .is_err());
+        assert!(!registry.contains("queue_depth"));
+
+        // Existing metrics and series keep updating
+        jobs.inc();
+        in_flight.inc(&["/users"]).unwrap();
+        latency.observe(&["/users"], 0.2).unwrap();
+        assert_eq!(in_flight.get(&["/users"]).unwrap(), 2);
+
+        assert_eq!(
+            in_flight.set(&["/admin"], 1),
+            Err(MetricError::RegistryFrozen("in_flight".into()))
+        );
+        assert!(latency.histogram_series(&["/admin"]).is_err());
+        let text = registry.render_text().unwrap();
+        assert!(!text.contains("/admin"));
+        assert!(text.contains("jobs_total 1\n"));
+    }
+
+    #[test]
+    fn test_handles_are_orphaned_when_their_registry_goes_away() {
+        use crate::core::metrics::MetricError;
+
+        let mut registry = PrometheusRegistry::new();
+        let requests = registry.counter("requests", "Requests").unwrap();
+        let depth = registry.gauge("queue_depth", "Jobs waiting").unwrap();
+        let latency = registry.histogram("latency_seconds", "Latency").unwrap();
+        let workers = registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .unwrap();
+        assert!(!requests.is_orphaned());
+        requests.live().unwrap().inc();
+        requests.try_inc_by(2).unwrap();
+        assert_eq!(requests.get_counter(), 3);
+
+        registry.retire();
+        let orphaned = |name: &str| Err(MetricError::OrphanedHandle(name.into()));
+        assert_eq!(requests.live().map(|_| ()), orphaned("requests"));
+        assert_eq!(requests.try_inc(), orphaned("requests"));
+        assert_eq!(depth.try_set(4), orphaned("queue_depth"));
+        assert_eq!(latency.try_observe(0.5), orphaned("latency_seconds"));
+        assert!(workers.is_orphaned());
+        assert_eq!(workers.set(&["io"], 2), orphaned("workers"));
+        assert!(matches!(
+            workers.gauge_series(&["io"]),
+            Err(MetricError::OrphanedHandle(_))
+        ));
+
+        // The infallible methods drop the update, logging a warning
+        requests.inc_by(5);
+        assert_eq!(requests.get_counter(), 3);
+        assert!(requests.last_updated().is_some());
+
+        let mut replacement = PrometheusRegistry::new();
+        let depth_again = replacement.gauge("queue_depth", "Jobs waiting").unwrap();
+        drop(registry);
+        assert!(depth.is_orphaned());
+        assert!(depth_again.live().is_ok());
+        assert!(!gauge("standalone", "Not in a registry").is_orphaned());
+    }
+
+    #[test]
+    fn test_smoothed_gauge_renders_converging_average() {
+        use crate::core::smoothed::SmoothedGauge;
+
+        let load = SmoothedGauge::new(float_gauge("load", "Smoothed load"), 0.5);
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .inner_mut()
+            .register("load", "Smoothed load", load.metric().inner().clone());
+
+        let rendered = |registry: &PrometheusRegistry| -> f64 {
+            let output = registry.render().unwrap();
+            let text = output.as_str().unwrap().to_string();
+            let line = text.lines().find(|line| line.starts_with("load ")).unwrap();
+            line["load ".len()..].parse().unwrap()
+        };
+
+        load.set(0.0);
+        let mut previous_gap = 100.0;
+        for _ in 0..8 {
+            load.set(100.0);
+            let gap = 100.0 - rendered(&registry);
+            assert!(gap > 0.0 && gap < previous_gap);
+            previous_gap = gap;
+        }
+        assert!(previous_gap < 1.0);
+        assert_eq!(load.raw(), Some(100.0));
+    }
+
+    #[test]
+    fn test_smoothed_gauge_renders_only_finite_samples() {
+        use crate::core::smoothed::SmoothedGauge;
+
+        let load = SmoothedGauge::new(float_gauge("load", "Smoothed load"), 0.5);
+        let mut registry = PrometheusRegistry::new();
+        let exported = load.metric().inner().clone();
+        registry
+            .register_external("load", move |inner| {
+                inner.register("load", "Smoothed load", exported.clone())
+            })
+            .unwrap();
+
+        load.set(10.0);
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            load.set(value);
+        }
+        load.set(20.0);
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("# TYPE load gauge\nload 15.0\n"), "{text}");
+        assert!(!text.contains("NaN") && !text.contains("Inf"));
+    }
+
+    #[test]
+    fn test_prometheus_histogram_default() {
+        let metric = histogram("general_metric", "A general purpose histogram");
+
+        // Observe values across the default bucket range
+        metric.observe(0.005);
+        metric.observe(0.5);
+        metric.observe(50.0);
+        metric.observe(500.0);
+
+        assert_eq!(metric.name(), "general_metric");
+        assert_eq!(metric.description(), "A general purpose histogram");
+    }
+
+    #[test]
+    fn test_prometheus_histogram_for_latency() {
+        let latency =
+            histogram_for_latency("request_duration_seconds", "Request latency in seconds");
+
+        // Observe some values
+        latency.observe(0.001); // 1ms
+        latency.observe(0.042); // 42ms
+        latency.observe(0.5); // 500ms
+        latency.observe(2.0); // 2s
+
+        // Verify metadata
+        assert_eq!(latency.name(), "request_duration_seconds");
+        assert_eq!(latency.description(), "Request latency in seconds");
+    }
+
+    #[test]
+    fn test_prometheus_histogram_observe_duration() {
+        let mut registry = PrometheusRegistry::new();
+        let latency = registry
+            .histogram_with_buckets(
+                "request_duration_seconds",
+                "Request latency",
+                DEFAULT_LATENCY_BUCKETS.to_vec(),
+            )
+            .unwrap();
+
+        latency.observe_duration(std::time::Duration::from_millis(250));
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("request_duration_seconds_sum 0.25"));
+        assert!(text.contains("request_duration_seconds_bucket{le=\"0.1\"} 0"));
+        assert!(text.contains("request_duration_seconds_bucket{le=\"0.25\"} 1"));
+    }
+
+    #[test]
+    fn test_prometheus_histogram_reads_back_bucket_counts() {
+        let latency =
+            histogram_with_buckets("latency_seconds", "Latency", [0.1, 0.5, 1.0].into_iter())
+                .unwrap();
+        assert_eq!(latency.count(), 0);
+
+        for value in [0.05, 0.3, 0.4, 2.0] {
+            latency.observe(value);
+        }
+
+        assert_eq!(
+            latency.bucket_counts(),
+            vec![(0.1, 1), (0.5, 3), (1.0, 3), (f64::INFINITY, 4)]
+        );
+        assert_eq!(latency.count(), 4);
+        assert!((latency.sum() - 2.75).abs() < 1e-9);
+
+        // Values past every bound are counted in +Inf, so it always matches the count
+        latency.inner().observe(f64::INFINITY);
+        assert_eq!(latency.bucket_counts().last(), Some(&(f64::INFINITY, 5)));
+        assert_eq!(latency.count(), 5);
+    }
+
+    #[test]
+    fn test_prometheus_histogram_observe_weighted() {
+        let latency =
+            histogram_with_buckets("latency_seconds", "Latency", [0.1, 1.0].into_iter()).unwrap();
+
+        latency.observe_weighted(0.5, 3);
+        latency.observe_weighted(0.05, 0);
+
+        assert_eq!(
+            latency.bucket_counts(),
+            vec![(0.1, 0), (1.0, 3), (f64::INFINITY, 3)]
+        );
+        assert_eq!(latency.sum(), 1.5);
+        assert_eq!(latency.count(), 3);
+
+        // Added in one step, so a huge count returns straight away
+        latency.observe_weighted(2.0, u64::MAX / 2);
+        assert_eq!(latency.count(), 3 + u64::MAX / 2);
+        assert_eq!(latency.bucket_counts()[1], (1.0, 3));
+    }
+
+    #[test]
+    fn test_prometheus_histogram_observe_iter_skips_non_finite() {
+        let latency =
+            histogram_with_buckets("latency_seconds", "Latency", [0.1, 1.0].into_iter()).unwrap();
+
+        let counts = latency.observe_iter([0.05, f64::NAN, 0.5, f64::INFINITY, 2.0]);
+
+        assert_eq!(
+            counts,
+            crate::core::metrics::ObserveCounts {
+                recorded: 3,
+                skipped: 2
+            }
+        );
+        assert_eq!(latency.count(), 3);
+        assert!((latency.sum() - 2.55).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_prometheus_histogram_custom_buckets() {
+        let custom_buckets = [0.1, 0.5, 1.0, 5.0, 10.0];
+        let latency = histogram_with_buckets(
+            "custom_duration_seconds",
+            "Custom latency histogram",
+            custom_buckets.into_iter(),
+        )
+        .unwrap();
+
+        latency.observe(0.25);
+        latency.observe(0.75);
+        latency.observe(3.0);
+
+        assert_eq!(latency.name(), "custom_duration_seconds");
+    }
+
+    #[test]
+    fn test_prometheus_histogram_for_bytes() {
+        let response_size =
+            histogram_for_bytes("http_response_size_bytes", "HTTP response size in bytes");
+
+        response_size.observe(512.0); // 512 bytes
         response_size.observe(15_000.0); // 15 KB
         response_size.observe(5_000_000.0); // 5 MB
 
@@ -540,6 +1690,785 @@ mod tests {
         assert!(output.content_type.contains("text/plain"));
     }
 
+    #[test]
+    fn test_content_types_match_spec() {
+        use crate::core::registry::{openmetrics_content_type, prometheus_content_type};
+
+        assert_eq!(
+            prometheus_content_type(),
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        assert_eq!(
+            openmetrics_content_type(),
+            "application/openmetrics-text; version=1.0.0; charset=utf-8"
+        );
+
+        let registry = PrometheusRegistry::new();
+        assert_eq!(
+            registry.render().unwrap().content_type,
+            prometheus_content_type()
+        );
+    }
+
+    #[test]
+    fn test_prometheus_registry_rejects_overlong_names() {
+        use crate::core::deserialise::DeserializeError;
+
+        let mut registry = PrometheusRegistry::new();
+
+        let result = registry.counter("a".repeat(1000), "Way too long");
+        assert!(matches!(
+            result,
+            Err(DeserializeError::InvalidMetricName { .. })
+        ));
+
+        // Nothing was registered for the rejected name
+        let output = registry.render().unwrap();
+        assert!(!output.as_str().unwrap().contains("aaaa"));
+
+        let mut registry = PrometheusRegistry::new().with_max_name_length(10);
+        assert!(registry.gauge("short", "Fits").is_ok());
+        assert!(registry.gauge("much_too_long", "Does not fit").is_err());
+
+        // Characters outside the Prometheus set are refused as well
+        assert!(matches!(
+            registry.gauge("queue.depth", "Dotted"),
+            Err(DeserializeError::InvalidMetricName { .. })
+        ));
+
+        // Registration errors still propagate as the backend's error type
+        fn register(registry: &mut PrometheusRegistry) -> Result<(), PrometheusError> {
+            registry.counter("2xx_total", "Bad start")?;
+            Ok(())
+        }
+        assert!(matches!(
+            register(&mut registry),
+            Err(PrometheusError::InvalidDefinition(
+                DeserializeError::InvalidMetricName { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_registry_set_description() {
+        use crate::core::deserialise::DeserializeError;
+        use crate::core::metrics::MetricKind;
+
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("requests_total", "").unwrap();
+        registry.gauge("queue_depth", "Queue depth").unwrap();
+
+        registry
+            .set_description("requests_total", MetricKind::Counter, "Requests served")
+            .unwrap();
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("# HELP requests_total Requests served"));
+        assert!(text.contains("# HELP queue_depth Queue depth"));
+
+        // Float counters and labeled families are covered too
+        registry.float_counter("cpu_seconds_total", "").unwrap();
+        registry
+            .labeled_gauge("pool_size", "", vec!["pool".into()])
+            .unwrap();
+        registry
+            .labeled_histogram("job_seconds", "", vec!["queue".into()])
+            .unwrap();
+        for (name, kind) in [
+            ("cpu_seconds_total", MetricKind::Counter),
+            ("pool_size", MetricKind::Gauge),
+            ("job_seconds", MetricKind::Histogram),
+        ] {
+            registry.set_description(name, kind, "Updated").unwrap();
+        }
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("# HELP cpu_seconds_total Updated"));
+        assert!(text.contains("# HELP pool_size Updated"));
+        assert!(text.contains("# HELP job_seconds Updated"));
+        assert_eq!(
+            registry
+                .find_labeled_gauge("pool_size")
+                .unwrap()
+                .description(),
+            "Updated"
+        );
+
+        // Unknown names and mismatched kinds are both reported as missing
+        assert!(matches!(
+            registry.set_description("missing", MetricKind::Counter, "Nope"),
+            Err(DeserializeError::MetricNotFound { .. })
+        ));
+        assert!(matches!(
+            registry.set_description("queue_depth", MetricKind::Counter, "Nope"),
+            Err(DeserializeError::MetricNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_registry_with_capacity() {
+        let mut registry = PrometheusRegistry::with_capacity(100, 1, 1);
+        for i in 0..100 {
+            registry
+                .counter(format!("counter_{i}_total"), "A counter")
+                .unwrap();
+        }
+        registry.gauge("depth", "Depth").unwrap();
+        registry.histogram("latency_seconds", "Latency").unwrap();
+
+        assert_eq!(registry.metric_names().count(), 102);
+        assert!(registry.find_counter("counter_99_total").is_some());
+        assert_eq!(registry.max_name_length(), 200);
+    }
+
+    #[test]
+    fn test_prometheus_registry_escapes_help_text() {
+        use crate::core::metrics::MetricKind;
+
+        let mut registry = PrometheusRegistry::new();
+        let counter = registry
+            .counter("requests_total", "Served requests\nfrom C:\\app")
+            .unwrap();
+        assert_eq!(counter.description(), "Served requests\nfrom C:\\app");
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("# HELP requests_total Served requests\\nfrom C:\\\\app.\n"));
+        assert!(text.lines().all(|line| !line.starts_with("from")));
+
+        registry
+            .set_description("requests_total", MetricKind::Counter, "Now\ntwo lines")
+            .unwrap();
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("# HELP requests_total Now\\ntwo lines.\n"));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_one() {
+        use crate::core::deserialise::DeserializeError;
+
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .counter("requests_total", "Requests served")
+            .unwrap()
+            .inc_by(3);
+        registry.gauge("queue_depth", "Queue depth").unwrap().set(7);
+        registry
+            .histogram("request_duration_seconds", "Request latency")
+            .unwrap();
+
+        let text = registry.render_one("requests_total").unwrap();
+        assert!(text.contains("# HELP requests_total Requests served"));
+        assert!(text.contains("# TYPE requests_total counter"));
+        assert!(text.contains("requests_total_total 3"));
+        assert!(!text.contains("queue_depth"));
+        assert!(!text.contains("request_duration_seconds"));
+
+        assert!(matches!(
+            registry.render_one("missing"),
+            Err(DeserializeError::MetricNotFound { kind: None, .. })
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_openmetrics_created() {
+        let before = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("requests", "Requests").unwrap().inc();
+        registry.gauge("queue_depth", "Queue depth").unwrap();
+        registry
+            .histogram_with_buckets("latency_seconds", "Latency", vec![1.0])
+            .unwrap();
+
+        let classic = registry.render().unwrap();
+        assert!(!classic.as_str().unwrap().contains("_created"));
+
+        let text = registry.render_openmetrics().unwrap();
+        let created: f64 = text
+            .lines()
+            .find_map(|line| line.strip_prefix("requests_created "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(created >= before - 1.0 && created <= before + 60.0);
+        assert!(text.contains("latency_seconds_created "));
+        assert!(!text.contains("queue_depth_created"));
+        assert!(text.ends_with("# EOF\n"));
+
+        // `_created` closes its family block
+        let lines: Vec<&str> = text.lines().collect();
+        let index = lines
+            .iter()
+            .position(|line| line.starts_with("requests_created"))
+            .unwrap();
+        assert_eq!(lines[index - 1], "requests_total 1");
+    }
+
+    #[test]
+    fn test_prometheus_registry_remove_metrics_keeps_the_rest() {
+        let mut registry = PrometheusRegistry::new();
+        let kept = registry.counter("kept", "Kept").unwrap();
+        registry
+            .gauge("legacy_depth", "Legacy depth")
+            .unwrap()
+            .set(3);
+        registry.add_alias("legacy_depth", "old_depth").unwrap();
+        kept.inc();
+
+        registry
+            .remove_metrics(["legacy_depth", "unknown"])
+            .unwrap();
+        kept.inc();
+
+        assert!(!registry.contains("legacy_depth"));
+        assert!(!registry.contains("old_depth"));
+        let output = registry.render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(output.contains("kept_total 2"));
+        assert!(!output.contains("depth"));
+    }
+
+    #[test]
+    fn test_register_external_checks_names_and_survives_rebuilds() {
+        use crate::core::deserialise::DeserializeError;
+
+        let mut registry = PrometheusRegistry::new();
+        registry.gauge("legacy_depth", "Legacy depth").unwrap();
+        let jobs: LabeledCounter<DynamicLabels> = labeled_counter();
+        let family = jobs.clone();
+        registry
+            .register_external("jobs", move |inner| {
+                inner.register("jobs", "Jobs by queue", family.clone())
+            })
+            .unwrap();
+        jobs.get_or_create(&vec![("queue".to_string(), "io".to_string())])
+            .inc();
+        assert!(registry.contains("jobs"));
+
+        let err = registry
+            .register_external("legacy_depth", |_| unreachable!())
+            .unwrap_err();
+        assert!(matches!(err, DeserializeError::DuplicateMetricName(_)));
+
+        registry.remove_metrics(["legacy_depth"]).unwrap();
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("jobs_total{queue=\"io\"} 1"));
+
+        registry.freeze();
+        let err = registry
+            .register_external("late", |_| unreachable!())
+            .unwrap_err();
+        assert!(matches!(err, DeserializeError::RegistryFrozen(_)));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_with_prefix() {
+        use crate::core::deserialise::DeserializeError;
+
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .counter("requests_total", "Requests")
+            .unwrap()
+            .inc();
+        registry
+            .histogram_with_buckets("latency_seconds", "Latency", vec![0.5])
+            .unwrap()
+            .observe(0.1);
+
+        let edge = registry.render_with_prefix("edge").unwrap();
+        let core = registry.render_with_prefix("core").unwrap();
+
+        assert!(edge.contains("# HELP edge_requests_total Requests."));
+        assert!(edge.contains("# TYPE edge_requests_total counter"));
+        assert!(edge.contains("edge_requests_total_total 1"));
+        assert!(edge.contains("edge_latency_seconds_bucket{le=\"0.5\"} 1"));
+        assert!(edge.ends_with("# EOF\n"));
+        assert_eq!(edge.replace("edge_", "core_"), core);
+
+        // The registry itself is untouched
+        assert!(registry.find_counter("requests_total").is_some());
+        let plain = String::from_utf8(registry.render().unwrap().into_bytes()).unwrap();
+        assert_eq!(registry.render_with_prefix("").unwrap(), plain);
+
+        let mut long_names = PrometheusRegistry::new().with_max_name_length(20);
+        long_names.counter("requests_total", "Requests").unwrap();
+        assert!(matches!(
+            long_names.render_with_prefix("federated"),
+            Err(DeserializeError::InvalidMetricName { .. })
+        ));
+    }
+
+    #[cfg(feature = "graphite")]
+    #[test]
+    fn test_prometheus_registry_render_graphite() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .counter("cache_hits", "Cache hits")
+            .unwrap()
+            .inc_by(7);
+
+        let ts = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let lines = registry.render_graphite("app", ts).unwrap();
+
+        assert!(lines
+            .lines()
+            .any(|line| line == "app.cache.hits.total 7 1700000000"));
+        assert!(lines.lines().all(|line| !line.starts_with('#')));
+    }
+
+    #[cfg(feature = "redaction")]
+    #[test]
+    fn test_prometheus_registry_redacts_matching_label_values() {
+        use crate::core::deserialise::DeserializeError;
+
+        let mut registry = PrometheusRegistry::new()
+            .with_label_redaction(r"^[^@\s]+@[^@\s]+\.[a-z]+$")
+            .unwrap();
+        let logins = registry
+            .labeled_histogram_with_buckets(
+                "login_seconds",
+                "Login duration",
+                vec!["user".into()],
+                vec![1.0],
+            )
+            .unwrap();
+        logins.observe(&["ann@example.com"], 0.5).unwrap();
+        logins.observe(&["service-account"], 0.5).unwrap();
+
+        let output = registry.render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(!output.contains("ann@example.com"));
+        assert!(output.contains("login_seconds_count{user=\"<redacted>\"} 1"));
+        assert!(output.contains("login_seconds_count{user=\"service-account\"} 1"));
+
+        assert!(matches!(
+            PrometheusRegistry::new().with_label_redaction("(unclosed"),
+            Err(DeserializeError::InvalidPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_registry_snapshot_tracks_last_updated() {
+        use crate::core::snapshot::SnapshotValue;
+        use std::time::{Duration, SystemTime};
+
+        let mut registry = PrometheusRegistry::new();
+        let requests = registry.counter("requests_total", "Requests").unwrap();
+        registry.gauge("idle_depth", "Never touched").unwrap();
+        let latency = registry.histogram("latency_seconds", "Latency").unwrap();
+
+        requests.inc_by(2);
+        latency.observe(0.2);
+        let snapshot = registry.snapshot();
+
+        let names: Vec<_> = snapshot.metrics.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["idle_depth", "latency_seconds", "requests_total"]);
+
+        let requests = snapshot.get("requests_total").unwrap();
+        assert_eq!(requests.value, SnapshotValue::Counter(2));
+        let age = SystemTime::now()
+            .duration_since(requests.last_updated.unwrap())
+            .unwrap();
+        assert!(age < Duration::from_secs(5));
+
+        assert!(snapshot
+            .get("latency_seconds")
+            .unwrap()
+            .last_updated
+            .is_some());
+
+        let idle = snapshot.get("idle_depth").unwrap();
+        assert_eq!(idle.value, SnapshotValue::Gauge(0));
+        assert_eq!(idle.last_updated, None);
+    }
+
+    #[test]
+    fn test_snapshot_includes_every_kind() {
+        use crate::core::metrics::MetricKind;
+        use crate::core::snapshot::SnapshotValue;
+
+        let mut registry = PrometheusRegistry::new();
+        registry.counter("jobs", "Jobs").unwrap().inc();
+        registry
+            .float_counter("cpu_seconds", "CPU time")
+            .unwrap()
+            .try_inc_by_f64(1.5)
+            .unwrap();
+        let workers = registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .unwrap();
+        workers.set(&["io"], 4).unwrap();
+        workers.set(&["cpu"], 2).unwrap();
+        let latency = registry
+            .labeled_histogram("latency_seconds", "Latency", vec!["route".into()])
+            .unwrap();
+        latency.observe(&["/users"], 0.2).unwrap();
+
+        let snapshot = registry.snapshot();
+        let names: Vec<_> = snapshot.metrics.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "cpu_seconds",
+                "jobs",
+                "latency_seconds",
+                "workers",
+                "workers"
+            ]
+        );
+        assert_eq!(
+            snapshot.get("cpu_seconds").unwrap().value,
+            SnapshotValue::Float(1.5)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("workers", &[("pool", "io")])
+                .unwrap()
+                .value,
+            SnapshotValue::Gauge(4)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("workers", &[("pool", "cpu")])
+                .unwrap()
+                .value,
+            SnapshotValue::Gauge(2)
+        );
+        let users = snapshot
+            .get_series("latency_seconds", &[("route", "/users")])
+            .unwrap();
+        assert_eq!(users.kind, MetricKind::Histogram);
+        assert_eq!(users.last_updated, None);
+    }
+
+    #[test]
+    fn test_last_updated_is_read_when_first_noticed() {
+        use crate::core::clock::MockClock;
+        use std::sync::Arc;
+        use std::time::{Duration, SystemTime};
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = MockClock::new(start);
+        let mut registry = PrometheusRegistry::new().with_clock(Arc::new(clock.clone()));
+        let requests = registry.counter("requests_total", "Requests").unwrap();
+
+        requests.inc();
+        clock.advance(Duration::from_secs(5));
+        let noticed = start + Duration::from_secs(5);
+        assert_eq!(
+            registry
+                .snapshot()
+                .get("requests_total")
+                .unwrap()
+                .last_updated,
+            Some(noticed)
+        );
+
+        // Once read, the time stays put until the next update
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(requests.last_updated(), Some(noticed));
+        requests.inc();
+        assert_eq!(
+            requests.last_updated(),
+            Some(start + Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn test_consistent_snapshot_sees_update_groups_whole() {
+        use crate::core::snapshot::SnapshotValue;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut registry = PrometheusRegistry::new();
+        let sent = registry.counter("messages_sent", "Messages sent").unwrap();
+        let acked = registry
+            .counter("messages_acked", "Messages acked")
+            .unwrap();
+        let gate = registry.snapshot_gate();
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        gate.update(|| {
+                            sent.inc();
+                            acked.inc();
+                        });
+                    }
+                });
+            }
+
+            for _ in 0..200 {
+                let snapshot = registry.consistent_snapshot();
+                let value = |name| snapshot.get(name).unwrap().value;
+                assert_eq!(value("messages_sent"), value("messages_acked"));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        let final_count = SnapshotValue::Counter(sent.get_counter());
+        assert_eq!(
+            registry
+                .consistent_snapshot()
+                .get("messages_acked")
+                .unwrap()
+                .value,
+            final_count
+        );
+    }
+
+    #[test]
+    fn test_consistent_snapshot_holds_off_every_handle_update() {
+        use crate::core::snapshot::SnapshotValue;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let mut registry = PrometheusRegistry::new();
+        let sent = registry.counter("messages_sent", "Messages sent").unwrap();
+        let queued = registry
+            .labeled_gauge("queued", "Queued messages", vec!["queue".into()])
+            .unwrap();
+        let gate = registry.snapshot_gate();
+
+        let reading = gate.exclusive();
+        let (done_tx, done) = mpsc::channel();
+        let writer = std::thread::spawn({
+            let (sent, queued) = (sent.clone(), queued.clone());
+            move || {
+                sent.inc();
+                queued.set(&["emails"], 3).unwrap();
+                done_tx.send(()).unwrap();
+            }
+        });
+
+        // Plain updates wait for the snapshot to finish
+        assert!(done.recv_timeout(Duration::from_millis(50)).is_err());
+        assert_eq!(sent.get_counter(), 0);
+        drop(reading);
+        writer.join().unwrap();
+        assert_eq!(sent.get_counter(), 1);
+
+        // Handle updates inside a group don't take the gate again
+        gate.update(|| {
+            sent.inc();
+            queued.inc(&["emails"]).unwrap();
+        });
+        let snapshot = registry.consistent_snapshot();
+        assert_eq!(
+            snapshot.get("messages_sent").unwrap().value,
+            SnapshotValue::Counter(2)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("queued", &[("queue", "emails")])
+                .unwrap()
+                .value,
+            SnapshotValue::Gauge(4)
+        );
+    }
+
+    #[test]
+    fn test_prometheus_registry_write_to_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "observability-kit-write-to-file-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.prom");
+
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .counter("requests_total", "Requests")
+            .unwrap()
+            .inc();
+        registry.write_to_file(&path).unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        assert_eq!(written, registry.render().unwrap().into_bytes());
+
+        // Writers on several threads don't trip over each other's temp files
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| registry.write_to_file(&path).unwrap());
+            }
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), written);
+
+        // Only the final file is left behind
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prometheus_registry_mock_clock_expires_metrics() {
+        use crate::core::clock::MockClock;
+        use std::sync::Arc;
+        use std::time::{Duration, SystemTime};
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = MockClock::new(start);
+        let mut registry = PrometheusRegistry::new().with_clock(Arc::new(clock.clone()));
+        let depth = registry.gauge("queue_depth", "Queue depth").unwrap();
+        let requests = registry.counter("requests_total", "Requests").unwrap();
+        let ttl = Duration::from_secs(300);
+
+        clock.advance(Duration::from_secs(200));
+        depth.set(3);
+        assert!(registry.stale_metrics(ttl).is_empty());
+
+        // Past the TTL for the untouched counter, not yet for the gauge
+        clock.advance(Duration::from_secs(150));
+        assert_eq!(registry.stale_metrics(ttl), vec!["requests_total"]);
+
+        clock.advance(Duration::from_secs(200));
+        requests.inc();
+        assert_eq!(registry.stale_metrics(ttl), vec!["queue_depth"]);
+
+        assert_eq!(requests.created(), start);
+        assert_eq!(depth.last_updated(), Some(start + Duration::from_secs(200)));
+        let output = registry.render_openmetrics().unwrap();
+        assert!(output.contains("requests_total_created 1700000000\n"));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_delta() {
+        let mut registry = PrometheusRegistry::new();
+        let requests = registry.counter("requests_total", "Requests").unwrap();
+        let depth = registry.gauge("queue_depth", "Queue depth").unwrap();
+        let latency = registry
+            .histogram_with_buckets("latency_seconds", "Latency", vec![0.5])
+            .unwrap();
+
+        let first = registry.render_delta().unwrap();
+        assert!(first.contains("requests_total_total 0"));
+        assert!(first.contains("queue_depth 0"));
+        assert!(first.contains("latency_seconds_count 0"));
+
+        depth.set(4);
+        let delta = registry.render_delta().unwrap();
+        assert_eq!(
+            delta,
+            "# HELP queue_depth Queue depth.\n# TYPE queue_depth gauge\nqueue_depth 4\n# EOF\n"
+        );
+
+        // Nothing changed since the last delta
+        assert_eq!(registry.render_delta().unwrap(), "# EOF\n");
+
+        requests.inc();
+        latency.observe(0.9);
+        let delta = registry.render_delta().unwrap();
+        assert!(delta.contains("requests_total_total 1"));
+        assert!(!delta.contains("queue_depth"));
+        // Unchanged buckets of a changed histogram are still emitted
+        assert!(delta.contains("latency_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(delta.contains("latency_seconds_bucket{le=\"0.5\"} 0"));
+        assert!(delta.contains("latency_seconds_sum 0.9"));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_sorted() {
+        let mut registry = PrometheusRegistry::new();
+        registry.gauge("zeta_depth", "Z").unwrap();
+        registry.counter("alpha_total", "A").unwrap();
+        registry.histogram("mid_seconds", "M").unwrap();
+
+        let text = registry.render_sorted().unwrap();
+        let alpha = text.find("# HELP alpha_total").unwrap();
+        let mid = text.find("# HELP mid_seconds").unwrap();
+        let zeta = text.find("# HELP zeta_depth").unwrap();
+
+        assert!(alpha < mid && mid < zeta);
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_prometheus_registry_render_with_timeout() {
+        use crate::core::renderer::RenderError;
+        use prometheus_client::encoding::{EncodeMetric, MetricEncoder};
+        use prometheus_client::metrics::MetricType;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        /// A gauge whose value takes a while to compute.
+        #[derive(Debug)]
+        struct SlowGauge;
+
+        impl EncodeMetric for SlowGauge {
+            fn encode(&self, mut encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+                std::thread::sleep(Duration::from_millis(300));
+                encoder.encode_gauge(&1i64)
+            }
+
+            fn metric_type(&self) -> MetricType {
+                MetricType::Gauge
+            }
+        }
+
+        let mut fast = PrometheusRegistry::new();
+        fast.counter("requests_total", "Requests").unwrap().inc();
+        let fast = Arc::new(fast);
+        let output = fast.render_with_timeout(Duration::from_secs(5)).unwrap();
+        assert!(output.as_str().unwrap().contains("requests_total_total 1"));
+
+        let mut slow = PrometheusRegistry::new();
+        slow.inner_mut()
+            .register("slow_value", "Slow to compute", SlowGauge);
+        let slow = Arc::new(slow);
+        assert!(matches!(
+            slow.render_with_timeout(Duration::from_millis(20)),
+            Err(RenderError::Timeout(timeout)) if timeout == Duration::from_millis(20)
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_histogram_always_has_single_inf_bucket() {
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .histogram_with_buckets("implicit_seconds", "Implicit +Inf", vec![0.1, 1.0])
+            .unwrap()
+            .observe(0.5);
+        registry
+            .histogram_with_buckets(
+                "explicit_seconds",
+                "Explicit +Inf",
+                vec![0.1, 1.0, f64::INFINITY],
+            )
+            .unwrap()
+            .observe(5.0);
+        registry
+            .histogram_with_buckets(
+                "repeated_seconds",
+                "Repeated +Inf",
+                vec![0.1, f64::INFINITY, f64::INFINITY],
+            )
+            .unwrap()
+            .observe(5.0);
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+
+        assert!(text.contains("implicit_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(text.contains("explicit_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(text.contains("repeated_seconds_bucket{le=\"+Inf\"} 1"));
+        assert_eq!(text.matches("le=\"+Inf\"").count(), 3);
+        assert!(!text.contains("le=\"inf\""));
+    }
+
     #[test]
     fn test_labeled_histogram_for_latency() {
         use std::hash::Hash;
@@ -625,6 +2554,223 @@ mod tests {
         assert_eq!(post_count, 5);
     }
 
+    #[test]
+    fn test_same_label_set_returns_the_existing_series() {
+        let mut registry = PrometheusRegistry::new();
+        let workers = registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .unwrap();
+        let latency = registry
+            .labeled_histogram_with_buckets(
+                "latency_seconds",
+                "Latency",
+                vec!["route".into()],
+                vec![1.0],
+            )
+            .unwrap();
+
+        let first = workers.gauge_series(&["db"]).unwrap();
+        let second = workers.gauge_series(&["db"]).unwrap();
+        first.inc();
+        second.inc();
+        assert_eq!(first.get(), 2);
+        assert_eq!(workers.get(&["db"]).unwrap(), 2);
+        assert!(workers.gauge_series(&[]).is_err());
+
+        latency.histogram_series(&["/users"]).unwrap().observe(0.5);
+        latency.histogram_series(&["/users"]).unwrap().observe(0.5);
+
+        let requests: LabeledCounter<DynamicLabels> = labeled_counter();
+        let labels = vec![("method".to_string(), "GET".to_string())];
+        let counter = requests.get_or_create(&labels).clone();
+        requests.get_or_create(&labels).inc();
+        counter.inc();
+        assert_eq!(counter.get(), 2);
+
+        let output = registry.render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(output.contains("latency_seconds_count{route=\"/users\"} 2"));
+        assert_eq!(output.matches("workers{pool=\"db\"} 2").count(), 1);
+    }
+
+    #[test]
+    fn test_init_labels_renders_zero_series() {
+        let mut registry = PrometheusRegistry::new();
+        let workers = registry
+            .labeled_gauge("workers", "Workers", vec!["pool".into(), "zone".into()])
+            .unwrap();
+        let latency = registry
+            .labeled_histogram_with_buckets(
+                "latency_seconds",
+                "Latency",
+                vec!["route".into()],
+                vec![0.5],
+            )
+            .unwrap();
+
+        workers
+            .init_labels(&[
+                &[("pool", "io"), ("zone", "a")],
+                &[("zone", "b"), ("pool", "cpu")],
+            ])
+            .unwrap();
+        latency.init_labels(&[&[("route", "/users")]]).unwrap();
+        assert_eq!(workers.series_count(), 2);
+        assert_eq!(latency.series_count(), 1);
+
+        let err = workers
+            .init_labels(&[&[("pool", "db"), ("zone", "a")], &[("pool", "db")]])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MetricError::LabelCount {
+                expected: 2,
+                actual: 1
+            }
+        );
+        let err = workers
+            .init_labels(&[&[("pool", "db"), ("region", "eu")]])
+            .unwrap_err();
+        assert_eq!(err, MetricError::MissingLabel("zone".into()));
+
+        registry.freeze();
+        assert_eq!(
+            workers.init_labels(&[&[("pool", "db"), ("zone", "a")]]),
+            Err(MetricError::RegistryFrozen("workers".into()))
+        );
+        assert_eq!(workers.series_count(), 2);
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("workers{pool=\"io\",zone=\"a\"} 0"));
+        assert!(text.contains("workers{pool=\"cpu\",zone=\"b\"} 0"));
+        assert!(text.contains("latency_seconds_count{route=\"/users\"} 0"));
+        assert!(!text.contains("db"));
+    }
+
+    #[test]
+    fn test_labeled_counter_series_share_one_family_header() {
+        let requests: LabeledCounter<DynamicLabels> = labeled_counter();
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .inner_mut()
+            .register("http_requests", "HTTP requests", requests.clone());
+        registry.gauge("queue_depth", "Queue depth").unwrap();
+
+        for method in ["GET", "POST", "DELETE"] {
+            requests
+                .get_or_create(&vec![("method".to_string(), method.to_string())])
+                .inc();
+        }
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        let lines: Vec<&str> = text
+            .lines()
+            .skip_while(|line| !line.contains("http_requests"))
+            .take(5)
+            .collect();
+
+        assert_eq!(text.matches("# HELP http_requests ").count(), 1);
+        assert_eq!(text.matches("# TYPE http_requests ").count(), 1);
+        assert!(lines[0].starts_with("# HELP http_requests "));
+        assert!(lines[1].starts_with("# TYPE http_requests "));
+        assert!(lines[2..]
+            .iter()
+            .all(|line| line.starts_with("http_requests_total{method=")));
+    }
+
+    #[test]
+    fn test_registry_labeled_histogram_renders_series_per_label_set() {
+        let mut registry = PrometheusRegistry::new();
+        let latency = registry
+            .labeled_histogram_with_buckets(
+                "route_latency_seconds",
+                "Latency by route",
+                vec!["route".into()],
+                vec![0.1, 1.0],
+            )
+            .unwrap();
+
+        latency.observe(&["/users"], 0.05).unwrap();
+        latency.observe(&["/orders"], 0.5).unwrap();
+        latency.observe(&["/orders"], 5.0).unwrap();
+        assert!(latency.observe(&[], 1.0).is_err());
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert_eq!(
+            text.matches("# TYPE route_latency_seconds histogram")
+                .count(),
+            1
+        );
+        assert!(text.contains("route_latency_seconds_bucket{le=\"0.1\",route=\"/users\"} 1"));
+        assert!(text.contains("route_latency_seconds_bucket{le=\"1.0\",route=\"/orders\"} 1"));
+        assert!(text.contains("route_latency_seconds_count{route=\"/orders\"} 2"));
+        assert!(registry.contains("route_latency_seconds"));
+        assert_eq!(
+            registry.histogram_buckets("route_latency_seconds"),
+            Some(&[0.1, 1.0][..])
+        );
+
+        let err = registry
+            .labeled_histogram_with_buckets("h", "H", vec!["__name__".into()], vec![1.0])
+            .unwrap_err();
+        assert!(matches!(err, DeserializeError::ReservedLabel(_)));
+    }
+
+    #[test]
+    fn test_labeled_histogram_time_result() {
+        let mut registry = PrometheusRegistry::new();
+        let jobs = registry
+            .labeled_histogram("job_seconds", "Job duration", vec!["result".into()])
+            .unwrap();
+
+        let outcome = jobs.time_result(&[], || "x".parse::<u32>()).unwrap();
+        assert!(outcome.is_err());
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("job_seconds_count{result=\"err\"} 1"));
+        assert!(!text.contains("result=\"ok\""));
+    }
+
+    #[test]
+    fn test_labeled_counter_with_enum_labels() {
+        #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
+        enum Method {
+            Get,
+            Post,
+        }
+
+        #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+        struct RequestLabels {
+            method: Method,
+        }
+
+        let requests: LabeledCounter<RequestLabels> = labeled_counter();
+        let mut registry = PrometheusRegistry::new();
+        registry
+            .inner_mut()
+            .register("requests", "Requests by method", requests.clone());
+
+        requests
+            .get_or_create(&RequestLabels {
+                method: Method::Get,
+            })
+            .inc();
+        requests
+            .get_or_create(&RequestLabels {
+                method: Method::Post,
+            })
+            .inc_by(2);
+
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("requests_total{method=\"Get\"} 1"));
+        assert!(text.contains("requests_total{method=\"Post\"} 2"));
+    }
+
     #[test]
     fn test_labeled_gauge() {
         #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
@@ -0,0 +1,186 @@
+//! Comparing two versions of a metrics config.
+//!
+//! Useful in change review: a [`ConfigDiff`] lists which metrics a config
+//! change adds, removes or redefines.
+
+use super::{MetricConfig, RegistryConfig};
+use crate::core::metrics::MetricKind;
+use std::collections::HashMap;
+
+/// Metric names added, removed and changed between two configs, each sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ConfigDiff {
+    /// Returns true if the configs define the same metrics.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare the metrics defined by `old` and `new`.
+///
+/// Metrics are matched by name and kind, so a metric whose type changes is
+/// reported as removed and added. A matched metric is changed when its
+/// description, buckets, label names, aliases, summary-only flag or counter
+/// value type differ; starting values and metadata don't affect the
+/// exported series and are ignored. Disabled metrics count as absent.
+///
+/// # Example
+/// ```ignore
+/// let diff = diff_configs(&load_file("metrics.old.yaml")?, &load_file("metrics.yaml")?);
+/// for name in &diff.removed {
+///     println!("- {}", name);
+/// }
+/// ```
+pub fn diff_configs(old: &RegistryConfig, new: &RegistryConfig) -> ConfigDiff {
+    let old_metrics = enabled_by_key(old);
+    let new_metrics = enabled_by_key(new);
+    let mut diff = ConfigDiff::default();
+
+    for (key, new_metric) in &new_metrics {
+        match old_metrics.get(key) {
+            None => diff.added.push(key.0.to_string()),
+            Some(old_metric) if is_redefined(old_metric, new_metric) => {
+                diff.changed.push(key.0.to_string())
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = old_metrics
+        .keys()
+        .filter(|key| !new_metrics.contains_key(*key))
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    diff
+}
+
+/// Index the enabled metrics of `config` by name and kind.
+fn enabled_by_key(config: &RegistryConfig) -> HashMap<(&str, MetricKind), &MetricConfig> {
+    config
+        .metrics
+        .iter()
+        .filter(|metric| metric.is_enabled())
+        .map(|metric| ((metric.name(), metric.kind()), metric))
+        .collect()
+}
+
+/// Returns true if the two definitions of a metric export different series.
+fn is_redefined(old: &MetricConfig, new: &MetricConfig) -> bool {
+    old.description() != new.description()
+        || old.buckets() != new.buckets()
+        || old.labels() != new.labels()
+        || old.aliases() != new.aliases()
+        || old.is_summary_only() != new.is_summary_only()
+        || old.has_integer_sum() != new.has_integer_sum()
+        || old.counter_value_type() != new.counter_value_type()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CounterValueType;
+    use std::collections::BTreeMap;
+
+    fn counter(name: &str, description: &str) -> MetricConfig {
+        MetricConfig::Counter {
+            name: name.into(),
+            description: description.into(),
+            initial_value: 0,
+            value_type: CounterValueType::U64,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        }
+    }
+
+    fn histogram(name: &str, buckets: Vec<f64>) -> MetricConfig {
+        MetricConfig::Histogram {
+            name: name.into(),
+            description: "Latency".into(),
+            buckets: Some(buckets),
+            labels: vec![],
+            initial_observations: vec![],
+            summary_only: false,
+            integer_sum: false,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_diff_configs() {
+        let old = RegistryConfig {
+            metrics: vec![
+                counter("requests_total", "Requests"),
+                counter("errors_total", "Errors"),
+                counter("retries_total", "Retries"),
+                histogram("latency_seconds", vec![0.1, 1.0]),
+                counter("queue_depth", "Queue depth"),
+            ],
+        };
+        let new = RegistryConfig {
+            metrics: vec![
+                counter("requests_total", "Requests"),
+                counter("errors_total", "Failed requests"),
+                histogram("latency_seconds", vec![0.1, 0.5, 1.0]),
+                counter("cache_hits_total", "Cache hits"),
+                // Retyped, so removed and added under the same name
+                MetricConfig::Gauge {
+                    name: "queue_depth".into(),
+                    description: "Queue depth".into(),
+                    initial_value: 0,
+                    labels: vec![],
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+            ],
+        };
+
+        assert_eq!(
+            diff_configs(&old, &new),
+            ConfigDiff {
+                added: vec!["cache_hits_total".into(), "queue_depth".into()],
+                removed: vec!["queue_depth".into(), "retries_total".into()],
+                changed: vec!["errors_total".into(), "latency_seconds".into()],
+            }
+        );
+        assert!(diff_configs(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_ignores_values_and_treats_disabled_as_absent() {
+        let old = RegistryConfig {
+            metrics: vec![counter("requests_total", "Requests")],
+        };
+        let mut new = old.clone();
+        if let MetricConfig::Counter {
+            initial_value,
+            metadata,
+            ..
+        } = &mut new.metrics[0]
+        {
+            *initial_value = 10;
+            metadata.insert("team".into(), "payments".into());
+        }
+        assert!(diff_configs(&old, &new).is_empty());
+
+        if let MetricConfig::Counter { enabled, .. } = &mut new.metrics[0] {
+            *enabled = false;
+        }
+        assert_eq!(
+            diff_configs(&old, &new).removed,
+            vec!["requests_total".to_string()]
+        );
+    }
+}
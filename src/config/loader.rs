@@ -0,0 +1,888 @@
+//! Loading configs from disk.
+//!
+//! Config paths often come from CLI flags or environment variables, so a file
+//! is only read if it resolves (after following symlinks) to a location under
+//! one of the allowed base directories. By default these are:
+//!
+//! - `$XDG_CONFIG_HOME`, or `$HOME/.config` when unset
+//! - the current working directory
+//! - an optional caller-supplied extra base
+//!
+//! A [`FileLoader`] replaces the defaults with an explicit list. Where none
+//! of the defaults exist (e.g. a container with no `$HOME` and a deleted
+//! working directory), [`FileLoader::with_default_bases_or_config_dir`] can
+//! fall back to the directory of the config file itself.
+//!
+//! The format is picked from the file extension: `.json` (feature:
+//! `json-config`), `.ndjson` / `.jsonl` with one metric per line (feature:
+//! `json-config`) or `.yaml` / `.yml` (feature: `yaml-config`).
+//!
+//! JSON and YAML files may also be a [`RegistryDocument`] listing other
+//! files to `include`. Included paths are resolved against the including
+//! file, go through the same base directory checks, and must not form a
+//! cycle.
+
+use super::registry::{validate_config, LoadOptions};
+use super::MetricConfig;
+use super::{RegistryConfig, RegistryDocument};
+use crate::core::deserialise::DeserializeError;
+use std::ffi::OsString;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+
+/// Collect the directories config files may be loaded from.
+///
+/// Directories that do not exist are skipped. Fails with
+/// [`DeserializeError::InvalidFilePath`] if no base can be determined; the
+/// message lists each environment variable and directory that was checked.
+pub fn allowed_base_directories(
+    extra_base: Option<&Path>,
+) -> Result<Vec<PathBuf>, DeserializeError> {
+    BaseSources::from_env().resolve(extra_base, None)
+}
+
+/// Like [`allowed_base_directories`], but when no default base exists the
+/// directory containing `config_path` is allowed instead.
+///
+/// The fallback only applies when every default is unavailable, so it never
+/// widens the bases on a normally configured host.
+pub fn allowed_base_directories_or_config_dir(
+    extra_base: Option<&Path>,
+    config_path: &Path,
+) -> Result<Vec<PathBuf>, DeserializeError> {
+    BaseSources::from_env().resolve(extra_base, Some(config_path))
+}
+
+/// The environment the default base directories are derived from.
+struct BaseSources {
+    xdg_config_home: Option<OsString>,
+    home: Option<OsString>,
+    current_dir: std::io::Result<PathBuf>,
+}
+
+impl BaseSources {
+    fn from_env() -> Self {
+        Self {
+            xdg_config_home: std::env::var_os("XDG_CONFIG_HOME"),
+            home: std::env::var_os("HOME"),
+            current_dir: std::env::current_dir(),
+        }
+    }
+
+    /// Canonicalise the bases that exist, falling back to the directory of
+    /// `config_path` (if given) when none do.
+    fn resolve(
+        self,
+        extra_base: Option<&Path>,
+        config_path: Option<&Path>,
+    ) -> Result<Vec<PathBuf>, DeserializeError> {
+        let mut search = BaseSearch::default();
+
+        match (self.xdg_config_home, self.home) {
+            (Some(xdg), _) => search.try_dir("$XDG_CONFIG_HOME", PathBuf::from(xdg)),
+            (None, Some(home)) => {
+                search.try_dir("$HOME/.config", PathBuf::from(home).join(".config"))
+            }
+            (None, None) => search.skip("XDG_CONFIG_HOME and HOME are unset"),
+        }
+        match self.current_dir {
+            Ok(dir) => search.try_dir("current directory", dir),
+            Err(e) => search.skip(&format!("current directory is unavailable ({})", e)),
+        }
+        match extra_base {
+            Some(dir) => search.try_dir("extra base", dir.to_path_buf()),
+            None => search.skip("no extra base was given"),
+        }
+
+        if search.bases.is_empty() {
+            if let Some(path) = config_path {
+                let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+                search.try_dir("config file directory", dir);
+            }
+        }
+
+        if search.bases.is_empty() {
+            return Err(DeserializeError::InvalidFilePath(format!(
+                "no allowed base directory could be determined; checked: {}. \
+                 Pass an extra base directory or use FileLoader::new",
+                search.checked.join("; ")
+            )));
+        }
+        Ok(search.bases)
+    }
+}
+
+/// Bases found so far, and why each unusable candidate was skipped.
+#[derive(Default)]
+struct BaseSearch {
+    bases: Vec<PathBuf>,
+    checked: Vec<String>,
+}
+
+impl BaseSearch {
+    fn try_dir(&mut self, source: &str, dir: PathBuf) {
+        match dir.canonicalize() {
+            Ok(canonical) => self.bases.push(canonical),
+            Err(e) => self
+                .checked
+                .push(format!("{} {} ({})", source, dir.display(), e)),
+        }
+    }
+
+    fn skip(&mut self, reason: &str) {
+        self.checked.push(reason.to_string());
+    }
+}
+
+/// Loads config files from a caller-defined set of base directories.
+///
+/// Use this when the allowed locations must not depend on environment
+/// variables or the working directory, e.g. one base per tenant. The free
+/// functions such as [`load_file`] use a loader built by
+/// [`with_default_bases`](Self::with_default_bases).
+///
+/// # Example
+/// ```ignore
+/// let loader = FileLoader::new(["/etc/my-service", "/srv/tenants/acme"]);
+/// let config = loader.load("/srv/tenants/acme/metrics.yaml")?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLoader {
+    /// Canonical directories config files may be loaded from
+    pub allowed_bases: Vec<PathBuf>,
+}
+
+impl FileLoader {
+    /// Create a loader allowing files under `bases`.
+    ///
+    /// Bases are canonicalised so symlinked paths compare correctly; bases
+    /// that do not exist are dropped, as nothing can be loaded from them.
+    pub fn new<P: AsRef<Path>>(bases: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            allowed_bases: bases
+                .into_iter()
+                .filter_map(|base| base.as_ref().canonicalize().ok())
+                .collect(),
+        }
+    }
+
+    /// Create a loader using the bases from [`allowed_base_directories`].
+    pub fn with_default_bases(extra_base: Option<&Path>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            allowed_bases: allowed_base_directories(extra_base)?,
+        })
+    }
+
+    /// Like [`with_default_bases`](Self::with_default_bases), but allows the
+    /// directory of `config_path` when no default base exists.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let loader = FileLoader::with_default_bases_or_config_dir(None, &path)?;
+    /// let config = loader.load(&path)?;
+    /// ```
+    pub fn with_default_bases_or_config_dir(
+        extra_base: Option<&Path>,
+        config_path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            allowed_bases: allowed_base_directories_or_config_dir(extra_base, config_path)?,
+        })
+    }
+
+    /// Resolve `path` and check it is a regular file under an allowed base.
+    ///
+    /// Returns the canonical path on success.
+    pub fn validate(&self, path: impl AsRef<Path>) -> Result<PathBuf, DeserializeError> {
+        let path = path.as_ref();
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| DeserializeError::InvalidFilePath(format!("{}: {}", path.display(), e)))?;
+
+        if !canonical.is_file() {
+            return Err(DeserializeError::InvalidFilePath(format!(
+                "{} is not a regular file",
+                canonical.display()
+            )));
+        }
+
+        check_within_bases(&canonical, &self.allowed_bases)?;
+        Ok(canonical)
+    }
+
+    /// Load and parse a config file after validating its path.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<RegistryConfig, DeserializeError> {
+        self.load_detailed(path).map(|loaded| loaded.config)
+    }
+
+    /// Like [`load`](Self::load), but also reports the format and canonical path.
+    ///
+    /// The format and path are those of `path` itself, not its includes.
+    pub fn load_detailed(&self, path: impl AsRef<Path>) -> Result<LoadedConfig, DeserializeError> {
+        let canonical = self.validate(path)?;
+        let format = format_for_path(&canonical)?;
+        let config = self.load_with_includes(&canonical, &mut Vec::new())?;
+
+        Ok(LoadedConfig {
+            config,
+            format,
+            path: canonical,
+        })
+    }
+
+    /// Load the validated file at `canonical`, merging its includes first.
+    ///
+    /// `chain` holds the files whose includes are being loaded, outermost
+    /// first, so a file including one of them is reported as a cycle.
+    fn load_with_includes(
+        &self,
+        canonical: &Path,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<RegistryConfig, DeserializeError> {
+        let content = std::fs::read_to_string(canonical)?;
+        let document = parse_document(format_for_path(canonical)?, &content)?;
+        let dir = canonical.parent().unwrap_or(Path::new(""));
+
+        chain.push(canonical.to_path_buf());
+        let mut metrics = Vec::new();
+        for include in &document.include {
+            let included = self.validate(dir.join(include))?;
+            check_include_cycle(chain, &included)?;
+            merge_metrics(
+                &mut metrics,
+                self.load_with_includes(&included, chain)?.metrics,
+            );
+        }
+        chain.pop();
+
+        merge_metrics(&mut metrics, document.metrics);
+        Ok(RegistryConfig { metrics })
+    }
+
+    /// Async version of [`validate`](Self::validate) using `tokio::fs`.
+    #[cfg(feature = "tokio")]
+    pub async fn validate_async(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<PathBuf, DeserializeError> {
+        let path = path.as_ref();
+        let canonical = tokio::fs::canonicalize(path)
+            .await
+            .map_err(|e| DeserializeError::InvalidFilePath(format!("{}: {}", path.display(), e)))?;
+
+        if !tokio::fs::metadata(&canonical).await?.is_file() {
+            return Err(DeserializeError::InvalidFilePath(format!(
+                "{} is not a regular file",
+                canonical.display()
+            )));
+        }
+
+        check_within_bases(&canonical, &self.allowed_bases)?;
+        Ok(canonical)
+    }
+
+    /// Async version of [`load`](Self::load) using `tokio::fs`, resolving
+    /// includes the same way.
+    #[cfg(feature = "tokio")]
+    pub async fn load_async(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<RegistryConfig, DeserializeError> {
+        let canonical = self.validate_async(path).await?;
+        self.load_with_includes_async(canonical, Vec::new()).await
+    }
+
+    /// Async version of [`load_with_includes`](Self::load_with_includes).
+    ///
+    /// Boxed because it recurses into includes.
+    #[cfg(feature = "tokio")]
+    fn load_with_includes_async(
+        &self,
+        canonical: PathBuf,
+        mut chain: Vec<PathBuf>,
+    ) -> Pin<Box<dyn Future<Output = Result<RegistryConfig, DeserializeError>> + Send + '_>> {
+        Box::pin(async move {
+            let content = tokio::fs::read_to_string(&canonical).await?;
+            let document = parse_document(format_for_path(&canonical)?, &content)?;
+            let dir = canonical.parent().unwrap_or(Path::new("")).to_path_buf();
+
+            chain.push(canonical);
+            let mut metrics = Vec::new();
+            for include in &document.include {
+                let included = self.validate_async(dir.join(include)).await?;
+                check_include_cycle(&chain, &included)?;
+                let config = self
+                    .load_with_includes_async(included, chain.clone())
+                    .await?;
+                merge_metrics(&mut metrics, config.metrics);
+            }
+
+            merge_metrics(&mut metrics, document.metrics);
+            Ok(RegistryConfig { metrics })
+        })
+    }
+}
+
+/// Fail if `included` is already in `chain`, the files whose includes are
+/// being loaded.
+fn check_include_cycle(chain: &[PathBuf], included: &Path) -> Result<(), DeserializeError> {
+    let Some(start) = chain.iter().position(|path| path == included) else {
+        return Ok(());
+    };
+    let cycle: Vec<String> = chain[start..]
+        .iter()
+        .map(PathBuf::as_path)
+        .chain([included])
+        .map(|path| path.display().to_string())
+        .collect();
+    Err(DeserializeError::IncludeCycle(cycle.join(" -> ")))
+}
+
+/// Add `overrides` to `metrics`, replacing any definition with the same name.
+fn merge_metrics(metrics: &mut Vec<MetricConfig>, overrides: Vec<MetricConfig>) {
+    for metric in overrides {
+        match metrics.iter_mut().find(|m| m.name() == metric.name()) {
+            Some(existing) => *existing = metric,
+            None => metrics.push(metric),
+        }
+    }
+}
+
+/// Resolve `path` and check it is a regular file under an allowed base directory.
+///
+/// Returns the canonical path on success.
+pub fn validate_file_path(
+    path: impl AsRef<Path>,
+    extra_base: Option<&Path>,
+) -> Result<PathBuf, DeserializeError> {
+    FileLoader::with_default_bases(extra_base)?.validate(path)
+}
+
+/// The format a config file was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    /// JSON Lines, one metric per line
+    JsonLines,
+    Yaml,
+}
+
+/// A config loaded by [`load_file_detailed`], with where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedConfig {
+    pub config: RegistryConfig,
+    pub format: ConfigFormat,
+    /// Canonical path of the file that was read
+    pub path: PathBuf,
+}
+
+/// Load and parse a config file after validating its path.
+///
+/// # Example
+/// ```ignore
+/// let config = load_file("metrics.yaml", None)?;
+/// let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config)?;
+/// ```
+pub fn load_file(
+    path: impl AsRef<Path>,
+    extra_base: Option<&Path>,
+) -> Result<RegistryConfig, DeserializeError> {
+    load_file_detailed(path, extra_base).map(|loaded| loaded.config)
+}
+
+/// Like [`load_file`], but also reports the format and canonical path.
+///
+/// # Example
+/// ```ignore
+/// let loaded = load_file_detailed("metrics.yaml", None)?;
+/// println!("Loaded {:?} config from {}", loaded.format, loaded.path.display());
+/// ```
+pub fn load_file_detailed(
+    path: impl AsRef<Path>,
+    extra_base: Option<&Path>,
+) -> Result<LoadedConfig, DeserializeError> {
+    FileLoader::with_default_bases(extra_base)?.load_detailed(path)
+}
+
+/// Async version of [`load_file`] using `tokio::fs`, for use inside a runtime.
+///
+/// Applies exactly the same path checks as the blocking version and
+/// resolves includes the same way, relative to the including file.
+#[cfg(feature = "tokio")]
+pub async fn load_file_async(
+    path: impl AsRef<Path>,
+    extra_base: Option<&Path>,
+) -> Result<RegistryConfig, DeserializeError> {
+    FileLoader::with_default_bases(extra_base)?
+        .load_async(path)
+        .await
+}
+
+/// Parse JSON Lines content, one [`MetricConfig`] per non-empty line.
+///
+/// Parse errors name the 1-based line they occurred on.
+///
+/// # Example
+/// ```ignore
+/// let config = load_ndjson_str(r#"
+/// { "metric_type": "Counter", "name": "requests_total", "description": "Requests" }
+/// { "metric_type": "Gauge", "name": "queue_depth", "description": "Queue depth" }
+/// "#)?;
+/// ```
+#[cfg(feature = "json-config")]
+pub fn load_ndjson_str(content: &str) -> Result<RegistryConfig, DeserializeError> {
+    let metrics = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str::<MetricConfig>(line)
+                .map_err(|e| DeserializeError::Parse(format!("line {}: {}", index + 1, e)))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(RegistryConfig { metrics })
+}
+
+/// Load a JSON Lines config file after validating its path.
+///
+/// Unlike [`load_file`] the content is parsed as JSON Lines whatever the
+/// file extension.
+#[cfg(feature = "json-config")]
+pub fn load_ndjson_file(
+    path: impl AsRef<Path>,
+    extra_base: Option<&Path>,
+) -> Result<RegistryConfig, DeserializeError> {
+    let canonical = validate_file_path(path, extra_base)?;
+    let content = std::fs::read_to_string(&canonical)?;
+    load_ndjson_str(&content)
+}
+
+/// Parse and validate config content in memory, without touching the filesystem.
+///
+/// Applies the structural checks of [`validate_config`] with default
+/// [`LoadOptions`], so content accepted here loads with
+/// [`ConfiguredRegistry::from_config`](super::ConfiguredRegistry::from_config).
+///
+/// # Example
+/// ```ignore
+/// let config = validate_config_str(&request_body, ConfigFormat::Json)?;
+/// ```
+pub fn validate_config_str(
+    content: &str,
+    format: ConfigFormat,
+) -> Result<RegistryConfig, DeserializeError> {
+    let config = parse_as(format, content)?;
+    validate_config(&config, &LoadOptions::default())?;
+    Ok(config)
+}
+
+/// Ensure a canonical path sits under one of the allowed base directories.
+fn check_within_bases(canonical: &Path, bases: &[PathBuf]) -> Result<(), DeserializeError> {
+    if bases.iter().any(|base| canonical.starts_with(base)) {
+        Ok(())
+    } else {
+        Err(DeserializeError::InvalidFilePath(format!(
+            "{} is outside the allowed config directories",
+            canonical.display()
+        )))
+    }
+}
+
+/// Pick the config format from the file extension.
+///
+/// Fails with [`DeserializeError::UnsupportedFormat`] for unknown extensions
+/// and for formats whose feature is disabled.
+fn format_for_path(path: &Path) -> Result<ConfigFormat, DeserializeError> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        #[cfg(feature = "json-config")]
+        Some("json") => Ok(ConfigFormat::Json),
+        #[cfg(feature = "json-config")]
+        Some("ndjson" | "jsonl") => Ok(ConfigFormat::JsonLines),
+        #[cfg(feature = "yaml-config")]
+        Some("yaml" | "yml") => Ok(ConfigFormat::Yaml),
+        _ => Err(DeserializeError::UnsupportedFormat(
+            path.display().to_string(),
+        )),
+    }
+}
+
+/// Parse content as the given format, in list or document form.
+///
+/// Content not read from a file has nothing to resolve includes against,
+/// so documents with includes are rejected.
+pub(super) fn parse_as(
+    format: ConfigFormat,
+    content: &str,
+) -> Result<RegistryConfig, DeserializeError> {
+    let document = parse_document(format, content)?;
+    if !document.include.is_empty() {
+        return Err(DeserializeError::Parse(
+            "`include` is only supported when loading with load_file or FileLoader".to_string(),
+        ));
+    }
+    Ok(RegistryConfig {
+        metrics: document.metrics,
+    })
+}
+
+/// Parse content as the given format, in list or document form.
+fn parse_document(
+    format: ConfigFormat,
+    content: &str,
+) -> Result<RegistryDocument, DeserializeError> {
+    match format {
+        #[cfg(feature = "json-config")]
+        ConfigFormat::Json => {
+            serde_json::from_str(content).map_err(|e| DeserializeError::Parse(e.to_string()))
+        }
+        #[cfg(feature = "json-config")]
+        ConfigFormat::JsonLines => load_ndjson_str(content).map(|config| RegistryDocument {
+            include: Vec::new(),
+            metrics: config.metrics,
+        }),
+        #[cfg(feature = "yaml-config")]
+        ConfigFormat::Yaml => {
+            serde_yaml::from_str(content).map_err(|e| DeserializeError::Parse(e.to_string()))
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(DeserializeError::UnsupportedFormat(format!("{:?}", format))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a fresh directory under the system temp dir for one test.
+    fn scratch_dir(test_name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "observability-kit-{}-{}",
+            test_name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(feature = "json-config")]
+    const JSON_CONFIG: &str =
+        r#"[{ "metric_type": "Counter", "name": "requests_total", "description": "Requests" }]"#;
+
+    #[cfg(feature = "json-config")]
+    const JSON_LINE_A: &str =
+        r#"{ "metric_type": "Counter", "name": "a_total", "description": "A" }"#;
+
+    #[cfg(feature = "json-config")]
+    const JSON_LINE_B: &str =
+        r#"{ "metric_type": "Gauge", "name": "b_depth", "description": "B" }"#;
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_load_file_under_extra_base() {
+        let dir = scratch_dir("load-extra-base");
+        let path = dir.join("metrics.json");
+        std::fs::write(&path, JSON_CONFIG).unwrap();
+
+        let config = load_file(&path, Some(&dir)).unwrap();
+        assert_eq!(config.metrics.len(), 1);
+        assert_eq!(config.metrics[0].name(), "requests_total");
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_load_file_outside_allowed_bases_is_rejected() {
+        let dir = scratch_dir("load-outside");
+        let path = dir.join("metrics.json");
+        std::fs::write(&path, JSON_CONFIG).unwrap();
+
+        let result = load_file(&path, None);
+        assert!(matches!(result, Err(DeserializeError::InvalidFilePath(_))));
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_load_file_merges_includes() {
+        let dir = scratch_dir("load-includes");
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(
+            dir.join("shared/base.yaml"),
+            "- metric_type: Counter\n  name: requests_total\n  description: Requests\n- metric_type: Gauge\n  name: queue_depth\n  description: Queue depth\n",
+        )
+        .unwrap();
+        let child = dir.join("service.yaml");
+        std::fs::write(
+            &child,
+            "include: [shared/base.yaml]\nmetrics:\n  - metric_type: Counter\n    name: requests_total\n    description: Requests served\n    value: 2\n  - metric_type: Counter\n    name: errors_total\n    description: Errors\n",
+        )
+        .unwrap();
+
+        let loaded = load_file_detailed(&child, Some(&dir)).unwrap();
+        let names: Vec<&str> = loaded.config.metrics.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["requests_total", "queue_depth", "errors_total"]);
+        // The child's definition replaces the base one in place
+        assert_eq!(loaded.config.metrics[0].description(), "Requests served");
+        assert_eq!(loaded.path, child.canonicalize().unwrap());
+
+        // Content not read from a file can't include anything
+        let err = validate_config_str(
+            &std::fs::read_to_string(&child).unwrap(),
+            ConfigFormat::Yaml,
+        )
+        .unwrap_err();
+        assert!(matches!(err, DeserializeError::Parse(_)));
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_load_file_rejects_include_cycles_and_escapes() {
+        let dir = scratch_dir("load-include-cycle");
+        let a = dir.join("a.yaml");
+        std::fs::write(&a, "include: [b.yaml]\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "include: [a.yaml]\n").unwrap();
+
+        let err = load_file(&a, Some(&dir)).unwrap_err();
+        assert!(
+            matches!(&err, DeserializeError::IncludeCycle(cycle) if cycle.ends_with("a.yaml")),
+            "{err}"
+        );
+
+        // Includes get the same base directory checks as the top-level file
+        let outside = scratch_dir("load-include-outside").join("base.yaml");
+        std::fs::write(&outside, "[]\n").unwrap();
+        std::fs::write(&a, format!("include: [{}]\n", outside.display())).unwrap();
+        let err = FileLoader::new([&dir]).load(&a).unwrap_err();
+        assert!(matches!(err, DeserializeError::InvalidFilePath(_)));
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_load_ndjson_reports_line_numbers() {
+        let config = load_ndjson_str(
+            r#"{ "metric_type": "Counter", "name": "requests_total", "description": "Requests" }
+
+{ "metric_type": "Gauge", "name": "queue_depth", "description": "Queue depth", "value": 3 }
+{ "metric_type": "Histogram", "name": "latency_seconds", "description": "Latency" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.metrics.len(), 3);
+        assert_eq!(config.metrics[1].name(), "queue_depth");
+
+        let err = load_ndjson_str(
+            r#"{ "metric_type": "Counter", "name": "a_total", "description": "A" }
+{ "metric_type": "Counter", "name": 
+{ "metric_type": "Counter", "name": "b_total", "description": "B" }"#,
+        )
+        .unwrap_err();
+        assert!(matches!(&err, DeserializeError::Parse(msg) if msg.starts_with("line 2:")));
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_load_file_picks_ndjson_by_extension() {
+        let dir = scratch_dir("load-ndjson");
+        let path = dir.join("metrics.ndjson");
+        std::fs::write(&path, format!("{}\n{}\n", JSON_LINE_A, JSON_LINE_B)).unwrap();
+
+        let config = load_file(&path, Some(&dir)).unwrap();
+        assert_eq!(config, load_ndjson_file(&path, Some(&dir)).unwrap());
+        assert_eq!(config.metrics.len(), 2);
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_load_file_detailed_reports_format_and_path() {
+        let dir = scratch_dir("load-detailed");
+        let path = dir.join("metrics.yaml");
+        std::fs::write(
+            &path,
+            "- metric_type: Counter\n  name: requests_total\n  description: Requests\n",
+        )
+        .unwrap();
+
+        let loaded = load_file_detailed(&path, Some(&dir)).unwrap();
+        assert_eq!(loaded.format, ConfigFormat::Yaml);
+        assert_eq!(loaded.path, path.canonicalize().unwrap());
+        assert_eq!(loaded.config, load_file(&path, Some(&dir)).unwrap());
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_validate_config_str_rejects_bad_buckets() {
+        let err = validate_config_str(
+            r#"[{ "metric_type": "Histogram", "name": "h", "description": "d", "buckets": [1, 0.5] }]"#,
+            ConfigFormat::Json,
+        )
+        .unwrap_err();
+        assert!(matches!(err, DeserializeError::InvalidBuckets { .. }));
+
+        let config = validate_config_str(JSON_CONFIG, ConfigFormat::Json).unwrap();
+        assert_eq!(config.metrics[0].name(), "requests_total");
+    }
+
+    fn unavailable_environment() -> BaseSources {
+        BaseSources {
+            xdg_config_home: None,
+            home: None,
+            current_dir: Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "working directory was removed",
+            )),
+        }
+    }
+
+    #[test]
+    fn test_no_base_directory_error_lists_what_was_checked() {
+        let err = unavailable_environment().resolve(None, None).unwrap_err();
+        let DeserializeError::InvalidFilePath(message) = err else {
+            panic!("expected InvalidFilePath, got {:?}", err);
+        };
+        assert!(message.contains("XDG_CONFIG_HOME and HOME are unset"));
+        assert!(message.contains("current directory is unavailable"));
+        assert!(message.contains("no extra base was given"));
+
+        let missing = scratch_dir("bases-missing").join("missing");
+        let sources = BaseSources {
+            home: Some(missing.clone().into_os_string()),
+            ..unavailable_environment()
+        };
+        let err = sources.resolve(Some(&missing), None).unwrap_err();
+        assert!(err.to_string().contains("$HOME/.config"));
+        assert!(err.to_string().contains("extra base"));
+    }
+
+    #[test]
+    fn test_config_dir_fallback_only_applies_without_other_bases() {
+        let dir = scratch_dir("bases-config-dir");
+        let config_path = dir.join("metrics.json");
+
+        let bases = unavailable_environment()
+            .resolve(None, Some(&config_path))
+            .unwrap();
+        assert_eq!(bases, vec![dir.canonicalize().unwrap()]);
+
+        let other = scratch_dir("bases-config-dir-other");
+        let bases = unavailable_environment()
+            .resolve(Some(&other), Some(&config_path))
+            .unwrap();
+        assert_eq!(bases, vec![other.canonicalize().unwrap()]);
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_file_loader_only_allows_its_bases() {
+        let tenant_a = scratch_dir("loader-tenant-a");
+        let tenant_b = scratch_dir("loader-tenant-b");
+        let other = scratch_dir("loader-other");
+        for dir in [&tenant_a, &tenant_b, &other] {
+            std::fs::write(dir.join("metrics.json"), JSON_CONFIG).unwrap();
+        }
+
+        let loader = FileLoader::new([&tenant_a, &tenant_b, &other.join("missing")]);
+        assert_eq!(loader.allowed_bases.len(), 2);
+
+        assert!(loader.validate(tenant_a.join("metrics.json")).is_ok());
+        assert_eq!(
+            loader
+                .load(tenant_b.join("metrics.json"))
+                .unwrap()
+                .metrics
+                .len(),
+            1
+        );
+        assert!(matches!(
+            loader.load(other.join("metrics.json")),
+            Err(DeserializeError::InvalidFilePath(_))
+        ));
+        assert!(matches!(
+            loader.validate(tenant_a.join("../loader-other/metrics.json")),
+            Err(DeserializeError::InvalidFilePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_file_path_rejects_missing_files_and_directories() {
+        let dir = scratch_dir("validate");
+
+        assert!(matches!(
+            validate_file_path(dir.join("missing.json"), Some(&dir)),
+            Err(DeserializeError::InvalidFilePath(_))
+        ));
+        assert!(matches!(
+            validate_file_path(&dir, Some(&dir)),
+            Err(DeserializeError::InvalidFilePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_file_rejects_unknown_extension() {
+        let dir = scratch_dir("extension");
+        let path = dir.join("metrics.toml");
+        std::fs::write(&path, "").unwrap();
+
+        let result = load_file(&path, Some(&dir));
+        assert!(matches!(
+            result,
+            Err(DeserializeError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[cfg(all(feature = "tokio", feature = "json-config"))]
+    #[tokio::test]
+    async fn test_load_file_async_matches_blocking_load() {
+        let dir = scratch_dir("load-async");
+        let path = dir.join("metrics.json");
+        std::fs::write(&path, JSON_CONFIG).unwrap();
+
+        let config = load_file_async(&path, Some(&dir)).await.unwrap();
+        assert_eq!(config, load_file(&path, Some(&dir)).unwrap());
+
+        let outside = scratch_dir("load-async-outside").join("metrics.json");
+        std::fs::write(&outside, JSON_CONFIG).unwrap();
+        assert!(matches!(
+            load_file_async(&outside, Some(&dir)).await,
+            Err(DeserializeError::InvalidFilePath(_))
+        ));
+    }
+
+    #[cfg(all(feature = "tokio", feature = "yaml-config"))]
+    #[tokio::test]
+    async fn test_load_file_async_merges_includes() {
+        let dir = scratch_dir("load-async-includes");
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(
+            dir.join("shared/base.yaml"),
+            "- metric_type: Counter\n  name: requests_total\n  description: Requests\n",
+        )
+        .unwrap();
+        let child = dir.join("service.yaml");
+        std::fs::write(
+            &child,
+            "include: [shared/base.yaml]\nmetrics:\n  - metric_type: Gauge\n    name: queue_depth\n    description: Queue depth\n",
+        )
+        .unwrap();
+
+        let config = load_file_async(&child, Some(&dir)).await.unwrap();
+        let names: Vec<&str> = config.metrics.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["requests_total", "queue_depth"]);
+        assert_eq!(config, load_file(&child, Some(&dir)).unwrap());
+
+        let a = dir.join("a.yaml");
+        std::fs::write(&a, "include: [b.yaml]\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "include: [a.yaml]\n").unwrap();
+        assert!(matches!(
+            load_file_async(&a, Some(&dir)).await,
+            Err(DeserializeError::IncludeCycle(_))
+        ));
+    }
+}
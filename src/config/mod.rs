@@ -0,0 +1,823 @@
+//! Configuration-driven metric definitions.
+//!
+//! This module lets metrics be declared in JSON (feature: `json-config`)
+//! or YAML (feature: `yaml-config`) and registered in one go.
+//! Configs can be read from disk (see [`loader`]) or compiled into the
+//! binary with `include_str!` and parsed by
+//! [`RegistryConfig::from_embedded_str`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use observability_kit::config::{ConfiguredRegistry, RegistryConfig};
+//! use observability_kit::backends::prometheus::PrometheusBackend;
+//!
+//! let config = RegistryConfig::from_json_str(r#"[
+//!     { "metric_type": "Counter", "name": "requests_total", "description": "Requests", "value": 0 },
+//!     { "metric_type": "Gauge", "name": "queue_depth", "description": "Queue depth", "value": 20 }
+//! ]"#)?;
+//!
+//! let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config)?;
+//! println!("{}", configured.registry().render()?.as_str()?);
+//! ```
+
+pub mod diff;
+pub mod loader;
+pub mod registry;
+
+pub use diff::{diff_configs, ConfigDiff};
+#[cfg(feature = "tokio")]
+pub use loader::load_file_async;
+pub use loader::{
+    load_file, load_file_detailed, validate_config_str, validate_file_path, ConfigFormat,
+    FileLoader, LoadedConfig,
+};
+#[cfg(feature = "json-config")]
+pub use loader::{load_ndjson_file, load_ndjson_str};
+pub use registry::{
+    config_warnings, validate_config, ConfigWarning, ConfiguredRegistry, LoadOptions,
+};
+
+use crate::core::deserialise::DeserializeError;
+use crate::core::family::FamilyDescriptor;
+use crate::core::metrics::MetricKind;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single metric definition.
+///
+/// The variant is selected by the `metric_type` field, which may also be
+/// spelled `type` on input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self", tag = "metric_type", deny_unknown_fields)]
+pub enum MetricConfig {
+    Counter {
+        name: String,
+        description: String,
+        /// Starting value of the counter (default: 0)
+        #[serde(rename = "value", alias = "initial_value", default)]
+        initial_value: u64,
+        /// Whether the counter holds a whole (`u64`) or fractional (`f64`) total
+        #[serde(default, skip_serializing_if = "CounterValueType::is_default")]
+        value_type: CounterValueType,
+        /// Extra names the metric is also exported under, e.g. during a rename
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        aliases: Vec<String>,
+        /// Free-form annotations (owner, team, ...), ignored at registration
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        /// Whether the metric is registered at all (default: true)
+        #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+        enabled: bool,
+    },
+    Gauge {
+        name: String,
+        description: String,
+        /// Starting value of the gauge (default: 0)
+        ///
+        /// Whole-number floats such as `3.0` are accepted; fractional values
+        /// are rejected rather than truncated.
+        #[serde(
+            rename = "value",
+            alias = "initial_value",
+            default,
+            deserialize_with = "deserialize_whole_i64"
+        )]
+        initial_value: i64,
+        /// Label names; when set, one series is kept per combination of values
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        labels: Vec<String>,
+        /// Extra names the metric is also exported under, e.g. during a rename
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        aliases: Vec<String>,
+        /// Free-form annotations (owner, team, ...), ignored at registration
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        /// Whether the metric is registered at all (default: true)
+        #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+        enabled: bool,
+    },
+    Histogram {
+        name: String,
+        description: String,
+        /// Bucket upper bounds (default: latency buckets)
+        ///
+        /// Bounds may also be given as OpenMetrics `le` strings such as
+        /// `"0.5"` or `"+Inf"`.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_bucket_bounds",
+            serialize_with = "serialize_bucket_bounds"
+        )]
+        buckets: Option<Vec<f64>>,
+        /// Label names; when set, one series is kept per combination of values
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        labels: Vec<String>,
+        /// Values observed once at registration, e.g. to prime dashboards in tests
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        initial_observations: Vec<f64>,
+        /// Render only the `_sum` and `_count` series, leaving out the buckets
+        #[serde(default, skip_serializing_if = "is_false")]
+        summary_only: bool,
+        /// Keep the sum as a `u64` for integer-valued observations, rendering
+        /// `_sum` without a decimal point; unlabeled histograms only
+        #[serde(default, skip_serializing_if = "is_false")]
+        integer_sum: bool,
+        /// Extra names the metric is also exported under, e.g. during a rename
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        aliases: Vec<String>,
+        /// Free-form annotations (owner, team, ...), ignored at registration
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        /// Whether the metric is registered at all (default: true)
+        #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+        enabled: bool,
+    },
+}
+
+/// How a configured counter stores its total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CounterValueType {
+    /// Whole numbers
+    #[default]
+    U64,
+    /// Fractional totals, e.g. costs
+    F64,
+}
+
+impl CounterValueType {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl MetricConfig {
+    /// The metric name.
+    pub fn name(&self) -> &str {
+        match self {
+            MetricConfig::Counter { name, .. }
+            | MetricConfig::Gauge { name, .. }
+            | MetricConfig::Histogram { name, .. } => name,
+        }
+    }
+
+    /// The kind of metric this defines.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            MetricConfig::Counter { .. } => MetricKind::Counter,
+            MetricConfig::Gauge { .. } => MetricKind::Gauge,
+            MetricConfig::Histogram { .. } => MetricKind::Histogram,
+        }
+    }
+
+    /// The metric description.
+    pub fn description(&self) -> &str {
+        match self {
+            MetricConfig::Counter { description, .. }
+            | MetricConfig::Gauge { description, .. }
+            | MetricConfig::Histogram { description, .. } => description,
+        }
+    }
+
+    /// Whether the metric should be registered.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            MetricConfig::Counter { enabled, .. }
+            | MetricConfig::Gauge { enabled, .. }
+            | MetricConfig::Histogram { enabled, .. } => *enabled,
+        }
+    }
+
+    /// Label names, empty for unlabeled metrics.
+    pub fn labels(&self) -> &[String] {
+        match self {
+            MetricConfig::Counter { .. } => &[],
+            MetricConfig::Gauge { labels, .. } | MetricConfig::Histogram { labels, .. } => labels,
+        }
+    }
+
+    /// Histogram bucket bounds, `None` for other kinds or default buckets.
+    pub fn buckets(&self) -> Option<&[f64]> {
+        match self {
+            MetricConfig::Histogram { buckets, .. } => buckets.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// How a counter stores its total, `None` for other kinds.
+    pub fn counter_value_type(&self) -> Option<CounterValueType> {
+        match self {
+            MetricConfig::Counter { value_type, .. } => Some(*value_type),
+            _ => None,
+        }
+    }
+
+    /// Whether a histogram is rendered without its `_bucket` series.
+    pub fn is_summary_only(&self) -> bool {
+        match self {
+            MetricConfig::Histogram { summary_only, .. } => *summary_only,
+            _ => false,
+        }
+    }
+
+    /// Whether a histogram keeps its sum as a whole number.
+    pub fn has_integer_sum(&self) -> bool {
+        match self {
+            MetricConfig::Histogram { integer_sum, .. } => *integer_sum,
+            _ => false,
+        }
+    }
+
+    /// Extra names the metric is also exported under.
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            MetricConfig::Counter { aliases, .. }
+            | MetricConfig::Gauge { aliases, .. }
+            | MetricConfig::Histogram { aliases, .. } => aliases,
+        }
+    }
+
+    /// Annotations attached to the metric definition.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        match self {
+            MetricConfig::Counter { metadata, .. }
+            | MetricConfig::Gauge { metadata, .. }
+            | MetricConfig::Histogram { metadata, .. } => metadata,
+        }
+    }
+}
+
+impl From<&MetricConfig> for FamilyDescriptor {
+    /// The family a config entry defines; starting values and metadata are dropped.
+    fn from(metric: &MetricConfig) -> Self {
+        match metric {
+            MetricConfig::Counter {
+                name,
+                description,
+                aliases,
+                ..
+            } => FamilyDescriptor::Counter {
+                name: name.clone(),
+                description: description.clone(),
+                aliases: aliases.clone(),
+            },
+            MetricConfig::Gauge {
+                name,
+                description,
+                labels,
+                aliases,
+                ..
+            } => FamilyDescriptor::Gauge {
+                name: name.clone(),
+                description: description.clone(),
+                labels: labels.clone(),
+                aliases: aliases.clone(),
+            },
+            MetricConfig::Histogram {
+                name,
+                description,
+                buckets,
+                labels,
+                summary_only,
+                aliases,
+                ..
+            } => FamilyDescriptor::Histogram {
+                name: name.clone(),
+                description: description.clone(),
+                buckets: buckets.clone(),
+                labels: labels.clone(),
+                summary_only: *summary_only,
+                aliases: aliases.clone(),
+            },
+        }
+    }
+}
+
+impl Serialize for MetricConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MetricConfig::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MetricConfig {
+    /// Deserialize a metric, accepting `type` as an alias for the
+    /// `metric_type` tag, which serde can't alias directly.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MetricVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for MetricVisitor {
+            type Value = MetricConfig;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a metric definition")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<MetricConfig, A::Error> {
+                MetricConfig::deserialize(serde::de::value::MapAccessDeserializer::new(
+                    TypeTagAlias(map),
+                ))
+            }
+        }
+
+        deserializer.deserialize_map(MetricVisitor)
+    }
+}
+
+/// Map access that renames a `type` key to `metric_type`.
+struct TypeTagAlias<A>(A);
+
+impl<'de, A: serde::de::MapAccess<'de>> serde::de::MapAccess<'de> for TypeTagAlias<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        use serde::de::IntoDeserializer;
+
+        match self.0.next_key::<String>()? {
+            Some(key) if key == "type" => seed
+                .deserialize("metric_type".into_deserializer())
+                .map(Some),
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, A::Error> {
+        self.0.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+/// Deserialize an `i64`, accepting floats only when they have no fractional part.
+fn deserialize_whole_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct WholeI64;
+
+    impl serde::de::Visitor<'_> for WholeI64 {
+        type Value = i64;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a whole number")
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<i64, E> {
+            Ok(value)
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<i64, E> {
+            i64::try_from(value)
+                .map_err(|_| E::custom(format!("value {} is out of range for i64", value)))
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<i64, E> {
+            if value.fract() != 0.0 || !value.is_finite() {
+                return Err(E::custom(format!(
+                    "fractional value {} is not allowed for an integer gauge",
+                    value
+                )));
+            }
+            if value < i64::MIN as f64 || value >= i64::MAX as f64 {
+                return Err(E::custom(format!(
+                    "value {} is out of range for i64",
+                    value
+                )));
+            }
+            Ok(value as i64)
+        }
+    }
+
+    deserializer.deserialize_any(WholeI64)
+}
+
+/// Deserialize histogram bounds given as numbers or `le`-style strings.
+fn deserialize_bucket_bounds<'de, D>(deserializer: D) -> Result<Option<Vec<f64>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct BucketBound(f64);
+
+    impl<'de> Deserialize<'de> for BucketBound {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(BucketBoundVisitor)
+        }
+    }
+
+    struct BucketBoundVisitor;
+
+    impl serde::de::Visitor<'_> for BucketBoundVisitor {
+        type Value = BucketBound;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a number or a bucket bound string such as \"0.5\" or \"+Inf\"")
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<BucketBound, E> {
+            Ok(BucketBound(value))
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<BucketBound, E> {
+            Ok(BucketBound(value as f64))
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<BucketBound, E> {
+            Ok(BucketBound(value as f64))
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<BucketBound, E> {
+            parse_bucket_bound(value)
+                .map(BucketBound)
+                .ok_or_else(|| E::custom(format!("invalid bucket bound '{}'", value)))
+        }
+    }
+
+    let bounds = Option::<Vec<BucketBound>>::deserialize(deserializer)?;
+    Ok(bounds.map(|bounds| bounds.into_iter().map(|BucketBound(bound)| bound).collect()))
+}
+
+/// Parse an OpenMetrics `le` value, e.g. `"0.25"`, `"1e3"` or `"+Inf"`.
+fn parse_bucket_bound(value: &str) -> Option<f64> {
+    match value.trim() {
+        "+Inf" | "Inf" | "inf" | "+inf" => Some(f64::INFINITY),
+        text => text.parse::<f64>().ok().filter(|bound| bound.is_finite()),
+    }
+}
+
+/// Serialize histogram bounds, writing infinite bounds as `"+Inf"`.
+///
+/// JSON has no representation for infinity, so the `le` spelling is used.
+fn serialize_bucket_bounds<S>(buckets: &Option<Vec<f64>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::SerializeSeq;
+
+    let Some(buckets) = buckets else {
+        return serializer.serialize_none();
+    };
+
+    let mut seq = serializer.serialize_seq(Some(buckets.len()))?;
+    for bound in buckets {
+        if *bound == f64::INFINITY {
+            seq.serialize_element("+Inf")?;
+        } else {
+            seq.serialize_element(bound)?;
+        }
+    }
+    seq.end()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// A full set of metric definitions.
+///
+/// Serialised as a plain list of [`MetricConfig`] entries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RegistryConfig {
+    pub metrics: Vec<MetricConfig>,
+}
+
+/// A config file in document form: metrics plus other files to load first.
+///
+/// Config files hold either a plain list of metrics or a document:
+///
+/// ```yaml
+/// include: [base.yaml]
+/// metrics:
+///   - metric_type: Counter
+///     name: requests_total
+///     description: Requests
+/// ```
+///
+/// Included files are loaded and merged in order, then `metrics` is merged
+/// on top; a later definition replaces an earlier one with the same name.
+/// Includes are only followed by [`load_file`] and [`FileLoader`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self", deny_unknown_fields)]
+pub struct RegistryDocument {
+    /// Config paths to load first, relative to the including file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub metrics: Vec<MetricConfig>,
+}
+
+impl Serialize for RegistryDocument {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RegistryDocument::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RegistryDocument {
+    /// Deserialize a document, or a plain list of metrics as a document
+    /// without includes.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DocumentVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for DocumentVisitor {
+            type Value = RegistryDocument;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a list of metrics or a document with `metrics`")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                seq: A,
+            ) -> Result<RegistryDocument, A::Error> {
+                Ok(RegistryDocument {
+                    include: Vec::new(),
+                    metrics: Vec::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))?,
+                })
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<RegistryDocument, A::Error> {
+                RegistryDocument::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer.deserialize_any(DocumentVisitor)
+    }
+}
+
+impl RegistryConfig {
+    /// Parse a config from a JSON string.
+    #[cfg(feature = "json-config")]
+    pub fn from_json_str(content: &str) -> Result<Self, DeserializeError> {
+        serde_json::from_str(content).map_err(|e| DeserializeError::Parse(e.to_string()))
+    }
+
+    /// Parse a config from a YAML string.
+    #[cfg(feature = "yaml-config")]
+    pub fn from_yaml_str(content: &str) -> Result<Self, DeserializeError> {
+        serde_yaml::from_str(content).map_err(|e| DeserializeError::Parse(e.to_string()))
+    }
+
+    /// Parse a config compiled into the binary with `include_str!`.
+    ///
+    /// Embedded content never touches the filesystem, so unlike
+    /// [`load_file`] there is no path to validate.
+    ///
+    /// # Example
+    /// ```ignore
+    /// static METRICS: &str = include_str!("../metrics.yaml");
+    ///
+    /// let config = RegistryConfig::from_embedded_str(METRICS, ConfigFormat::Yaml)?;
+    /// ```
+    pub fn from_embedded_str(
+        content: &'static str,
+        format: ConfigFormat,
+    ) -> Result<Self, DeserializeError> {
+        loader::parse_as(format, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_family_descriptor_from_config_entry() {
+        let metric = MetricConfig::Histogram {
+            name: "latency_seconds".into(),
+            description: "Latency".into(),
+            buckets: Some(vec![0.1, 1.0]),
+            labels: vec!["route".into()],
+            initial_observations: vec![0.2],
+            summary_only: true,
+            integer_sum: false,
+            aliases: vec!["latency".into()],
+            metadata: BTreeMap::from([("team".into(), "web".into())]),
+            enabled: true,
+        };
+
+        assert_eq!(
+            FamilyDescriptor::from(&metric),
+            FamilyDescriptor::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets: Some(vec![0.1, 1.0]),
+                labels: vec!["route".into()],
+                summary_only: true,
+                aliases: vec!["latency".into()],
+            }
+        );
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_parse_json_config() {
+        let config = RegistryConfig::from_json_str(
+            r#"[
+                { "metric_type": "Counter", "name": "requests_total", "description": "Requests", "value": 3 },
+                { "metric_type": "Gauge", "name": "queue_depth", "description": "Queue depth", "value": -2 },
+                { "metric_type": "Histogram", "name": "latency_seconds", "description": "Latency", "buckets": [0.1, 1.0] }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.metrics.len(), 3);
+        assert_eq!(
+            config.metrics[0],
+            MetricConfig::Counter {
+                name: "requests_total".into(),
+                description: "Requests".into(),
+                initial_value: 3,
+                value_type: CounterValueType::U64,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }
+        );
+        assert_eq!(config.metrics[1].name(), "queue_depth");
+        assert_eq!(config.metrics[2].description(), "Latency");
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_gauge_value_rejects_fractions() {
+        let parse = |value: &str| {
+            RegistryConfig::from_json_str(&format!(
+                r#"[{{ "metric_type": "Gauge", "name": "g", "description": "d", "value": {} }}]"#,
+                value
+            ))
+        };
+
+        let err = parse("1.5").unwrap_err();
+        assert!(
+            matches!(&err, DeserializeError::Parse(msg) if msg.contains("fractional value 1.5"))
+        );
+
+        for (literal, expected) in [("1", 1), ("-4", -4), ("2.0", 2), ("1e3", 1000)] {
+            let config = parse(literal).unwrap();
+            assert!(matches!(
+                config.metrics[0],
+                MetricConfig::Gauge { initial_value, .. } if initial_value == expected
+            ));
+        }
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_histogram_buckets_accept_le_strings() {
+        let config = RegistryConfig::from_yaml_str(
+            "- metric_type: Histogram\n  name: latency_seconds\n  description: Latency\n  buckets: [\"0.1\", \"1\", 2.5, \"+Inf\"]\n",
+        )
+        .unwrap();
+
+        let MetricConfig::Histogram { buckets, .. } = &config.metrics[0] else {
+            panic!("expected a histogram");
+        };
+        assert_eq!(
+            buckets.as_deref(),
+            Some(&[0.1, 1.0, 2.5, f64::INFINITY][..])
+        );
+
+        let err = RegistryConfig::from_yaml_str(
+            "- metric_type: Histogram\n  name: latency_seconds\n  description: Latency\n  buckets: [\"0.1\", \"fast\"]\n",
+        )
+        .unwrap_err();
+        assert!(
+            matches!(&err, DeserializeError::Parse(msg) if msg.contains("invalid bucket bound 'fast'"))
+        );
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_infinite_bucket_round_trips_through_json() {
+        let config = RegistryConfig::from_json_str(
+            r#"[{ "metric_type": "Histogram", "name": "h", "description": "d", "buckets": [0.5, "+Inf"] }]"#,
+        )
+        .unwrap();
+
+        let serialised = serde_json::to_string(&config).unwrap();
+        assert!(serialised.contains(r#""buckets":[0.5,"+Inf"]"#));
+        assert_eq!(RegistryConfig::from_json_str(&serialised).unwrap(), config);
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_parse_json_rejects_unknown_fields() {
+        let result = RegistryConfig::from_json_str(
+            r#"[{ "metric_type": "Counter", "name": "a", "description": "b", "colour": "red" }]"#,
+        );
+        assert!(matches!(result, Err(DeserializeError::Parse(_))));
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_metadata_round_trips() {
+        let config = RegistryConfig::from_yaml_str(
+            "- metric_type: Counter\n  name: payments_total\n  description: Payments\n  metadata:\n    team: payments\n    owner: alice\n",
+        )
+        .unwrap();
+
+        let metadata = config.metrics[0].metadata();
+        assert_eq!(metadata.get("team").map(String::as_str), Some("payments"));
+        assert_eq!(metadata.get("owner").map(String::as_str), Some("alice"));
+
+        let serialised = serde_yaml::to_string(&config).unwrap();
+        assert!(serialised.contains("team: payments"));
+        assert_eq!(RegistryConfig::from_yaml_str(&serialised).unwrap(), config);
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_enabled_defaults_to_true() {
+        let config = RegistryConfig::from_yaml_str(
+            "- metric_type: Counter\n  name: a_total\n  description: A\n- metric_type: Counter\n  name: b_total\n  description: B\n  enabled: false\n",
+        )
+        .unwrap();
+
+        assert!(config.metrics[0].is_enabled());
+        assert!(!config.metrics[1].is_enabled());
+
+        let serialised = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(serialised.matches("enabled").count(), 1);
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_parse_yaml_config() {
+        let config = RegistryConfig::from_yaml_str(
+            "- metric_type: Gauge\n  name: active_connections\n  description: Active connections\n  value: 20\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.metrics,
+            vec![MetricConfig::Gauge {
+                name: "active_connections".into(),
+                description: "Active connections".into(),
+                initial_value: 20,
+                labels: vec![],
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }]
+        );
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_type_and_initial_value_aliases() {
+        let canonical = RegistryConfig::from_yaml_str(
+            "- metric_type: Counter\n  name: jobs_total\n  description: Jobs\n  value: 4\n- metric_type: Gauge\n  name: workers\n  description: Workers\n  value: -1\n- metric_type: Histogram\n  name: job_seconds\n  description: Job duration\n",
+        )
+        .unwrap();
+        let aliased = RegistryConfig::from_yaml_str(
+            "- type: Counter\n  name: jobs_total\n  description: Jobs\n  initial_value: 4\n- type: Gauge\n  name: workers\n  description: Workers\n  initial_value: -1\n- type: Histogram\n  name: job_seconds\n  description: Job duration\n",
+        )
+        .unwrap();
+        assert_eq!(aliased, canonical);
+
+        // Output keeps the canonical names
+        let serialised = serde_yaml::to_string(&aliased).unwrap();
+        assert!(serialised.contains("metric_type: Counter"));
+        assert!(serialised.contains("value: 4"));
+        assert!(!serialised.contains("initial_value"));
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_type_alias_conflicts_with_metric_type() {
+        let aliased = RegistryConfig::from_json_str(
+            r#"[{ "type": "Gauge", "name": "workers", "description": "Workers", "initial_value": 2.0 }]"#,
+        )
+        .unwrap();
+        assert_eq!(aliased.metrics[0].kind(), MetricKind::Gauge);
+
+        let result = RegistryConfig::from_json_str(
+            r#"[{ "type": "Gauge", "metric_type": "Gauge", "name": "workers", "description": "Workers" }]"#,
+        );
+        assert!(matches!(result, Err(DeserializeError::Parse(_))));
+    }
+}
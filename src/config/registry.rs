@@ -0,0 +1,1781 @@
+//! Building registries from configuration.
+
+#[cfg(feature = "json-config")]
+use super::ConfigFormat;
+use super::{CounterValueType, MetricConfig, RegistryConfig};
+use crate::core::deserialise::{
+    normalize_and_validate_buckets, validate_label_name, validate_metric_name, DeserializeError,
+    DEFAULT_MAX_BUCKETS, DEFAULT_MAX_NAME_LENGTH,
+};
+use crate::core::labeled::Labeled;
+use crate::core::metrics::{Metric, MetricKind};
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use std::collections::{BTreeMap, HashSet};
+
+/// Name of the self-metric counting failed registrations during a lenient load.
+pub const REGISTRATION_FAILURES_METRIC: &str = "obs_kit_registration_failures_total";
+
+/// Name of the gauge holding the number of metrics registered from config.
+pub const CONFIG_METRICS_METRIC: &str = "config_metrics_total";
+
+/// Names of the gauges holding the number of counters, gauges and histograms
+/// registered from config.
+pub const CONFIG_KIND_METRICS: [(MetricKind, &str); 3] = [
+    (MetricKind::Counter, "config_counters"),
+    (MetricKind::Gauge, "config_gauges"),
+    (MetricKind::Histogram, "config_histograms"),
+];
+
+/// Histograms with more buckets than this get a [`ConfigWarning::ManyBuckets`].
+pub const MANY_BUCKETS_WARNING_THRESHOLD: usize = 20;
+
+/// A non-fatal issue in a config, reported by [`config_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigWarning {
+    /// The metric's description is empty, leaving its `# HELP` line blank
+    EmptyDescription { name: String },
+    /// The histogram has one bucket, so it can only tell values below the
+    /// bound from values above it
+    SingleBucket { name: String },
+    /// The histogram has more than [`MANY_BUCKETS_WARNING_THRESHOLD`]
+    /// buckets, each exported as its own series
+    ManyBuckets { name: String, count: usize },
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyDescription { name } => write!(f, "'{}' has an empty description", name),
+            Self::SingleBucket { name } => write!(f, "histogram '{}' has a single bucket", name),
+            Self::ManyBuckets { name, count } => {
+                write!(f, "histogram '{}' has {} buckets", name, count)
+            }
+        }
+    }
+}
+
+/// Options controlling how a [`RegistryConfig`] is turned into a registry.
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// Register [`REGISTRATION_FAILURES_METRIC`] and count each metric that
+    /// fails to register during [`ConfiguredRegistry::from_config_lenient_with_options`]
+    /// (default: false)
+    pub track_registration_failures: bool,
+    /// Treat names differing only in case (e.g. `Requests_Total` and
+    /// `requests_total`) as duplicates (default: false)
+    pub case_insensitive_names: bool,
+    /// Maximum number of buckets a histogram may declare (default: 64)
+    pub max_buckets: usize,
+    /// Fail with [`DeserializeError::EmptyConfig`] when the config has no
+    /// enabled metrics (default: false)
+    pub require_non_empty: bool,
+    /// Register [`CONFIG_METRICS_METRIC`] and the [`CONFIG_KIND_METRICS`]
+    /// gauges, set to how many metrics of each kind were registered from
+    /// config (default: false)
+    pub register_config_gauges: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            track_registration_failures: false,
+            case_insensitive_names: false,
+            max_buckets: DEFAULT_MAX_BUCKETS,
+            require_non_empty: false,
+            register_config_gauges: false,
+        }
+    }
+}
+
+/// A registry populated from a [`RegistryConfig`].
+///
+/// # Example
+/// ```ignore
+/// let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config)?;
+///
+/// if let Some(requests) = configured.counter("requests_total") {
+///     requests.inc();
+/// }
+/// ```
+pub struct ConfiguredRegistry<B: MetricBackend> {
+    registry: ObservabilityRegistry<B>,
+    owned: ConfigOwned,
+}
+
+/// The metrics of a registry that were registered from config.
+///
+/// Kept apart from the registry so a registry served behind a lock can be
+/// reloaded in place.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConfigOwned {
+    /// Metrics registered from config, in registration order
+    configured: Vec<(String, MetricKind)>,
+    /// Configured starting values of unlabeled gauges
+    initial_gauges: Vec<(String, i64)>,
+    /// The options the metrics were loaded with, reused on reload
+    options: LoadOptions,
+}
+
+impl<B: MetricBackend> ConfiguredRegistry<B> {
+    /// Register every metric in `config`, failing on the first error.
+    pub fn from_config(config: &RegistryConfig) -> Result<Self, DeserializeError> {
+        Self::from_config_with_options(config, &LoadOptions::default())
+    }
+
+    /// Register every metric in `config` using `options`, failing on the first error.
+    pub fn from_config_with_options(
+        config: &RegistryConfig,
+        options: &LoadOptions,
+    ) -> Result<Self, DeserializeError> {
+        check_non_empty(config, options)?;
+
+        let mut configured = Self {
+            registry: presized_registry(config, options),
+            owned: ConfigOwned::with_capacity(config.metrics.len(), options),
+        };
+
+        if options.track_registration_failures {
+            configured.registry.counter(
+                REGISTRATION_FAILURES_METRIC,
+                "Metric registrations that failed during config load",
+            )?;
+        }
+
+        for metric in config.metrics.iter().filter(|m| m.is_enabled()) {
+            configured
+                .owned
+                .register(&mut configured.registry, metric)?;
+        }
+
+        if options.register_config_gauges {
+            configured.register_config_gauges()?;
+        }
+
+        Ok(configured)
+    }
+
+    /// Register every metric in `config` like [`from_config`](Self::from_config),
+    /// also returning the config's [`ConfigWarning`]s.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (configured, warnings) = ConfiguredRegistry::<PrometheusBackend>::from_config_with_warnings(&config)?;
+    /// for warning in &warnings {
+    ///     eprintln!("metrics config: {}", warning);
+    /// }
+    /// ```
+    pub fn from_config_with_warnings(
+        config: &RegistryConfig,
+    ) -> Result<(Self, Vec<ConfigWarning>), DeserializeError> {
+        let configured = Self::from_config(config)?;
+        Ok((configured, config_warnings(config)))
+    }
+
+    /// Register every metric in a JSON config compiled into the binary.
+    ///
+    /// For single-binary deployments; see
+    /// [`RegistryConfig::from_embedded_str`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let configured = ConfiguredRegistry::<PrometheusBackend>::from_embedded_json(
+    ///     include_str!("../metrics.json"),
+    /// )?;
+    /// ```
+    #[cfg(feature = "json-config")]
+    pub fn from_embedded_json(content: &'static str) -> Result<Self, DeserializeError> {
+        Self::from_config(&RegistryConfig::from_embedded_str(
+            content,
+            ConfigFormat::Json,
+        )?)
+    }
+
+    /// Register every metric in `config`, skipping and collecting failures.
+    pub fn from_config_lenient(config: &RegistryConfig) -> (Self, Vec<DeserializeError>) {
+        Self::from_config_lenient_with_options(config, &LoadOptions::default())
+    }
+
+    /// Register every metric in `config` using `options`, skipping and collecting failures.
+    ///
+    /// With `track_registration_failures` enabled, each collected error also
+    /// increments [`REGISTRATION_FAILURES_METRIC`].
+    pub fn from_config_lenient_with_options(
+        config: &RegistryConfig,
+        options: &LoadOptions,
+    ) -> (Self, Vec<DeserializeError>) {
+        let mut configured = Self {
+            registry: presized_registry(config, options),
+            owned: ConfigOwned::with_capacity(config.metrics.len(), options),
+        };
+        let mut errors: Vec<DeserializeError> =
+            check_non_empty(config, options).err().into_iter().collect();
+
+        let failures = if options.track_registration_failures {
+            configured
+                .registry
+                .counter(
+                    REGISTRATION_FAILURES_METRIC,
+                    "Metric registrations that failed during config load",
+                )
+                .map_err(|e| errors.push(e))
+                .ok()
+        } else {
+            None
+        };
+
+        for metric in config.metrics.iter().filter(|m| m.is_enabled()) {
+            if let Err(e) = configured.owned.register(&mut configured.registry, metric) {
+                if let Some(failures) = &failures {
+                    failures.inc();
+                }
+                errors.push(e);
+            }
+        }
+
+        if options.register_config_gauges {
+            if let Err(e) = configured.register_config_gauges() {
+                errors.push(e);
+            }
+        }
+
+        (configured, errors)
+    }
+
+    /// Register gauges describing how many metrics were registered from config.
+    fn register_config_gauges(&mut self) -> Result<(), DeserializeError> {
+        let total = self
+            .registry
+            .gauge(CONFIG_METRICS_METRIC, "Metrics registered from config")?;
+        total.set(self.owned.configured.len() as i64);
+
+        for (kind, name) in CONFIG_KIND_METRICS {
+            let count = self
+                .owned
+                .configured
+                .iter()
+                .filter(|(_, k)| *k == kind)
+                .count();
+            self.registry
+                .gauge(
+                    name,
+                    format!("Metrics of type {} registered from config", kind),
+                )?
+                .set(count as i64);
+        }
+
+        Ok(())
+    }
+
+    /// Rebuild a config describing the metrics registered from config.
+    ///
+    /// Names, descriptions (including later [`set_description`] changes),
+    /// histogram buckets (without a trailing `+Inf`), summary-only flags and
+    /// labels are recovered. Current values are not: counters and gauges are emitted
+    /// with an initial value of 0, histograms without initial observations,
+    /// and metadata is dropped.
+    ///
+    /// [`set_description`]: ObservabilityRegistry::set_description
+    pub fn to_config(&self) -> RegistryConfig {
+        let metrics = self
+            .owned
+            .configured
+            .iter()
+            .filter_map(|(name, kind)| {
+                let metric = match kind {
+                    MetricKind::Counter => {
+                        let (description, value_type) = match self.registry.find_counter(name) {
+                            Some(counter) => (counter.description(), CounterValueType::U64),
+                            None => (
+                                self.registry.find_float_counter(name)?.description(),
+                                CounterValueType::F64,
+                            ),
+                        };
+                        MetricConfig::Counter {
+                            name: name.clone(),
+                            description: description.into(),
+                            initial_value: 0,
+                            value_type,
+                            aliases: self.registry.aliases_of(name).to_vec(),
+                            metadata: BTreeMap::new(),
+                            enabled: true,
+                        }
+                    }
+                    MetricKind::Gauge => {
+                        let (description, labels) = match self.registry.find_gauge(name) {
+                            Some(gauge) => (gauge.description(), Vec::new()),
+                            None => {
+                                let family = self.registry.find_labeled_gauge(name)?;
+                                (family.description(), family.label_names().to_vec())
+                            }
+                        };
+                        MetricConfig::Gauge {
+                            name: name.clone(),
+                            description: description.into(),
+                            initial_value: 0,
+                            labels,
+                            aliases: self.registry.aliases_of(name).to_vec(),
+                            metadata: BTreeMap::new(),
+                            enabled: true,
+                        }
+                    }
+                    MetricKind::Histogram => {
+                        let (description, labels) = match self.registry.find_histogram(name) {
+                            Some(histogram) => (histogram.description(), Vec::new()),
+                            None => {
+                                let family = self.registry.find_labeled_histogram(name)?;
+                                (family.description(), family.label_names().to_vec())
+                            }
+                        };
+                        MetricConfig::Histogram {
+                            name: name.clone(),
+                            description: description.into(),
+                            buckets: self.registry.histogram_buckets(name).map(<[f64]>::to_vec),
+                            labels,
+                            initial_observations: Vec::new(),
+                            summary_only: self.registry.is_summary_only(name),
+                            integer_sum: self.registry.has_integer_sum(name),
+                            aliases: self.registry.aliases_of(name).to_vec(),
+                            metadata: BTreeMap::new(),
+                            enabled: true,
+                        }
+                    }
+                };
+                Some(metric)
+            })
+            .collect();
+
+        RegistryConfig { metrics }
+    }
+
+    /// Replace the metrics of `kind` registered from config with those in `config`.
+    ///
+    /// Metrics of other kinds, and metrics not registered from config, are
+    /// left untouched and keep their values. Metrics of `kind` in `config`
+    /// are registered afresh, e.g. histograms with their new buckets, and
+    /// ones no longer in `config` are removed. The [`LoadOptions`] the
+    /// registry was built with apply again, and every new definition is
+    /// checked first, so on error nothing has changed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let config = load_file("metrics.yaml", None)?;
+    /// configured.reload_kind(&config, MetricKind::Histogram)?;
+    /// ```
+    pub fn reload_kind(
+        &mut self,
+        config: &RegistryConfig,
+        kind: MetricKind,
+    ) -> Result<(), DeserializeError> {
+        self.owned.reload(&mut self.registry, config, Some(kind))
+    }
+
+    /// Set every gauge registered from config back to its configured value.
+    ///
+    /// Useful between tests or at the start of a new epoch. Counters,
+    /// histograms and labeled gauges are left as they are.
+    pub fn reset_gauges_to_initial(&self) {
+        for (name, initial_value) in &self.owned.initial_gauges {
+            if let Some(gauge) = self.registry.find_gauge(name) {
+                gauge.set(*initial_value);
+            }
+        }
+    }
+
+    /// Look up a configured counter by name.
+    pub fn counter(&self, name: &str) -> Option<&Metric<B::Counter>> {
+        self.registry.find_counter(name)
+    }
+
+    /// Look up a configured counter with `value_type: f64` by name.
+    pub fn counter_f64(&self, name: &str) -> Option<&Metric<B::FloatCounter>> {
+        self.registry.find_float_counter(name)
+    }
+
+    /// Look up a configured gauge by name.
+    pub fn gauge(&self, name: &str) -> Option<&Metric<B::Gauge>> {
+        self.registry.find_gauge(name)
+    }
+
+    /// Look up a configured histogram by name.
+    pub fn histogram(&self, name: &str) -> Option<&Metric<B::Histogram>> {
+        self.registry.find_histogram(name)
+    }
+
+    /// Look up a configured labeled gauge by name.
+    pub fn labeled_gauge(&self, name: &str) -> Option<&Labeled<B::LabeledGauge>> {
+        self.registry.find_labeled_gauge(name)
+    }
+
+    /// Look up a configured labeled histogram by name.
+    pub fn labeled_histogram(&self, name: &str) -> Option<&Labeled<B::LabeledHistogram>> {
+        self.registry.find_labeled_histogram(name)
+    }
+
+    /// Get a reference to the underlying registry.
+    pub fn registry(&self) -> &ObservabilityRegistry<B> {
+        &self.registry
+    }
+
+    /// Get a mutable reference to the underlying registry.
+    pub fn registry_mut(&mut self) -> &mut ObservabilityRegistry<B> {
+        &mut self.registry
+    }
+
+    /// Consume and return the underlying registry.
+    pub fn into_registry(self) -> ObservabilityRegistry<B> {
+        self.registry
+    }
+}
+
+impl ConfigOwned {
+    fn with_capacity(capacity: usize, options: &LoadOptions) -> Self {
+        Self {
+            configured: Vec::with_capacity(capacity),
+            initial_gauges: Vec::new(),
+            options: options.clone(),
+        }
+    }
+
+    /// Register a single metric, rejecting names that are already taken.
+    fn register<B: MetricBackend>(
+        &mut self,
+        registry: &mut ObservabilityRegistry<B>,
+        metric: &MetricConfig,
+    ) -> Result<(), DeserializeError> {
+        // Aliases claim their names too, so check them before registering anything
+        check_registration(registry, metric, &self.options, |_| false)?;
+
+        let kind = match metric {
+            MetricConfig::Counter {
+                name,
+                description,
+                initial_value,
+                value_type,
+                ..
+            } => {
+                match value_type {
+                    CounterValueType::U64 => {
+                        let counter = registry.counter(name, description)?;
+                        counter.inc_by(*initial_value);
+                    }
+                    CounterValueType::F64 => {
+                        let counter = registry.float_counter(name, description)?;
+                        // Can't fail: a u64 starting value is never negative
+                        let _ = counter.try_inc_by_f64(*initial_value as f64);
+                    }
+                }
+                MetricKind::Counter
+            }
+            MetricConfig::Gauge {
+                name,
+                description,
+                initial_value,
+                labels,
+                ..
+            } => {
+                if labels.is_empty() {
+                    let gauge = registry.gauge(name, description)?;
+                    gauge.set(*initial_value);
+                    self.initial_gauges.push((name.clone(), *initial_value));
+                } else {
+                    registry.labeled_gauge(name, description, labels.clone())?;
+                }
+                MetricKind::Gauge
+            }
+            MetricConfig::Histogram {
+                name,
+                description,
+                buckets,
+                labels,
+                initial_observations,
+                summary_only,
+                integer_sum,
+                ..
+            } => {
+                let histogram = match (buckets, labels.is_empty()) {
+                    (Some(buckets), true) => {
+                        Some(registry.histogram_with_buckets(name, description, buckets.clone())?)
+                    }
+                    (None, true) => Some(registry.histogram(name, description)?),
+                    (Some(buckets), false) => {
+                        registry.labeled_histogram_with_buckets(
+                            name,
+                            description,
+                            labels.clone(),
+                            buckets.clone(),
+                        )?;
+                        None
+                    }
+                    (None, false) => {
+                        registry.labeled_histogram(name, description, labels.clone())?;
+                        None
+                    }
+                };
+                if *summary_only {
+                    registry.set_summary_only(name)?;
+                }
+                // Before the initial observations, so they are summed too
+                if *integer_sum {
+                    registry.set_integer_sum(name)?;
+                }
+                if let Some(histogram) = histogram {
+                    initial_observations
+                        .iter()
+                        .for_each(|&v| histogram.observe(v));
+                }
+                MetricKind::Histogram
+            }
+        };
+
+        for alias in metric.aliases() {
+            registry.add_alias(metric.name(), alias.clone())?;
+        }
+
+        self.configured.push((metric.name().to_string(), kind));
+        Ok(())
+    }
+
+    /// Replace the metrics registered from config with those in `config`,
+    /// only touching metrics of `kind` if one is given.
+    ///
+    /// Metrics not registered from config are left untouched. The new
+    /// definitions are checked first, so on error nothing has changed.
+    pub(crate) fn reload<B: MetricBackend>(
+        &mut self,
+        registry: &mut ObservabilityRegistry<B>,
+        config: &RegistryConfig,
+        kind: Option<MetricKind>,
+    ) -> Result<(), DeserializeError> {
+        let reloaded = RegistryConfig {
+            metrics: config
+                .metrics
+                .iter()
+                .filter(|m| m.is_enabled() && kind.is_none_or(|kind| m.kind() == kind))
+                .cloned()
+                .collect(),
+        };
+        validate_config(&reloaded, &self.options)?;
+
+        let removed: HashSet<&str> = self
+            .configured
+            .iter()
+            .filter(|(_, k)| kind.is_none_or(|kind| *k == kind))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let freed = |name: &str| {
+            removed.contains(name)
+                || removed
+                    .iter()
+                    .any(|old| registry.aliases_of(old).iter().any(|a| a == name))
+        };
+        for metric in &reloaded.metrics {
+            check_registration(registry, metric, &self.options, freed)?;
+        }
+
+        registry.remove_metrics(removed.iter().copied())?;
+        self.initial_gauges
+            .retain(|(name, _)| !removed.contains(name.as_str()));
+        self.configured
+            .retain(|(_, k)| kind.is_some_and(|kind| *k != kind));
+
+        for metric in &reloaded.metrics {
+            self.register(registry, metric)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that `metric` can be registered in `registry` as configured,
+/// without registering anything.
+///
+/// Names for which `freed` returns true count as available, as they are
+/// about to be removed.
+fn check_registration<B: MetricBackend>(
+    registry: &ObservabilityRegistry<B>,
+    metric: &MetricConfig,
+    options: &LoadOptions,
+    freed: impl Fn(&str) -> bool,
+) -> Result<(), DeserializeError> {
+    if registry.is_frozen() {
+        return Err(DeserializeError::RegistryFrozen(metric.name().to_string()));
+    }
+
+    let mut own_names = HashSet::with_capacity(1 + metric.aliases().len());
+    for name in exported_names(metric) {
+        validate_metric_name(name, registry.max_name_length())?;
+        let taken = if options.case_insensitive_names {
+            registry
+                .metric_names()
+                .any(|existing| existing.eq_ignore_ascii_case(name) && !freed(existing))
+        } else {
+            registry.contains(name) && !freed(name)
+        };
+
+        if taken || !own_names.insert(name_key(name, options)) {
+            return Err(DeserializeError::DuplicateMetricName(name.to_string()));
+        }
+    }
+
+    if let Some(buckets) = metric.buckets() {
+        normalize_and_validate_buckets(metric.name(), buckets.to_vec(), registry.max_buckets())?;
+    }
+    for label in metric.labels() {
+        validate_label_name(label)?;
+    }
+    check_labeled_initial_values(metric)
+}
+
+/// Check `config` without building a registry.
+///
+/// Runs the same structural checks as [`ConfiguredRegistry::from_config_with_options`]
+/// on every enabled metric: name length, histogram buckets and duplicate names.
+///
+/// # Example
+/// ```ignore
+/// let config = RegistryConfig::from_json_str(&content)?;
+/// validate_config(&config, &LoadOptions::default())?;
+/// ```
+pub fn validate_config(
+    config: &RegistryConfig,
+    options: &LoadOptions,
+) -> Result<(), DeserializeError> {
+    check_non_empty(config, options)?;
+
+    let mut seen = HashSet::with_capacity(config.metrics.len());
+
+    for metric in config.metrics.iter().filter(|m| m.is_enabled()) {
+        let name = metric.name();
+        for exported in exported_names(metric) {
+            validate_metric_name(exported, DEFAULT_MAX_NAME_LENGTH)?;
+            if !seen.insert(name_key(exported, options)) {
+                return Err(DeserializeError::DuplicateMetricName(exported.to_string()));
+            }
+        }
+
+        if let MetricConfig::Histogram {
+            buckets: Some(buckets),
+            ..
+        } = metric
+        {
+            normalize_and_validate_buckets(name, buckets.clone(), options.max_buckets)?;
+        }
+        check_labeled_initial_values(metric)?;
+    }
+
+    Ok(())
+}
+
+/// Find the non-fatal issues in the enabled metrics of `config`.
+///
+/// Doesn't check anything [`validate_config`] rejects; run that first if
+/// the config may be invalid.
+pub fn config_warnings(config: &RegistryConfig) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    for metric in config.metrics.iter().filter(|m| m.is_enabled()) {
+        let name = metric.name().to_string();
+        if metric.description().trim().is_empty() {
+            warnings.push(ConfigWarning::EmptyDescription { name: name.clone() });
+        }
+
+        // A trailing +Inf is implied, so it doesn't count as a bucket.
+        // Summary-only histograms don't export their buckets at all.
+        let count = metric
+            .buckets()
+            .filter(|_| !metric.is_summary_only())
+            .map_or(0, |buckets| {
+                buckets.iter().filter(|b| b.is_finite()).count()
+            });
+        if count == 1 {
+            warnings.push(ConfigWarning::SingleBucket { name });
+        } else if count > MANY_BUCKETS_WARNING_THRESHOLD {
+            warnings.push(ConfigWarning::ManyBuckets { name, count });
+        }
+    }
+
+    warnings
+}
+
+/// The metric's name followed by its aliases.
+fn exported_names(metric: &MetricConfig) -> impl Iterator<Item = &str> {
+    std::iter::once(metric.name()).chain(metric.aliases().iter().map(String::as_str))
+}
+
+/// The key a name is compared under when looking for duplicates.
+fn name_key(name: &str, options: &LoadOptions) -> String {
+    if options.case_insensitive_names {
+        name.to_ascii_lowercase()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Apply [`LoadOptions::require_non_empty`].
+fn check_non_empty(config: &RegistryConfig, options: &LoadOptions) -> Result<(), DeserializeError> {
+    if options.require_non_empty && !config.metrics.iter().any(MetricConfig::is_enabled) {
+        return Err(DeserializeError::EmptyConfig);
+    }
+    Ok(())
+}
+
+/// Reject starting values and integer sums on labeled metrics, which have no
+/// single series to apply them to.
+fn check_labeled_initial_values(metric: &MetricConfig) -> Result<(), DeserializeError> {
+    let field = match metric {
+        MetricConfig::Gauge {
+            initial_value,
+            labels,
+            ..
+        } if *initial_value != 0 && !labels.is_empty() => "value",
+        MetricConfig::Histogram {
+            initial_observations,
+            labels,
+            ..
+        } if !initial_observations.is_empty() && !labels.is_empty() => "initial_observations",
+        MetricConfig::Histogram {
+            integer_sum: true,
+            labels,
+            ..
+        } if !labels.is_empty() => "integer_sum",
+        _ => return Ok(()),
+    };
+
+    Err(DeserializeError::Parse(format!(
+        "'{}' has labels, so {} can't be applied",
+        metric.name(),
+        field
+    )))
+}
+
+/// Create a registry sized for the enabled metrics in `config`, with the
+/// bucket limit from `options`.
+fn presized_registry<B: MetricBackend>(
+    config: &RegistryConfig,
+    options: &LoadOptions,
+) -> ObservabilityRegistry<B> {
+    let (mut counters, mut gauges, mut histograms) = (0, 0, 0);
+    for metric in config.metrics.iter().filter(|m| m.is_enabled()) {
+        match metric {
+            MetricConfig::Counter { .. } => counters += 1,
+            MetricConfig::Gauge { .. } => gauges += 1,
+            MetricConfig::Histogram { .. } => histograms += 1,
+        }
+    }
+    // Room for the optional registration failures and config size self-metrics
+    ObservabilityRegistry::with_capacity(
+        counters + 1,
+        gauges + 1 + CONFIG_KIND_METRICS.len(),
+        histograms,
+    )
+    .with_max_buckets(options.max_buckets)
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+
+    fn counter_config(name: &str) -> MetricConfig {
+        MetricConfig::Counter {
+            name: name.into(),
+            description: "A counter".into(),
+            initial_value: 0,
+            value_type: CounterValueType::U64,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_from_config_registers_metrics() {
+        let config = RegistryConfig {
+            metrics: vec![
+                MetricConfig::Counter {
+                    name: "requests_total".into(),
+                    description: "Requests".into(),
+                    initial_value: 5,
+                    value_type: CounterValueType::U64,
+                    aliases: vec![],
+                    metadata: BTreeMap::from([("team".into(), "payments".into())]),
+                    enabled: true,
+                },
+                MetricConfig::Gauge {
+                    name: "queue_depth".into(),
+                    description: "Queue depth".into(),
+                    initial_value: 20,
+                    labels: vec![],
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+                MetricConfig::Histogram {
+                    name: "latency_seconds".into(),
+                    description: "Latency".into(),
+                    buckets: Some(vec![0.1, 1.0]),
+                    labels: vec![],
+                    initial_observations: vec![],
+                    summary_only: false,
+                    integer_sum: false,
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+            ],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+
+        assert_eq!(
+            configured.counter("requests_total").unwrap().get_counter(),
+            5
+        );
+        assert_eq!(configured.gauge("queue_depth").unwrap().get_gauge(), 20);
+        assert!(configured.histogram("latency_seconds").is_some());
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_from_embedded_json() {
+        static EMBEDDED: &str = r#"[
+            { "metric_type": "Counter", "name": "boots_total", "description": "Boots", "value": 1 },
+            { "metric_type": "Gauge", "name": "workers", "description": "Workers", "value": 4 }
+        ]"#;
+
+        let configured =
+            ConfiguredRegistry::<PrometheusBackend>::from_embedded_json(EMBEDDED).unwrap();
+        assert_eq!(configured.counter("boots_total").unwrap().get_counter(), 1);
+        assert_eq!(configured.gauge("workers").unwrap().get_gauge(), 4);
+
+        assert!(matches!(
+            ConfiguredRegistry::<PrometheusBackend>::from_embedded_json("{ not json"),
+            Err(DeserializeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_config_with_warnings() {
+        let histogram = |name: &str, buckets: Vec<f64>| MetricConfig::Histogram {
+            name: name.into(),
+            description: "Latency".into(),
+            buckets: Some(buckets),
+            labels: vec![],
+            initial_observations: vec![],
+            summary_only: false,
+            integer_sum: false,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let mut undescribed = counter_config("jobs_total");
+        if let MetricConfig::Counter { description, .. } = &mut undescribed {
+            description.clear();
+        }
+        let config = RegistryConfig {
+            metrics: vec![
+                undescribed,
+                counter_config("requests_total"),
+                histogram("coarse_seconds", vec![1.0, f64::INFINITY]),
+                histogram("fine_seconds", (1..=30).map(f64::from).collect()),
+                histogram("latency_seconds", vec![0.1, 1.0]),
+            ],
+        };
+
+        let (configured, warnings) =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_warnings(&config).unwrap();
+        assert!(configured.counter("jobs_total").is_some());
+        assert_eq!(
+            warnings,
+            vec![
+                ConfigWarning::EmptyDescription {
+                    name: "jobs_total".into()
+                },
+                ConfigWarning::SingleBucket {
+                    name: "coarse_seconds".into()
+                },
+                ConfigWarning::ManyBuckets {
+                    name: "fine_seconds".into(),
+                    count: 30
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "'jobs_total' has an empty description"
+        );
+    }
+
+    #[test]
+    fn test_reset_gauges_to_initial() {
+        let config = RegistryConfig {
+            metrics: vec![
+                MetricConfig::Gauge {
+                    name: "workers".into(),
+                    description: "Workers".into(),
+                    initial_value: 4,
+                    labels: vec![],
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+                MetricConfig::Counter {
+                    name: "jobs".into(),
+                    description: "Jobs".into(),
+                    initial_value: 1,
+                    value_type: CounterValueType::U64,
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+            ],
+        };
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+
+        let workers = configured.gauge("workers").unwrap();
+        workers.set(11);
+        configured.counter("jobs").unwrap().inc_by(5);
+
+        configured.reset_gauges_to_initial();
+        assert_eq!(workers.get_gauge(), 4);
+        assert_eq!(configured.counter("jobs").unwrap().get_counter(), 6);
+    }
+
+    #[test]
+    fn test_reload_kind_only_replaces_that_kind() {
+        let histogram = |name: &str, buckets: Vec<f64>| MetricConfig::Histogram {
+            name: name.into(),
+            description: "Latency".into(),
+            buckets: Some(buckets),
+            labels: vec![],
+            initial_observations: vec![],
+            summary_only: false,
+            integer_sum: false,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let counter = |description: &str| MetricConfig::Counter {
+            name: "jobs".into(),
+            description: description.into(),
+            initial_value: 0,
+            value_type: CounterValueType::U64,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let mut configured =
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&RegistryConfig {
+                metrics: vec![
+                    counter("Jobs"),
+                    histogram("latency_seconds", vec![0.1, 1.0]),
+                ],
+            })
+            .unwrap();
+        let jobs = configured.counter("jobs").unwrap().clone();
+        jobs.inc_by(3);
+        configured
+            .histogram("latency_seconds")
+            .unwrap()
+            .observe(0.5);
+
+        let new_config = RegistryConfig {
+            metrics: vec![
+                counter("Renamed jobs"),
+                histogram("latency_seconds", vec![0.25, 2.5, 10.0]),
+            ],
+        };
+        configured
+            .reload_kind(&new_config, MetricKind::Histogram)
+            .unwrap();
+
+        assert_eq!(
+            configured.registry().histogram_buckets("latency_seconds"),
+            Some(&[0.25, 2.5, 10.0][..])
+        );
+        assert_eq!(configured.histogram("latency_seconds").unwrap().count(), 0);
+        assert_eq!(configured.counter("jobs").unwrap().get_counter(), 3);
+        assert_eq!(
+            configured.counter("jobs").unwrap().description(),
+            "Jobs",
+            "counters keep their old definition"
+        );
+
+        // Handles taken before the reload still feed the output
+        jobs.inc();
+        let text = configured.registry().render_text().unwrap();
+        assert!(text.contains("jobs_total 4"));
+        assert!(text.contains("latency_seconds_bucket{le=\"2.5\"} 0"));
+        assert!(!text.contains("le=\"0.1\""));
+        assert_eq!(configured.registry().validate_output(), Ok(()));
+
+        // A reloaded histogram can't take a name held by another kind
+        let clash = RegistryConfig {
+            metrics: vec![histogram("jobs", vec![1.0])],
+        };
+        assert!(matches!(
+            configured.reload_kind(&clash, MetricKind::Histogram),
+            Err(DeserializeError::DuplicateMetricName(name)) if name == "jobs"
+        ));
+        assert!(configured.histogram("latency_seconds").is_some());
+    }
+
+    #[test]
+    fn test_reload_kind_reuses_load_options_and_checks_first() {
+        let histogram =
+            |name: &str, buckets: Vec<f64>, labels: Vec<String>| MetricConfig::Histogram {
+                name: name.into(),
+                description: "Latency".into(),
+                buckets: Some(buckets),
+                labels,
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            };
+        let options = LoadOptions {
+            case_insensitive_names: true,
+            max_buckets: 2,
+            ..Default::default()
+        };
+        let mut configured = ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(
+            &RegistryConfig {
+                metrics: vec![
+                    counter_config("jobs"),
+                    histogram("latency_seconds", vec![0.1, 1.0], vec![]),
+                ],
+            },
+            &options,
+        )
+        .unwrap();
+        let reload = |configured: &mut ConfiguredRegistry<PrometheusBackend>, metric| {
+            configured.reload_kind(
+                &RegistryConfig {
+                    metrics: vec![metric],
+                },
+                MetricKind::Histogram,
+            )
+        };
+
+        // Names are compared as they were at load time
+        assert!(matches!(
+            reload(&mut configured, histogram("JOBS", vec![1.0], vec![])),
+            Err(DeserializeError::DuplicateMetricName(name)) if name == "JOBS"
+        ));
+        // So is the bucket limit
+        assert!(reload(
+            &mut configured,
+            histogram("latency_seconds", vec![0.1, 1.0, 10.0], vec![])
+        )
+        .is_err());
+        // Label names are only checked on registration, still before removing
+        assert!(matches!(
+            reload(
+                &mut configured,
+                histogram("latency_seconds", vec![1.0], vec!["__reserved".into()])
+            ),
+            Err(DeserializeError::ReservedLabel(_))
+        ));
+
+        assert_eq!(
+            configured.registry().histogram_buckets("latency_seconds"),
+            Some(&[0.1, 1.0][..])
+        );
+        assert!(reload(
+            &mut configured,
+            histogram("latency_seconds", vec![0.5, 5.0], vec![])
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_to_config_rebuilds_definitions() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("requests_total"),
+                MetricConfig::Gauge {
+                    name: "queue_depth".into(),
+                    description: "Queue depth".into(),
+                    initial_value: 0,
+                    labels: vec![],
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+                MetricConfig::Histogram {
+                    name: "latency_seconds".into(),
+                    description: "Latency".into(),
+                    buckets: Some(vec![0.1, 0.5, 1.0]),
+                    labels: vec![],
+                    initial_observations: vec![],
+                    summary_only: false,
+                    integer_sum: false,
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+            ],
+        };
+        let options = LoadOptions {
+            track_registration_failures: true,
+            ..Default::default()
+        };
+
+        let configured =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(&config, &options)
+                .unwrap();
+        configured.counter("requests_total").unwrap().inc_by(10);
+
+        // The self-metric is not part of the reconstructed config
+        assert_eq!(configured.to_config(), config);
+    }
+
+    #[test]
+    fn test_from_config_registers_labeled_histogram() {
+        let config = RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "request_duration_seconds".into(),
+                description: "Request duration".into(),
+                buckets: Some(vec![0.1, 1.0]),
+                labels: vec!["route".into()],
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        let latency = configured
+            .labeled_histogram("request_duration_seconds")
+            .unwrap();
+        latency.observe(&["/users"], 0.05).unwrap();
+        latency.observe(&["/orders"], 0.5).unwrap();
+        assert!(latency.observe(&["/users", "GET"], 0.5).is_err());
+
+        let output = configured.registry().render().unwrap();
+        let output = output.as_str().unwrap();
+        assert_eq!(
+            output
+                .matches("# TYPE request_duration_seconds histogram")
+                .count(),
+            1
+        );
+        assert!(output.contains("request_duration_seconds_count{route=\"/users\"} 1"));
+        assert!(output.contains("request_duration_seconds_count{route=\"/orders\"} 1"));
+        assert!(output.contains("le=\"0.1\""));
+
+        // Labels survive the round trip back to config
+        assert_eq!(configured.to_config(), config);
+    }
+
+    #[test]
+    fn test_from_config_registers_labeled_gauge() {
+        let gauge = |initial_value: i64| RegistryConfig {
+            metrics: vec![MetricConfig::Gauge {
+                name: "backend_connections".into(),
+                description: "Open connections per backend".into(),
+                initial_value,
+                labels: vec!["host".into()],
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+        let config = gauge(0);
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        let connections = configured.labeled_gauge("backend_connections").unwrap();
+        connections.set(&["db-1"], 12).unwrap();
+        connections.set(&["db-2"], 4).unwrap();
+        connections.inc(&["db-2"]).unwrap();
+        connections.dec(&["db-1"]).unwrap();
+        assert_eq!(connections.get(&["db-1"]).unwrap(), 11);
+        assert!(connections.set(&[], 1).is_err());
+
+        let output = configured.registry().render().unwrap();
+        let output = output.as_str().unwrap();
+        assert_eq!(
+            output.matches("# TYPE backend_connections gauge").count(),
+            1
+        );
+        assert!(output.contains("backend_connections{host=\"db-1\"} 11\n"));
+        assert!(output.contains("backend_connections{host=\"db-2\"} 5\n"));
+
+        assert_eq!(configured.to_config(), config);
+
+        // A starting value has no series to go to
+        assert!(matches!(
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&gauge(3)),
+            Err(DeserializeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_config_exports_aliases() {
+        let config = RegistryConfig {
+            metrics: vec![
+                MetricConfig::Counter {
+                    name: "http_requests_total".into(),
+                    description: "Requests".into(),
+                    initial_value: 0,
+                    value_type: CounterValueType::U64,
+                    aliases: vec!["requests_total".into()],
+                    metadata: BTreeMap::new(),
+                    enabled: true,
+                },
+                counter_config("errors_total"),
+            ],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        configured.counter("http_requests_total").unwrap().inc_by(3);
+
+        let output = configured.registry().render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(output.contains("# TYPE http_requests_total counter\n"));
+        assert!(output.contains("# TYPE requests_total counter\n"));
+        assert!(output.contains("http_requests_total_total 3\n"));
+        assert!(output.contains("\nrequests_total_total 3\n"));
+        assert_eq!(configured.to_config(), config);
+
+        // An alias takes its name like any other metric
+        let mut clashing = config.clone();
+        clashing.metrics.push(counter_config("requests_total"));
+        for result in [
+            validate_config(&clashing, &LoadOptions::default()),
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&clashing).map(|_| ()),
+        ] {
+            assert!(matches!(
+                result,
+                Err(DeserializeError::DuplicateMetricName(name)) if name == "requests_total"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_from_config_replays_initial_observations() {
+        let histogram = |labels: Vec<String>| RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets: Some(vec![0.15, 1.0]),
+                labels,
+                initial_observations: vec![0.1, 0.2, 0.3],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+
+        let configured =
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&histogram(vec![])).unwrap();
+        let latency = configured.histogram("latency_seconds").unwrap();
+        assert_eq!(latency.count(), 3);
+        assert!((latency.sum() - 0.6).abs() < 1e-9);
+        assert_eq!(latency.bucket_counts()[0], (0.15, 1));
+
+        let labeled = histogram(vec!["route".into()]);
+        assert!(matches!(
+            validate_config(&labeled, &LoadOptions::default()),
+            Err(DeserializeError::Parse(_))
+        ));
+        assert!(ConfiguredRegistry::<PrometheusBackend>::from_config(&labeled).is_err());
+    }
+
+    #[test]
+    fn test_from_config_skips_disabled_metrics() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("requests_total"),
+                MetricConfig::Gauge {
+                    name: "expensive_gauge".into(),
+                    description: "Disabled during incidents".into(),
+                    initial_value: 1,
+                    labels: vec![],
+                    aliases: vec![],
+                    metadata: BTreeMap::new(),
+                    enabled: false,
+                },
+            ],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        assert!(configured.counter("requests_total").is_some());
+        assert!(configured.gauge("expensive_gauge").is_none());
+
+        let output = configured.registry().render().unwrap();
+        assert!(!output.as_str().unwrap().contains("expensive_gauge"));
+    }
+
+    #[test]
+    fn test_from_config_limits_bucket_count() {
+        let histogram = |bucket_count: usize| RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets: Some((1..=bucket_count).map(|i| i as f64).collect()),
+                labels: vec![],
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+
+        let result = ConfiguredRegistry::<PrometheusBackend>::from_config(&histogram(10_000));
+        assert!(matches!(
+            result,
+            Err(DeserializeError::InvalidBuckets { name, .. }) if name == "latency_seconds"
+        ));
+        assert!(ConfiguredRegistry::<PrometheusBackend>::from_config(&histogram(20)).is_ok());
+
+        let options = LoadOptions {
+            max_buckets: 10,
+            ..Default::default()
+        };
+        assert!(
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(
+                &histogram(20),
+                &options
+            )
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "json-config")]
+    #[test]
+    fn test_f64_counter_from_config() {
+        use crate::core::metrics::MetricError;
+
+        let config = RegistryConfig::from_json_str(
+            r#"[{ "metric_type": "Counter", "name": "cost_dollars", "description": "Spend", "value_type": "f64" }]"#,
+        )
+        .unwrap();
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        assert!(configured.counter("cost_dollars").is_none());
+
+        let cost = configured.counter_f64("cost_dollars").unwrap();
+        cost.try_inc_by_f64(0.5).unwrap();
+        cost.try_inc_by_f64(0.5).unwrap();
+        assert_eq!(
+            cost.try_inc_by_f64(-1.0),
+            Err(MetricError::NegativeIncrement)
+        );
+
+        let output = configured.registry().render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("cost_dollars_total 1.0\n"));
+        assert_eq!(configured.to_config(), config);
+    }
+
+    #[test]
+    fn test_summary_only_histogram_renders_without_buckets() {
+        let histogram = |name: &str, summary_only: bool| MetricConfig::Histogram {
+            name: name.into(),
+            description: "Latency".into(),
+            buckets: Some(vec![0.1, 1.0]),
+            labels: vec![],
+            initial_observations: vec![0.05, 0.5],
+            summary_only,
+            integer_sum: false,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let config = RegistryConfig {
+            metrics: vec![
+                histogram("db_seconds", true),
+                histogram("http_seconds", false),
+            ],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        let output = configured.registry().render().unwrap();
+        let text = output.as_str().unwrap();
+
+        assert!(!text.contains("db_seconds_bucket"));
+        assert!(!text
+            .lines()
+            .any(|line| line.starts_with("db_seconds") && line.contains("le=")));
+        assert!(text.contains("db_seconds_sum 0.55\n"));
+        assert!(text.contains("db_seconds_count 2\n"));
+        assert!(text.contains("http_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(configured.to_config().metrics[0].is_summary_only());
+    }
+
+    #[test]
+    fn test_integer_sum_histogram_renders_exact_sum() {
+        let histogram = |labels: Vec<String>| MetricConfig::Histogram {
+            name: "upload_bytes".into(),
+            description: "Upload sizes".into(),
+            buckets: Some(vec![1024.0]),
+            labels,
+            initial_observations: vec![],
+            summary_only: false,
+            integer_sum: true,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let config = RegistryConfig {
+            metrics: vec![histogram(vec![])],
+        };
+
+        let configured = ConfiguredRegistry::<PrometheusBackend>::from_config(&config).unwrap();
+        let uploads = configured
+            .registry()
+            .find_histogram("upload_bytes")
+            .unwrap();
+        // Each value is exact as an f64, but their sum is not
+        uploads.observe(9_007_199_254_740_994.0);
+        uploads.observe(9_007_199_254_740_994.0);
+        uploads.observe(1.0);
+
+        let output = configured.registry().render().unwrap();
+        let sum_line = output
+            .as_str()
+            .unwrap()
+            .lines()
+            .find(|line| line.starts_with("upload_bytes_sum"))
+            .unwrap()
+            .to_string();
+        assert_eq!(sum_line, "upload_bytes_sum 18014398509481989");
+        assert_eq!(uploads.integer_sum(), Some(18_014_398_509_481_989));
+        assert!(configured.to_config().metrics[0].has_integer_sum());
+
+        let labeled = RegistryConfig {
+            metrics: vec![histogram(vec!["route".into()])],
+        };
+        assert!(ConfiguredRegistry::<PrometheusBackend>::from_config(&labeled).is_err());
+    }
+
+    #[test]
+    fn test_from_config_rejects_empty_buckets() {
+        let histogram = |buckets: Option<Vec<f64>>| RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets,
+                labels: vec![],
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+
+        let err = ConfiguredRegistry::<PrometheusBackend>::from_config(&histogram(Some(vec![])))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid buckets for histogram 'latency_seconds': at least one finite bound is required"
+        );
+
+        // Leaving buckets out falls back to the defaults
+        let configured =
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&histogram(None)).unwrap();
+        let buckets = configured
+            .registry()
+            .histogram_buckets("latency_seconds")
+            .unwrap();
+        assert!(!buckets.is_empty());
+    }
+
+    #[test]
+    fn test_validate_config_matches_from_config() {
+        let unsorted = RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets: Some(vec![1.0, 0.5]),
+                labels: vec![],
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+        assert!(matches!(
+            validate_config(&unsorted, &LoadOptions::default()),
+            Err(DeserializeError::InvalidBuckets { .. })
+        ));
+        assert!(matches!(
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&unsorted),
+            Err(DeserializeError::InvalidBuckets { .. })
+        ));
+
+        let duplicates = RegistryConfig {
+            metrics: vec![counter_config("a_total"), counter_config("A_total")],
+        };
+        assert!(validate_config(&duplicates, &LoadOptions::default()).is_ok());
+        let options = LoadOptions {
+            case_insensitive_names: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_config(&duplicates, &options),
+            Err(DeserializeError::DuplicateMetricName(_))
+        ));
+    }
+
+    #[test]
+    fn test_every_histogram_path_rejects_unsorted_buckets() {
+        use crate::core::builder::RegistryBuilder;
+
+        let unsorted = vec![1.0, 0.5];
+        let config = RegistryConfig {
+            metrics: vec![MetricConfig::Histogram {
+                name: "latency_seconds".into(),
+                description: "Latency".into(),
+                buckets: Some(unsorted.clone()),
+                labels: vec![],
+                initial_observations: vec![],
+                summary_only: false,
+                integer_sum: false,
+                aliases: vec![],
+                metadata: BTreeMap::new(),
+                enabled: true,
+            }],
+        };
+        let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+
+        let errors = [
+            validate_config(&config, &LoadOptions::default()).err(),
+            ConfiguredRegistry::<PrometheusBackend>::from_config(&config).err(),
+            ConfiguredRegistry::<PrometheusBackend>::from_config_lenient(&config)
+                .1
+                .into_iter()
+                .next(),
+            registry
+                .histogram_with_buckets("latency_seconds", "Latency", unsorted.clone())
+                .err(),
+            registry
+                .labeled_histogram_with_buckets(
+                    "latency_seconds",
+                    "Latency",
+                    vec!["route".into()],
+                    unsorted.clone(),
+                )
+                .err(),
+            RegistryBuilder::<PrometheusBackend>::new()
+                .histogram_with_buckets("latency_seconds", "Latency", unsorted.clone())
+                .err(),
+            crate::backends::prometheus::histogram_with_buckets(
+                "latency_seconds",
+                "Latency",
+                unsorted.clone().into_iter(),
+            )
+            .err(),
+            crate::backends::prometheus::Histogram::with_buckets("latency_seconds", unsorted).err(),
+        ];
+
+        let expected = "Invalid buckets for histogram 'latency_seconds': \
+                        bounds must be strictly increasing, but 1 is followed by 0.5";
+        for error in errors {
+            let error = error.expect("unsorted buckets were accepted");
+            assert!(matches!(error, DeserializeError::InvalidBuckets { .. }));
+            assert_eq!(error.to_string(), expected);
+        }
+        assert!(!registry.contains("latency_seconds"));
+    }
+
+    #[test]
+    fn test_require_non_empty() {
+        let empty = RegistryConfig { metrics: vec![] };
+        assert!(ConfiguredRegistry::<PrometheusBackend>::from_config(&empty).is_ok());
+
+        let options = LoadOptions {
+            require_non_empty: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(&empty, &options),
+            Err(DeserializeError::EmptyConfig)
+        ));
+
+        let mut disabled = counter_config("requests_total");
+        if let MetricConfig::Counter { enabled, .. } = &mut disabled {
+            *enabled = false;
+        }
+        let all_disabled = RegistryConfig {
+            metrics: vec![disabled],
+        };
+        let (_, errors) = ConfiguredRegistry::<PrometheusBackend>::from_config_lenient_with_options(
+            &all_disabled,
+            &options,
+        );
+        assert!(matches!(errors.as_slice(), [DeserializeError::EmptyConfig]));
+
+        let config = RegistryConfig {
+            metrics: vec![counter_config("requests_total")],
+        };
+        assert!(
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(&config, &options)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_config_gauges_count_registered_metrics() {
+        let histogram = MetricConfig::Histogram {
+            name: "latency_seconds".into(),
+            description: "Latency".into(),
+            buckets: None,
+            labels: vec![],
+            initial_observations: vec![],
+            summary_only: false,
+            integer_sum: false,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let gauge = MetricConfig::Gauge {
+            name: "queue_depth".into(),
+            description: "Queue depth".into(),
+            initial_value: 0,
+            labels: vec![],
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+            enabled: true,
+        };
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("a_total"),
+                counter_config("b_total"),
+                counter_config("a_total"),
+                gauge,
+                histogram,
+            ],
+        };
+
+        let options = LoadOptions {
+            register_config_gauges: true,
+            ..Default::default()
+        };
+        let (configured, errors) =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_lenient_with_options(
+                &config, &options,
+            );
+        assert_eq!(errors.len(), 1);
+
+        let value = |name: &str| configured.registry().find_gauge(name).unwrap().get_gauge();
+        assert_eq!(value(CONFIG_METRICS_METRIC), 4);
+        assert_eq!(value("config_counters"), 2);
+        assert_eq!(value("config_gauges"), 1);
+        assert_eq!(value("config_histograms"), 1);
+
+        let plain = ConfiguredRegistry::<PrometheusBackend>::from_config(&RegistryConfig {
+            metrics: vec![counter_config("a_total")],
+        })
+        .unwrap();
+        assert!(plain.registry().find_gauge(CONFIG_METRICS_METRIC).is_none());
+    }
+
+    #[test]
+    fn test_from_config_rejects_duplicates() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("requests_total"),
+                counter_config("requests_total"),
+            ],
+        };
+
+        let result = ConfiguredRegistry::<PrometheusBackend>::from_config(&config);
+        assert!(matches!(
+            result,
+            Err(DeserializeError::DuplicateMetricName(_))
+        ));
+    }
+
+    #[test]
+    fn test_case_insensitive_duplicate_detection() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("Requests_Total"),
+                counter_config("requests_total"),
+            ],
+        };
+
+        // Case-sensitive by default
+        assert!(ConfiguredRegistry::<PrometheusBackend>::from_config(&config).is_ok());
+
+        let options = LoadOptions {
+            case_insensitive_names: true,
+            ..Default::default()
+        };
+        let result =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_with_options(&config, &options);
+        assert!(matches!(
+            result,
+            Err(DeserializeError::DuplicateMetricName(name)) if name == "requests_total"
+        ));
+    }
+
+    #[test]
+    fn test_lenient_load_tracks_registration_failures() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("requests_total"),
+                counter_config("requests_total"),
+                counter_config(&"a".repeat(1000)),
+                counter_config("errors_total"),
+            ],
+        };
+        let options = LoadOptions {
+            track_registration_failures: true,
+            ..Default::default()
+        };
+
+        let (configured, errors) =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_lenient_with_options(
+                &config, &options,
+            );
+
+        assert_eq!(errors.len(), 2);
+        assert!(configured.counter("errors_total").is_some());
+        assert_eq!(
+            configured
+                .counter(REGISTRATION_FAILURES_METRIC)
+                .unwrap()
+                .get_counter(),
+            2
+        );
+
+        let output = configured.registry().render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains(REGISTRATION_FAILURES_METRIC));
+    }
+
+    #[test]
+    fn test_lenient_load_without_tracking_has_no_self_metric() {
+        let config = RegistryConfig {
+            metrics: vec![
+                counter_config("requests_total"),
+                counter_config("requests_total"),
+            ],
+        };
+
+        let (configured, errors) =
+            ConfiguredRegistry::<PrometheusBackend>::from_config_lenient(&config);
+
+        assert_eq!(errors.len(), 1);
+        assert!(configured.counter(REGISTRATION_FAILURES_METRIC).is_none());
+    }
+}
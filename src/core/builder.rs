@@ -0,0 +1,189 @@
+//! Typestate builder for registries that must not be empty.
+//!
+//! A registry served without any metrics is almost always a wiring mistake.
+//! [`RegistryBuilder`] tracks in its type whether a metric has been added,
+//! and only offers [`finish`](RegistryBuilder::finish) once one has.
+
+use super::deserialise::DeserializeError;
+use super::registry::{MetricBackend, ObservabilityRegistry};
+use std::marker::PhantomData;
+
+/// Builder state before any metric has been added.
+#[derive(Debug, Clone, Copy)]
+pub struct Empty;
+
+/// Builder state once at least one metric has been added.
+#[derive(Debug, Clone, Copy)]
+pub struct Populated;
+
+/// Builds an [`ObservabilityRegistry`] holding at least one metric.
+///
+/// Every metric method moves the builder into the [`Populated`] state;
+/// calling `finish` on an [`Empty`] builder does not compile. Handles are
+/// looked up on the finished registry with `find_counter` and friends.
+///
+/// # Example
+/// ```ignore
+/// let registry = RegistryBuilder::<PrometheusBackend>::new()
+///     .counter("requests_total", "Requests")?
+///     .histogram("request_duration_seconds", "Request latency")?
+///     .finish();
+///
+/// let requests = registry.find_counter("requests_total").cloned().unwrap();
+/// ```
+///
+/// An empty builder cannot be finished:
+/// ```compile_fail
+/// use observability_kit::backends::prometheus::PrometheusBackend;
+/// use observability_kit::core::builder::RegistryBuilder;
+///
+/// let registry = RegistryBuilder::<PrometheusBackend>::new().finish();
+/// ```
+pub struct RegistryBuilder<B: MetricBackend, S = Empty> {
+    registry: ObservabilityRegistry<B>,
+    _state: PhantomData<S>,
+}
+
+impl<B: MetricBackend> RegistryBuilder<B, Empty> {
+    /// Create a builder for an empty registry.
+    pub fn new() -> Self {
+        Self::from_registry(ObservabilityRegistry::new())
+    }
+
+    /// Start from an existing, possibly pre-configured registry.
+    ///
+    /// The builder still counts as empty until a metric is added through it.
+    pub fn from_registry(registry: ObservabilityRegistry<B>) -> Self {
+        Self {
+            registry,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<B: MetricBackend> Default for RegistryBuilder<B, Empty> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: MetricBackend, S> RegistryBuilder<B, S> {
+    /// Expose a constant `build_info` gauge with the given version and commit.
+    ///
+    /// See [`ObservabilityRegistry::with_build_info`]. The gauge alone does
+    /// not make the builder populated.
+    pub fn with_build_info(mut self, version: &str, commit: &str) -> Self {
+        self.registry = self.registry.with_build_info(version, commit);
+        self
+    }
+
+    /// Add a counter.
+    pub fn counter(
+        mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<RegistryBuilder<B, Populated>, DeserializeError> {
+        self.registry.counter(name, help)?;
+        Ok(self.populated())
+    }
+
+    /// Add a gauge.
+    pub fn gauge(
+        mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<RegistryBuilder<B, Populated>, DeserializeError> {
+        self.registry.gauge(name, help)?;
+        Ok(self.populated())
+    }
+
+    /// Add a histogram with default latency buckets.
+    pub fn histogram(
+        mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<RegistryBuilder<B, Populated>, DeserializeError> {
+        self.registry.histogram(name, help)?;
+        Ok(self.populated())
+    }
+
+    /// Add a histogram with custom buckets.
+    pub fn histogram_with_buckets(
+        mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+        buckets: Vec<f64>,
+    ) -> Result<RegistryBuilder<B, Populated>, DeserializeError> {
+        self.registry.histogram_with_buckets(name, help, buckets)?;
+        Ok(self.populated())
+    }
+
+    fn populated(self) -> RegistryBuilder<B, Populated> {
+        RegistryBuilder {
+            registry: self.registry,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<B: MetricBackend> RegistryBuilder<B, Populated> {
+    /// Finish building, returning the populated registry.
+    pub fn finish(self) -> ObservabilityRegistry<B> {
+        self.registry
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+
+    #[test]
+    fn test_populated_builder_finishes() {
+        let registry = RegistryBuilder::<PrometheusBackend>::new()
+            .counter("requests_total", "Requests")
+            .unwrap()
+            .gauge("queue_depth", "Queue depth")
+            .unwrap()
+            .histogram_with_buckets("latency_seconds", "Latency", vec![0.1, 1.0])
+            .unwrap()
+            .finish();
+
+        assert!(registry.find_counter("requests_total").is_some());
+        assert!(registry.find_gauge("queue_depth").is_some());
+        assert_eq!(
+            registry.histogram_buckets("latency_seconds"),
+            Some(&[0.1, 1.0][..])
+        );
+    }
+
+    #[test]
+    fn test_builder_with_build_info() {
+        let registry = RegistryBuilder::<PrometheusBackend>::new()
+            .with_build_info("0.1.0", "")
+            .counter("requests_total", "Requests")
+            .unwrap()
+            .finish();
+
+        let output = registry.render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(output.contains("# TYPE build_info gauge\nbuild_info{version=\"0.1.0\"} 1\n"));
+        assert!(output.ends_with("# EOF\n"));
+        assert!(registry.contains("build_info"));
+
+        let registry = registry.with_build_info("0.2.0", "3f2c1ab");
+        let output = registry.render().unwrap();
+        let output = output.as_str().unwrap();
+        assert!(output.contains("build_info{version=\"0.2.0\",commit=\"3f2c1ab\"} 1\n"));
+        assert_eq!(output.matches("# TYPE build_info gauge").count(), 1);
+    }
+
+    #[test]
+    fn test_builder_surfaces_registration_errors() {
+        let result = RegistryBuilder::<PrometheusBackend>::new().counter("a".repeat(500), "Long");
+        assert!(matches!(
+            result,
+            Err(DeserializeError::InvalidMetricName { .. })
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+//! Time sources for metric timestamps.
+//!
+//! Metrics record when they were created and last updated, and timers and
+//! rate windows measure how much time passed. Reading the time through a
+//! [`Clock`] lets tests swap the system clock for a [`MockClock`] (feature:
+//! `test-utils`) and move time forward without sleeping.
+
+use std::fmt::Debug;
+use std::time::{Instant, SystemTime};
+
+#[cfg(any(test, feature = "test-utils"))]
+use std::sync::{Arc, Mutex};
+#[cfg(any(test, feature = "test-utils"))]
+use std::time::Duration;
+
+/// A source of wall-clock and monotonic time.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+
+    /// A reading of a monotonic clock, for measuring durations.
+    ///
+    /// Unlike [`now`](Self::now), this never goes back when the wall clock
+    /// is set back.
+    fn monotonic(&self) -> Instant;
+}
+
+/// The system clock, used unless another clock is injected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn monotonic(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one handle and pass
+/// another to the registry. [`advance`](Self::advance) moves both the wall
+/// clock and the monotonic clock; [`set`](Self::set) only moves the wall
+/// clock, like setting the system time.
+///
+/// # Example
+/// ```ignore
+/// let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+/// let mut registry = ObservabilityRegistry::<PrometheusBackend>::new()
+///     .with_clock(Arc::new(clock.clone()));
+///
+/// clock.advance(Duration::from_secs(60));
+/// ```
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<MockTime>>,
+}
+
+/// The wall-clock and monotonic readings of a [`MockClock`].
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug)]
+struct MockTime {
+    wall: SystemTime,
+    monotonic: Instant,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl MockClock {
+    /// Create a clock stopped at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(MockTime {
+                wall: start,
+                monotonic: Instant::now(),
+            })),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        now.wall += duration;
+        now.monotonic += duration;
+    }
+
+    /// Set the wall clock to `time`, leaving the monotonic clock alone.
+    pub fn set(&self, time: SystemTime) {
+        self.now.lock().unwrap().wall = time;
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.now.lock().unwrap().wall
+    }
+
+    fn monotonic(&self) -> Instant {
+        self.now.lock().unwrap().monotonic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_clones_share_time() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let handle = clock.clone();
+
+        handle.advance(Duration::from_secs(90));
+        assert_eq!(
+            clock.now(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(90)
+        );
+
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(handle.now(), SystemTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_mock_clock_monotonic_only_moves_forward() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+        let start = clock.monotonic();
+
+        clock.advance(Duration::from_secs(5));
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(clock.monotonic() - start, Duration::from_secs(5));
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,198 @@
+//! Series computed at scrape time.
+//!
+//! Some values are cheaper to read when asked for than to keep up to date,
+//! e.g. the size of a cache or the number of open file descriptors. A
+//! [`Collector`] registered with
+//! [`ObservabilityRegistry::register_collector`](super::registry::ObservabilityRegistry::register_collector)
+//! is asked for its series on every render, and they are added to the output
+//! alongside the registered metrics.
+
+use super::deserialise::{escape_help, escape_label_value};
+use super::metrics::MetricKind;
+use std::fmt::Write;
+
+/// A single sample reported by a [`Collector`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesInfo {
+    name: String,
+    help: String,
+    kind: MetricKind,
+    labels: Vec<(String, String)>,
+    value: f64,
+}
+
+impl SeriesInfo {
+    /// A gauge sample.
+    pub fn gauge(name: impl Into<String>, help: impl Into<String>, value: f64) -> Self {
+        Self::new(name.into(), help.into(), MetricKind::Gauge, value)
+    }
+
+    /// A counter sample; rendered with a `_total` suffix.
+    pub fn counter(name: impl Into<String>, help: impl Into<String>, value: f64) -> Self {
+        Self::new(name.into(), help.into(), MetricKind::Counter, value)
+    }
+
+    fn new(name: String, help: String, kind: MetricKind, value: f64) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            labels: Vec::new(),
+            value,
+        }
+    }
+
+    /// Add a label to the sample.
+    pub fn with_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((name.into(), value.into()));
+        self
+    }
+
+    /// Get the metric family name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the help text.
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    /// Get the metric kind.
+    pub fn kind(&self) -> MetricKind {
+        self.kind
+    }
+
+    /// Get the label pairs, in the order they were added.
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.labels
+    }
+
+    /// Get the sample value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+/// A source of series read at render time.
+///
+/// Histograms aren't supported; register those with the registry instead.
+///
+/// # Example
+/// ```ignore
+/// struct CacheCollector(Arc<Cache>);
+///
+/// impl Collector for CacheCollector {
+///     fn collect(&self) -> Vec<SeriesInfo> {
+///         vec![
+///             SeriesInfo::gauge("cache_entries", "Cached entries", self.0.len() as f64),
+///             SeriesInfo::counter("cache_evictions", "Evicted entries", self.0.evictions() as f64),
+///         ]
+///     }
+/// }
+///
+/// registry.register_collector(CacheCollector(cache.clone()));
+/// ```
+pub trait Collector: Send + Sync + 'static {
+    /// Report the current series.
+    fn collect(&self) -> Vec<SeriesInfo>;
+}
+
+impl<F> Collector for F
+where
+    F: Fn() -> Vec<SeriesInfo> + Send + Sync + 'static,
+{
+    fn collect(&self) -> Vec<SeriesInfo> {
+        self()
+    }
+}
+
+/// Render `series` as text exposition families, one per name.
+///
+/// Families keep the order their names first appear in; the first sample of
+/// a family sets its help text and type.
+pub(crate) fn render_series(series: &[SeriesInfo]) -> String {
+    let mut families: Vec<(&SeriesInfo, Vec<&SeriesInfo>)> = Vec::new();
+    for sample in series {
+        match families
+            .iter_mut()
+            .find(|(first, _)| first.name == sample.name)
+        {
+            Some((_, samples)) => samples.push(sample),
+            None => families.push((sample, vec![sample])),
+        }
+    }
+
+    let mut output = String::new();
+    for (first, samples) in families {
+        let _ = writeln!(
+            output,
+            "# HELP {} {}.",
+            first.name,
+            escape_help(&first.help)
+        );
+        let _ = writeln!(output, "# TYPE {} {}", first.name, first.kind);
+        let suffix = match first.kind {
+            MetricKind::Counter => "_total",
+            _ => "",
+        };
+
+        for sample in samples {
+            output.push_str(&first.name);
+            output.push_str(suffix);
+            if !sample.labels.is_empty() {
+                let labels: Vec<String> = sample
+                    .labels
+                    .iter()
+                    .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+                    .collect();
+                let _ = write!(output, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(output, " {}", format_value(sample.value));
+        }
+    }
+    output
+}
+
+/// Format a sample value the way the exposition format spells it.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_series_groups_by_name() {
+        let series = vec![
+            SeriesInfo::gauge("pool_size", "Pool size", 4.0).with_label("pool", "db"),
+            SeriesInfo::counter("pool_waits", "Pool waits", 12.0),
+            SeriesInfo::gauge("pool_size", "Pool size", 2.5).with_label("pool", "cache"),
+        ];
+
+        assert_eq!(
+            render_series(&series),
+            "# HELP pool_size Pool size.\n\
+             # TYPE pool_size gauge\n\
+             pool_size{pool=\"db\"} 4\n\
+             pool_size{pool=\"cache\"} 2.5\n\
+             # HELP pool_waits Pool waits.\n\
+             # TYPE pool_waits counter\n\
+             pool_waits_total 12\n"
+        );
+    }
+
+    #[test]
+    fn test_format_value_special_values() {
+        assert_eq!(format_value(f64::NAN), "NaN");
+        assert_eq!(format_value(f64::INFINITY), "+Inf");
+        assert_eq!(format_value(f64::NEG_INFINITY), "-Inf");
+    }
+}
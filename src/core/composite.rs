@@ -0,0 +1,244 @@
+//! Combined rendering across several registries.
+
+use super::deserialise::{escape_label_value, DeserializeError};
+use super::registry::{MetricBackend, ObservabilityRegistry};
+use super::renderer::split_families;
+use std::collections::HashMap;
+
+/// Label added to every series to tell the shards apart.
+pub const SHARD_LABEL: &str = "shard";
+
+/// A read-only view rendering several registries as one exposition.
+///
+/// Useful when metrics are sharded across registries (e.g. one per worker)
+/// but should be scraped together. The shards are borrowed, not merged, so
+/// each keeps its own lifecycle. Every series is labeled with the name of
+/// its shard, so families present in several shards stay distinct series.
+///
+/// # Example
+/// ```ignore
+/// let composite = CompositeRegistry::new()
+///     .with_shard("worker-a", &shard_a)
+///     .with_shard("worker-b", &shard_b);
+///
+/// println!("{}", composite.render()?);
+/// ```
+pub struct CompositeRegistry<'a, B: MetricBackend> {
+    shards: Vec<(String, &'a ObservabilityRegistry<B>)>,
+}
+
+impl<'a, B: MetricBackend> CompositeRegistry<'a, B> {
+    /// Create an empty composite.
+    pub fn new() -> Self {
+        Self { shards: Vec::new() }
+    }
+
+    /// Add a shard to the composite, labeling its series `shard="<name>"`.
+    pub fn with_shard(
+        mut self,
+        name: impl Into<String>,
+        shard: &'a ObservabilityRegistry<B>,
+    ) -> Self {
+        self.add_shard(name, shard);
+        self
+    }
+
+    /// Add a shard to an existing composite, labeling its series `shard="<name>"`.
+    pub fn add_shard(&mut self, name: impl Into<String>, shard: &'a ObservabilityRegistry<B>) {
+        self.shards.push((name.into(), shard));
+    }
+
+    /// Number of shards in the composite.
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns true if the composite has no shards.
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// Render every shard as a single exposition.
+    ///
+    /// Families present in several shards are emitted once: the `# HELP` /
+    /// `# TYPE` lines come from the first shard defining the family, followed
+    /// by the series of every shard in shard order, each labeled with its
+    /// shard.
+    ///
+    /// Fails if two shards share a name, if shards disagree on the type of a
+    /// family, or if a series already carries a `shard` label.
+    pub fn render(&self) -> Result<String, DeserializeError> {
+        for (index, (name, _)) in self.shards.iter().enumerate() {
+            if self.shards[..index].iter().any(|(other, _)| other == name) {
+                return Err(DeserializeError::Render(format!(
+                    "shard '{}' is added more than once",
+                    name
+                )));
+            }
+        }
+
+        let texts = self
+            .shards
+            .iter()
+            .map(|(name, shard)| Ok((name.as_str(), shard.render_text()?)))
+            .collect::<Result<Vec<_>, DeserializeError>>()?;
+
+        let mut order: Vec<&str> = Vec::new();
+        let mut families: HashMap<&str, (Vec<&str>, Vec<String>)> = HashMap::new();
+        let mut trailer: Vec<&str> = Vec::new();
+
+        for (shard, text) in &texts {
+            let exposition = split_families(text);
+
+            for family in exposition.families {
+                let (headers, samples) = families.entry(family.name).or_insert_with(|| {
+                    order.push(family.name);
+                    (Vec::new(), Vec::new())
+                });
+                let first_definition = headers.is_empty();
+
+                for line in family.lines {
+                    if !line.starts_with('#') {
+                        samples.push(label_sample(line, shard)?);
+                    } else if first_definition {
+                        headers.push(line);
+                    } else if line.starts_with("# TYPE ") && !headers.contains(&line) {
+                        return Err(DeserializeError::Render(format!(
+                            "shard '{}' has a different type for '{}'",
+                            shard, family.name
+                        )));
+                    }
+                }
+            }
+
+            for line in exposition.trailer {
+                if !trailer.contains(&line) {
+                    trailer.push(line);
+                }
+            }
+        }
+
+        let mut output = String::new();
+        for name in order {
+            let (headers, samples) = &families[name];
+            output.extend(headers.iter().copied());
+            output.extend(samples.iter().map(String::as_str));
+        }
+        output.extend(trailer);
+
+        Ok(output)
+    }
+}
+
+/// Add the `shard` label to a sample line.
+fn label_sample(line: &str, shard: &str) -> Result<String, DeserializeError> {
+    let label = format!("{}=\"{}\"", SHARD_LABEL, escape_label_value(shard));
+    let name_end = line.find(['{', ' ']).unwrap_or(line.len());
+    let (name, rest) = line.split_at(name_end);
+
+    match rest.strip_prefix('{') {
+        Some(labels)
+            if labels.starts_with(&format!("{}=", SHARD_LABEL))
+                || labels.contains(&format!(",{}=", SHARD_LABEL)) =>
+        {
+            Err(DeserializeError::Render(format!(
+                "series '{}' of shard '{}' already has a '{}' label",
+                name, shard, SHARD_LABEL
+            )))
+        }
+        Some(labels) if labels.starts_with('}') => Ok(format!("{}{{{}{}", name, label, labels)),
+        Some(labels) => Ok(format!("{}{{{},{}", name, label, labels)),
+        None => Ok(format!("{}{{{}}}{}", name, label, rest)),
+    }
+}
+
+impl<B: MetricBackend> Default for CompositeRegistry<'_, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusRegistry;
+
+    #[test]
+    fn test_composite_merges_shared_families() {
+        let mut shard_a = PrometheusRegistry::new();
+        shard_a.counter("jobs_total", "Jobs").unwrap().inc_by(2);
+        shard_a.gauge("shard_a_depth", "Depth of A").unwrap().set(1);
+
+        let mut shard_b = PrometheusRegistry::new();
+        shard_b.counter("jobs_total", "Jobs").unwrap().inc_by(5);
+
+        let composite = CompositeRegistry::new()
+            .with_shard("a", &shard_a)
+            .with_shard("b", &shard_b);
+        let text = composite.render().unwrap();
+
+        assert_eq!(text.matches("# TYPE jobs_total counter").count(), 1);
+        assert_eq!(text.matches("# HELP jobs_total").count(), 1);
+        assert!(text.contains("jobs_total_total{shard=\"a\"} 2\njobs_total_total{shard=\"b\"} 5\n"));
+        assert!(text.contains("shard_a_depth{shard=\"a\"} 1"));
+        assert_eq!(text.matches("# EOF").count(), 1);
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_composite_keeps_existing_labels() {
+        let mut shard = PrometheusRegistry::new();
+        let workers = shard
+            .labeled_gauge("workers", "Workers", vec!["pool".into()])
+            .unwrap();
+        workers.set(&["io"], 4).unwrap();
+
+        let text = CompositeRegistry::new()
+            .with_shard("worker \"1\"", &shard)
+            .render()
+            .unwrap();
+        assert!(text.contains("workers{shard=\"worker \\\"1\\\"\",pool=\"io\"} 4"));
+    }
+
+    #[test]
+    fn test_composite_rejects_conflicts() {
+        let mut counters = PrometheusRegistry::new();
+        counters.counter("jobs", "Jobs").unwrap();
+        let mut gauges = PrometheusRegistry::new();
+        gauges.gauge("jobs", "Jobs").unwrap();
+
+        let conflicting_types = CompositeRegistry::new()
+            .with_shard("a", &counters)
+            .with_shard("b", &gauges);
+        assert!(matches!(
+            conflicting_types.render(),
+            Err(DeserializeError::Render(_))
+        ));
+
+        let duplicate_names = CompositeRegistry::new()
+            .with_shard("a", &counters)
+            .with_shard("a", &counters);
+        assert!(matches!(
+            duplicate_names.render(),
+            Err(DeserializeError::Render(_))
+        ));
+
+        let mut labeled = PrometheusRegistry::new();
+        let sharded = labeled
+            .labeled_gauge("jobs", "Jobs", vec![SHARD_LABEL.into()])
+            .unwrap();
+        sharded.set(&["inner"], 1).unwrap();
+        let shard_label_taken = CompositeRegistry::new().with_shard("a", &labeled);
+        assert!(matches!(
+            shard_label_taken.render(),
+            Err(DeserializeError::Render(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_composite_renders_nothing() {
+        let composite = CompositeRegistry::<crate::backends::prometheus::PrometheusBackend>::new();
+        assert!(composite.is_empty());
+        assert_eq!(composite.render().unwrap(), "");
+    }
+}
@@ -1 +1,387 @@
-// Deserialise a JSON or YAML file into a MetricState
\ No newline at end of file
+//! Validation and error types for metric definitions.
+//!
+//! Metric definitions can come from code or from configuration files, so the
+//! checks applied before a metric reaches a backend live here.
+
+use super::metrics::MetricKind;
+use std::borrow::Cow;
+
+/// Default maximum length (in bytes) of a metric name.
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 200;
+
+/// Default maximum number of buckets a configured histogram may declare.
+pub const DEFAULT_MAX_BUCKETS: usize = 64;
+
+/// Errors raised while validating or registering metric definitions.
+#[derive(Debug, thiserror::Error)]
+pub enum DeserializeError {
+    #[error("Invalid metric name '{name}': {reason}")]
+    InvalidMetricName { name: String, reason: String },
+    #[error("Invalid buckets for histogram '{name}': {reason}")]
+    InvalidBuckets { name: String, reason: String },
+    #[error("Label name '{0}' is reserved: names starting with '__' are for internal use")]
+    ReservedLabel(String),
+    #[error("Failed to parse config: {0}")]
+    Parse(String),
+    #[error("Invalid pattern '{pattern}': {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+    #[error("Invalid config file path: {0}")]
+    InvalidFilePath(String),
+    #[error("Unsupported config format: {0}")]
+    UnsupportedFormat(String),
+    #[error("Config include cycle: {0}")]
+    IncludeCycle(String),
+    #[error("Failed to read config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Config defines no enabled metrics")]
+    EmptyConfig,
+    #[error("Metric name '{0}' is already registered")]
+    DuplicateMetricName(String),
+    #[error(
+        "No {} named '{name}' is registered",
+        kind.map_or("metric", |kind| kind.as_str())
+    )]
+    MetricNotFound {
+        name: String,
+        kind: Option<MetricKind>,
+    },
+    #[error("Failed to render metrics: {0}")]
+    Render(String),
+    #[error("Backend error: {0}")]
+    Backend(String),
+    #[error("Registry is frozen: '{0}' can't be registered")]
+    RegistryFrozen(String),
+}
+
+impl DeserializeError {
+    /// Returns true if the error is a mistake in the metric definitions.
+    ///
+    /// User errors (bad names, buckets or labels, unparsable or empty
+    /// configs or patterns, include cycles, duplicates, lookups of undefined metrics,
+    /// registering into a frozen registry) are fixed by
+    /// editing the config or calling code. The rest (file access, path
+    /// checks, rendering and backend failures) point at the environment the
+    /// process runs in.
+    pub fn is_user_error(&self) -> bool {
+        match self {
+            Self::InvalidMetricName { .. }
+            | Self::InvalidBuckets { .. }
+            | Self::ReservedLabel(_)
+            | Self::Parse(_)
+            | Self::InvalidPattern { .. }
+            | Self::UnsupportedFormat(_)
+            | Self::IncludeCycle(_)
+            | Self::EmptyConfig
+            | Self::DuplicateMetricName(_)
+            | Self::MetricNotFound { .. }
+            | Self::RegistryFrozen(_) => true,
+            Self::InvalidFilePath(_) | Self::Io(_) | Self::Render(_) | Self::Backend(_) => false,
+        }
+    }
+}
+
+/// Validate a metric name against the Prometheus character set and the
+/// configured length limit.
+///
+/// Names must match `[a-zA-Z_:][a-zA-Z0-9_:]*`. Very long names are accepted
+/// by most backends but cause problems downstream (storage, query engines,
+/// dashboards), so they are rejected before registration.
+pub fn validate_metric_name(name: &str, max_length: usize) -> Result<(), DeserializeError> {
+    let invalid = |reason: String| DeserializeError::InvalidMetricName {
+        name: name.to_string(),
+        reason,
+    };
+    if name.len() > max_length {
+        return Err(invalid(format!(
+            "length {} exceeds the maximum of {} bytes",
+            name.len(),
+            max_length
+        )));
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        None => return Err(invalid("name is empty".to_string())),
+        Some(first) if !(first.is_ascii_alphabetic() || first == '_' || first == ':') => {
+            return Err(invalid(format!("can't start with '{}'", first)));
+        }
+        Some(_) => {}
+    }
+    if let Some(c) = chars.find(|&c| !(c.is_ascii_alphanumeric() || c == '_' || c == ':')) {
+        return Err(invalid(format!("'{}' is not allowed in a metric name", c)));
+    }
+    Ok(())
+}
+
+/// Validate the number of buckets declared for a histogram.
+///
+/// Every bucket is a separate series, so an oversized list multiplies the
+/// series count of the histogram and each of its label combinations.
+pub fn validate_bucket_count(
+    name: &str,
+    count: usize,
+    max_buckets: usize,
+) -> Result<(), DeserializeError> {
+    if count > max_buckets {
+        return Err(DeserializeError::InvalidBuckets {
+            name: name.to_string(),
+            reason: format!("{} buckets exceeds the maximum of {}", count, max_buckets),
+        });
+    }
+    Ok(())
+}
+
+/// Validate the upper bounds declared for a histogram.
+///
+/// Bounds must be strictly increasing and not NaN; otherwise observations
+/// land in the wrong bucket and the cumulative `le` series stop making sense.
+pub fn validate_bucket_bounds(name: &str, buckets: &[f64]) -> Result<(), DeserializeError> {
+    let invalid = |reason: String| DeserializeError::InvalidBuckets {
+        name: name.to_string(),
+        reason,
+    };
+
+    if buckets.iter().any(|bound| bound.is_nan()) {
+        return Err(invalid("bucket bounds must not be NaN".to_string()));
+    }
+    if let Some(pair) = buckets.windows(2).find(|pair| pair[0] >= pair[1]) {
+        return Err(invalid(format!(
+            "bounds must be strictly increasing, but {} is followed by {}",
+            pair[0], pair[1]
+        )));
+    }
+    Ok(())
+}
+
+/// Check the bucket bounds of a histogram and put them in canonical form.
+///
+/// Every histogram registration path goes through this, so a bucket list is
+/// accepted or rejected the same way wherever it comes from. Trailing
+/// `+Inf` bounds are dropped, however many are listed, as backends always
+/// add the implicit `+Inf` bucket. At least one bound must remain, and the bounds must pass
+/// [`validate_bucket_count`] and [`validate_bucket_bounds`], and be finite.
+pub fn normalize_and_validate_buckets(
+    name: &str,
+    mut buckets: Vec<f64>,
+    max_buckets: usize,
+) -> Result<Vec<f64>, DeserializeError> {
+    while buckets.last() == Some(&f64::INFINITY) {
+        buckets.pop();
+    }
+    // A histogram with only the implicit +Inf bucket is almost certainly a mistake
+    if buckets.is_empty() {
+        return Err(DeserializeError::InvalidBuckets {
+            name: name.to_string(),
+            reason: "at least one finite bound is required".to_string(),
+        });
+    }
+
+    validate_bucket_count(name, buckets.len(), max_buckets)?;
+    validate_bucket_bounds(name, &buckets)?;
+    if let Some(bound) = buckets.iter().find(|bound| bound.is_infinite()) {
+        return Err(DeserializeError::InvalidBuckets {
+            name: name.to_string(),
+            reason: format!(
+                "bound {} must be finite; only a final +Inf is allowed",
+                bound
+            ),
+        });
+    }
+    Ok(buckets)
+}
+
+/// Validate a label name before it is attached to a series.
+///
+/// Prometheus reserves names starting with `__` (such as `__name__`) for its
+/// own use; series carrying them are rejected or silently rewritten at scrape
+/// time, so they are refused up front.
+pub fn validate_label_name(name: &str) -> Result<(), DeserializeError> {
+    if name.starts_with("__") {
+        return Err(DeserializeError::ReservedLabel(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Escape a description for use on an exposition `# HELP` line.
+///
+/// Backslashes become `\\` and line feeds become `\n`, as the text
+/// format requires, so a description from untrusted config can't break the
+/// line it is placed on. Other text is returned unchanged without allocating.
+pub fn escape_help(help: &str) -> Cow<'_, str> {
+    if !help.contains(['\\', '\n']) {
+        return Cow::Borrowed(help);
+    }
+
+    let mut escaped = String::with_capacity(help.len() + 2);
+    for c in help.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Escape a label value for use between double quotes in exposition output.
+///
+/// Like [`escape_help`], but double quotes are escaped as well.
+pub fn escape_label_value(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '\n', '"']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_user_error_classifies_every_variant() {
+        let user = [
+            DeserializeError::InvalidMetricName {
+                name: "a".into(),
+                reason: "too long".into(),
+            },
+            DeserializeError::InvalidBuckets {
+                name: "h".into(),
+                reason: "unsorted".into(),
+            },
+            DeserializeError::ReservedLabel("__name__".into()),
+            DeserializeError::Parse("expected ']'".into()),
+            DeserializeError::InvalidPattern {
+                pattern: "[".into(),
+                reason: "unclosed character class".into(),
+            },
+            DeserializeError::UnsupportedFormat("metrics.toml".into()),
+            DeserializeError::IncludeCycle("a.yaml -> b.yaml -> a.yaml".into()),
+            DeserializeError::EmptyConfig,
+            DeserializeError::DuplicateMetricName("requests_total".into()),
+            DeserializeError::MetricNotFound {
+                name: "requests_total".into(),
+                kind: Some(MetricKind::Counter),
+            },
+            DeserializeError::RegistryFrozen("requests_total".into()),
+        ];
+        let operational = [
+            DeserializeError::InvalidFilePath("/etc/metrics.json".into()),
+            DeserializeError::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+            DeserializeError::Render("encoder failed".into()),
+            DeserializeError::Backend("registry poisoned".into()),
+        ];
+
+        assert!(user.iter().all(DeserializeError::is_user_error));
+        assert!(!operational.iter().any(DeserializeError::is_user_error));
+    }
+
+    #[test]
+    fn test_validate_metric_name_length() {
+        assert!(validate_metric_name("http_requests_total", DEFAULT_MAX_NAME_LENGTH).is_ok());
+        assert!(validate_metric_name(&"a".repeat(200), DEFAULT_MAX_NAME_LENGTH).is_ok());
+
+        let err = validate_metric_name(&"a".repeat(201), DEFAULT_MAX_NAME_LENGTH).unwrap_err();
+        assert!(matches!(err, DeserializeError::InvalidMetricName { .. }));
+    }
+
+    #[test]
+    fn test_validate_metric_name_characters() {
+        for good in ["requests_total", "_hidden", "job:rate5m", "A9"] {
+            assert!(
+                validate_metric_name(good, DEFAULT_MAX_NAME_LENGTH).is_ok(),
+                "{good}"
+            );
+        }
+        for bad in [
+            "",
+            "9lives",
+            "http.requests",
+            "latency-seconds",
+            "naïve",
+            "a b",
+        ] {
+            assert!(
+                matches!(
+                    validate_metric_name(bad, DEFAULT_MAX_NAME_LENGTH),
+                    Err(DeserializeError::InvalidMetricName { .. })
+                ),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_and_validate_buckets() {
+        assert_eq!(
+            normalize_and_validate_buckets("h", vec![0.1, 1.0, f64::INFINITY], 2).unwrap(),
+            vec![0.1, 1.0]
+        );
+        assert_eq!(
+            normalize_and_validate_buckets("h", vec![1.0, f64::INFINITY, f64::INFINITY], 2)
+                .unwrap(),
+            vec![1.0]
+        );
+
+        for bad in [
+            vec![],
+            vec![f64::INFINITY],
+            vec![1.0, 0.5],
+            vec![f64::NEG_INFINITY, 1.0],
+            vec![0.1, 0.2, 0.3],
+        ] {
+            assert!(matches!(
+                normalize_and_validate_buckets("h", bad, 2),
+                Err(DeserializeError::InvalidBuckets { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_bucket_bounds() {
+        assert!(validate_bucket_bounds("h", &[0.1, 1.0, f64::INFINITY]).is_ok());
+        assert!(validate_bucket_bounds("h", &[]).is_ok());
+
+        for bad in [&[1.0, 0.5][..], &[1.0, 1.0], &[0.1, f64::NAN]] {
+            let err = validate_bucket_bounds("h", bad).unwrap_err();
+            assert!(matches!(err, DeserializeError::InvalidBuckets { .. }));
+        }
+    }
+
+    #[test]
+    fn test_validate_label_name_rejects_reserved_names() {
+        assert!(validate_label_name("method").is_ok());
+        assert!(validate_label_name("_private").is_ok());
+
+        let err = validate_label_name("__name__").unwrap_err();
+        assert!(matches!(err, DeserializeError::ReservedLabel(name) if name == "__name__"));
+    }
+
+    #[test]
+    fn test_escape_help() {
+        assert!(matches!(
+            escape_help("Plain text"),
+            Cow::Borrowed("Plain text")
+        ));
+        assert_eq!(escape_help("One\nTwo"), "One\\nTwo");
+        assert_eq!(escape_help("C:\\temp"), "C:\\\\temp");
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert!(matches!(
+            escape_label_value("0.1.0"),
+            Cow::Borrowed("0.1.0")
+        ));
+        assert_eq!(escape_label_value("say \"hi\""), "say \\\"hi\\\"");
+        assert_eq!(escape_label_value("a\\b\nc"), "a\\\\b\\nc");
+    }
+}
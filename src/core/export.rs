@@ -0,0 +1,113 @@
+//! Periodic export of metrics from a background task.
+//!
+//! Scraping is the usual way metrics leave a process, but file drops and
+//! push gateways need the process to export on its own schedule.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Largest random delay added to each interval, as a fraction of the interval.
+const MAX_JITTER_FRACTION: f64 = 0.1;
+
+/// Handle to a task started by [`spawn_periodic_exporter`].
+///
+/// Dropping the handle stops the exporter.
+#[derive(Debug)]
+pub struct ExporterHandle {
+    task: JoinHandle<()>,
+}
+
+impl ExporterHandle {
+    /// Stop the exporter. Equivalent to dropping the handle.
+    pub fn stop(self) {}
+
+    /// Returns true if the exporter task has stopped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for ExporterHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Call `export` on the Tokio runtime roughly every `interval`.
+///
+/// Each wait is lengthened by a random jitter of up to 10% of `interval`, so
+/// many processes started together don't all export at the same instant.
+/// The closure runs on a runtime worker; hand blocking work such as large
+/// file writes to `tokio::task::spawn_blocking` from inside it.
+///
+/// Must be called inside a Tokio runtime.
+///
+/// # Example
+/// ```ignore
+/// let registry = Arc::new(registry);
+/// let exporter = spawn_periodic_exporter(Duration::from_secs(15), move || {
+///     if let Err(e) = registry.write_to_file("/var/lib/metrics/app.prom") {
+///         eprintln!("Metrics export failed: {}", e);
+///     }
+/// });
+///
+/// // Exporting stops when `exporter` is dropped
+/// ```
+pub fn spawn_periodic_exporter<F>(interval: Duration, mut export: F) -> ExporterHandle
+where
+    F: FnMut() + Send + 'static,
+{
+    let task = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval + jitter(interval)).await;
+            export();
+        }
+    });
+
+    ExporterHandle { task }
+}
+
+/// A random delay between zero and [`MAX_JITTER_FRACTION`] of `interval`.
+fn jitter(interval: Duration) -> Duration {
+    // A freshly seeded hasher is a cheap source of randomness that needs no
+    // extra dependency; the quality only has to be good enough to spread load.
+    let random = RandomState::new().build_hasher().finish();
+    let unit = (random >> 11) as f64 / (1u64 << 53) as f64;
+    interval.mul_f64(MAX_JITTER_FRACTION * unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let interval = Duration::from_millis(100);
+        for _ in 0..1000 {
+            assert!(jitter(interval) <= interval.mul_f64(MAX_JITTER_FRACTION));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_periodic_exporter_fires_on_cadence_and_stops_on_drop() {
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&fired);
+
+        let exporter = spawn_periodic_exporter(Duration::from_millis(100), move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // Time is paused and only moves with the sleeps: each wait is
+        // 100-110ms, so after 450ms exactly 4 exports have run
+        tokio::time::sleep(Duration::from_millis(450)).await;
+        assert_eq!(fired.load(Ordering::SeqCst), 4);
+
+        drop(exporter);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(fired.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,742 @@
+//! Checking rendered output against the text exposition format.
+//!
+//! A safety net for rendering regressions: output that passes
+//! [`check_exposition`] has one `# TYPE` per family ahead of its samples,
+//! sample names matching their family's type, parsable values, no
+//! duplicate series, and histograms whose buckets only grow. Both the
+//! Prometheus 0.0.4 text format, where a counter family keeps its `_total`
+//! name, and OpenMetrics are accepted.
+//!
+//! [`parse_exposition`] goes the other way, reading text from this or
+//! another exporter back into a [`MetricsSnapshot`].
+
+use super::metrics::MetricKind;
+use super::renderer::{parse_sample, LabelPairs};
+use super::snapshot::{MetricSnapshot, MetricsSnapshot, SnapshotValue};
+use std::collections::{HashMap, HashSet};
+
+/// Metric types the text formats define.
+const KNOWN_TYPES: [&str; 8] = [
+    "counter",
+    "gauge",
+    "histogram",
+    "summary",
+    "unknown",
+    "untyped",
+    "info",
+    "stateset",
+];
+
+/// Check that `text` is well-formed Prometheus or OpenMetrics text exposition.
+///
+/// Returns a description of the first problem found.
+///
+/// # Example
+/// ```ignore
+/// let text = registry.render()?.as_str()?.to_string();
+/// check_exposition(&text).expect("malformed exposition");
+/// ```
+pub fn check_exposition(text: &str) -> Result<(), String> {
+    let mut seen_families = HashSet::new();
+    let mut seen_series = HashSet::new();
+    let mut family: Option<Family<'_>> = None;
+    let mut after_eof = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let at = |problem: String| format!("line {}: {}", line_number, problem);
+
+        if line.trim().is_empty() {
+            continue;
+        }
+        if after_eof {
+            return Err(at("content after # EOF".to_string()));
+        }
+        if line.trim_end() == "# EOF" {
+            after_eof = true;
+            continue;
+        }
+
+        if let Some((keyword, rest)) = header(line) {
+            let name = rest.split_whitespace().next().unwrap_or("");
+            if name.is_empty() {
+                return Err(at(format!("# {} line without a metric name", keyword)));
+            }
+            if family.as_ref().map(|f| f.name) != Some(name) {
+                if let Some(done) = family.take() {
+                    done.check_histogram()?;
+                }
+                if !seen_families.insert(name) {
+                    return Err(at(format!("family '{}' appears more than once", name)));
+                }
+                family = Some(Family::new(name));
+            }
+            let current = family.as_mut().expect("family was just set");
+
+            match keyword {
+                "HELP" if current.has_help => {
+                    return Err(at(format!("duplicate # HELP for '{}'", name)));
+                }
+                "HELP" => current.has_help = true,
+                "TYPE" if current.kind.is_some() => {
+                    return Err(at(format!("duplicate # TYPE for '{}'", name)));
+                }
+                "TYPE" if current.has_samples => {
+                    return Err(at(format!("# TYPE for '{}' follows its samples", name)));
+                }
+                "TYPE" => {
+                    let kind = rest[name.len()..].trim();
+                    if !KNOWN_TYPES.contains(&kind) {
+                        return Err(at(format!("unknown type '{}' for '{}'", kind, name)));
+                    }
+                    current.kind = Some(kind);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let Some(current) = family.as_mut() else {
+            return Err(at("sample outside of any family".to_string()));
+        };
+        let Some(kind) = current.kind else {
+            return Err(at(format!(
+                "sample for '{}' before its # TYPE",
+                current.name
+            )));
+        };
+        let (series, labels, rest) =
+            parse_sample(line).ok_or_else(|| at("malformed label set".to_string()))?;
+        let suffix = series
+            .strip_prefix(current.name)
+            .filter(|suffix| sample_suffixes(kind).contains(suffix))
+            .ok_or_else(|| {
+                at(format!(
+                    "sample '{}' doesn't belong to {} '{}'",
+                    series, kind, current.name
+                ))
+            })?;
+
+        // Drop any exemplar, then expect a value and an optional timestamp
+        let rest = rest.split(" # ").next().unwrap_or(rest);
+        let mut fields = rest.split_whitespace();
+        let value = fields
+            .next()
+            .and_then(parse_value)
+            .ok_or_else(|| at(format!("missing or invalid value for '{}'", series)))?;
+        if let Some(timestamp) = fields.next() {
+            if timestamp.parse::<f64>().is_err() {
+                return Err(at(format!("invalid timestamp for '{}'", series)));
+            }
+        }
+        if fields.next().is_some() {
+            return Err(at(format!("trailing text after '{}'", series)));
+        }
+
+        let mut sorted = labels.clone();
+        sorted.sort_unstable();
+        if sorted.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(at(format!("repeated label name in '{}'", series)));
+        }
+        if !seen_series.insert((series, sorted)) {
+            return Err(at(format!("duplicate series '{}'", line)));
+        }
+
+        current.has_samples = true;
+        if kind == "histogram" {
+            current.record_histogram_sample(suffix, &labels, value, line_number)?;
+        }
+    }
+
+    match family {
+        Some(done) => done.check_histogram(),
+        None => Ok(()),
+    }
+}
+
+/// A line of text exposition that [`parse_exposition`] couldn't make sense of.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    /// 1-based number of the offending line
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parse Prometheus or OpenMetrics text exposition into a snapshot.
+///
+/// Counters, gauges and histograms are kept, one [`MetricSnapshot`] per
+/// label set with label values unescaped. Counter and gauge values that
+/// aren't whole numbers in range of the integer variants are kept as
+/// [`SnapshotValue::Float`]; histograms keep no value, as in
+/// [`ObservabilityRegistry::snapshot`]. Counters are accepted with or
+/// without the OpenMetrics `_total` suffix on their samples, so both text
+/// format versions parse. Families of other types, and samples outside any
+/// family, can't be held by a snapshot and are reported as errors rather
+/// than dropped. Parsed metrics have no `last_updated` time. Parsing stops
+/// at `# EOF`.
+///
+/// # Example
+/// ```ignore
+/// let body = reqwest::get("http://sidecar:9100/metrics").await?.text().await?;
+/// let snapshot = parse_exposition(&body)?;
+/// let jobs = snapshot.get("jobs_processed");
+/// ```
+///
+/// [`ObservabilityRegistry::snapshot`]: super::registry::ObservabilityRegistry::snapshot
+pub fn parse_exposition(text: &str) -> Result<MetricsSnapshot, ParseError> {
+    let mut seen_families = HashSet::new();
+    let mut metrics = Vec::new();
+    let mut family: Option<ParsedFamily<'_>> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let at = |message: String| ParseError {
+            line: index + 1,
+            message,
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "# EOF" {
+            break;
+        }
+
+        if let Some((keyword, rest)) = header(line) {
+            let name = rest.split_whitespace().next().unwrap_or("");
+            if name.is_empty() {
+                return Err(at(format!("# {} line without a metric name", keyword)));
+            }
+            if family.as_ref().map(|f| f.name) != Some(name) {
+                metrics.extend(
+                    family
+                        .take()
+                        .into_iter()
+                        .flat_map(ParsedFamily::into_snapshots),
+                );
+                if !seen_families.insert(name) {
+                    return Err(at(format!("family '{}' appears more than once", name)));
+                }
+                family = Some(ParsedFamily::new(name));
+            }
+            if keyword == "TYPE" {
+                let kind = rest[name.len()..].trim();
+                if !KNOWN_TYPES.contains(&kind) {
+                    return Err(at(format!("unknown type '{}' for '{}'", kind, name)));
+                }
+                if !matches!(kind, "counter" | "gauge" | "histogram") {
+                    return Err(at(format!(
+                        "{} '{}' can't be held by a snapshot",
+                        kind, name
+                    )));
+                }
+                if let Some(current) = family.as_mut() {
+                    current.kind = Some(kind);
+                }
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (series, labels, rest) =
+            parse_sample(line).ok_or_else(|| at("malformed label set".to_string()))?;
+        let rest = rest.split(" # ").next().unwrap_or(rest);
+        let mut fields = rest.split_whitespace();
+        let value = fields
+            .next()
+            .and_then(parse_value)
+            .ok_or_else(|| at(format!("missing or invalid value for '{}'", series)))?;
+        if let Some(timestamp) = fields.next() {
+            if timestamp.parse::<f64>().is_err() {
+                return Err(at(format!("invalid timestamp for '{}'", series)));
+            }
+        }
+        if fields.next().is_some() {
+            return Err(at(format!("trailing text after '{}'", series)));
+        }
+
+        let Some(current) = family.as_mut() else {
+            return Err(at(format!("sample '{}' outside of any family", series)));
+        };
+        let Some(kind) = current.kind else {
+            return Err(at(format!(
+                "untyped sample '{}' can't be held by a snapshot",
+                series
+            )));
+        };
+        let suffix = series
+            .strip_prefix(current.name)
+            .filter(|suffix| sample_suffixes(kind).contains(suffix))
+            .ok_or_else(|| {
+                at(format!(
+                    "sample '{}' doesn't belong to {} '{}'",
+                    series, kind, current.name
+                ))
+            })?;
+
+        let mut key: SeriesLabels = labels
+            .iter()
+            .filter(|(label, _)| kind != "histogram" || *label != "le")
+            .map(|(label, value)| (label.to_string(), unescape_label_value(value)))
+            .collect();
+        key.sort_unstable();
+
+        match suffix {
+            "_bucket"
+                if !labels
+                    .iter()
+                    .any(|(label, le)| *label == "le" && parse_value(le).is_some()) =>
+            {
+                return Err(at(format!(
+                    "bucket of '{}' without a valid le label",
+                    current.name
+                )));
+            }
+            "_created" => {}
+            _ if kind == "histogram" => {
+                if !current.series.iter().any(|(labels, _)| *labels == key) {
+                    current.series.push((key, None));
+                }
+            }
+            _ => {
+                if current.series.iter().any(|(labels, _)| *labels == key) {
+                    return Err(at(format!("duplicate series '{}'", line)));
+                }
+                current.series.push((key, Some(value)));
+            }
+        }
+    }
+
+    metrics.extend(family.into_iter().flat_map(ParsedFamily::into_snapshots));
+    metrics.sort_by(|a, b| (&a.name, &a.labels).cmp(&(&b.name, &b.labels)));
+    Ok(MetricsSnapshot { metrics })
+}
+
+/// Sorted label pairs of one series.
+type SeriesLabels = Vec<(String, String)>;
+
+/// A family being read by [`parse_exposition`].
+struct ParsedFamily<'a> {
+    name: &'a str,
+    kind: Option<&'a str>,
+    /// Sorted labels of each series, with its value for counters and gauges
+    series: Vec<(SeriesLabels, Option<f64>)>,
+}
+
+impl<'a> ParsedFamily<'a> {
+    fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            kind: None,
+            series: Vec::new(),
+        }
+    }
+
+    /// One snapshot per series of this family.
+    fn into_snapshots(self) -> impl Iterator<Item = MetricSnapshot> + 'a {
+        let (name, kind) = (self.name, self.kind);
+        self.series.into_iter().filter_map(move |(labels, value)| {
+            let (kind, value) = match (kind?, value) {
+                ("counter", Some(value)) => (MetricKind::Counter, counter_value(value)),
+                ("gauge", Some(value)) => (MetricKind::Gauge, gauge_value(value)),
+                ("histogram", _) => (MetricKind::Histogram, SnapshotValue::Histogram),
+                _ => return None,
+            };
+            Some(MetricSnapshot {
+                name: name.to_string(),
+                kind,
+                value,
+                labels,
+                last_updated: None,
+            })
+        })
+    }
+}
+
+/// A counter value as [`SnapshotValue::Counter`] if it is a whole number
+/// that fits, otherwise as [`SnapshotValue::Float`].
+fn counter_value(value: f64) -> SnapshotValue {
+    if value.fract() == 0.0 && value >= 0.0 && value <= u64::MAX as f64 {
+        SnapshotValue::Counter(value as u64)
+    } else {
+        SnapshotValue::Float(value)
+    }
+}
+
+/// A gauge value as [`SnapshotValue::Gauge`] if it is a whole number that
+/// fits, otherwise as [`SnapshotValue::Float`].
+fn gauge_value(value: f64) -> SnapshotValue {
+    if value.fract() == 0.0 && value >= i64::MIN as f64 && value <= i64::MAX as f64 {
+        SnapshotValue::Gauge(value as i64)
+    } else {
+        SnapshotValue::Float(value)
+    }
+}
+
+/// Undo the escaping of a label value as written in the text format.
+fn unescape_label_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped @ ('\\' | '"'))) => {
+                unescaped.push(escaped);
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// The keyword and remainder of a `# HELP`, `# TYPE` or `# UNIT` line.
+fn header(line: &str) -> Option<(&str, &str)> {
+    ["HELP", "TYPE", "UNIT"].into_iter().find_map(|keyword| {
+        let rest = line.strip_prefix("# ")?.strip_prefix(keyword)?;
+        Some((keyword, rest.strip_prefix(' ')?))
+    })
+}
+
+/// The sample name suffixes a family of type `kind` may use.
+fn sample_suffixes(kind: &str) -> &'static [&'static str] {
+    match kind {
+        // A bare name is the Prometheus 0.0.4 form, where the family is `x_total`
+        "counter" => &["", "_total", "_created"],
+        "histogram" => &["_bucket", "_sum", "_count", "_created"],
+        "summary" => &["", "_sum", "_count", "_created"],
+        "info" => &["_info"],
+        _ => &[""],
+    }
+}
+
+/// Parse a sample value, including the `NaN` and `±Inf` spellings.
+fn parse_value(value: &str) -> Option<f64> {
+    match value {
+        "NaN" => Some(f64::NAN),
+        "+Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        _ => value.parse().ok(),
+    }
+}
+
+/// The family being checked, with what is needed to check histograms.
+struct Family<'a> {
+    name: &'a str,
+    kind: Option<&'a str>,
+    has_help: bool,
+    has_samples: bool,
+    /// `(le, count)` of each bucket, keyed by the other labels
+    buckets: HashMap<LabelPairs<'a>, Vec<(f64, f64)>>,
+    /// `_count` value, keyed by labels
+    counts: HashMap<LabelPairs<'a>, f64>,
+}
+
+impl<'a> Family<'a> {
+    fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            kind: None,
+            has_help: false,
+            has_samples: false,
+            buckets: HashMap::new(),
+            counts: HashMap::new(),
+        }
+    }
+
+    fn record_histogram_sample(
+        &mut self,
+        suffix: &str,
+        labels: &[(&'a str, &'a str)],
+        value: f64,
+        line_number: usize,
+    ) -> Result<(), String> {
+        let mut key: Vec<_> = labels
+            .iter()
+            .filter(|(label, _)| *label != "le")
+            .copied()
+            .collect();
+        key.sort_unstable();
+
+        match suffix {
+            "_bucket" => {
+                let le = labels
+                    .iter()
+                    .find(|(label, _)| *label == "le")
+                    .and_then(|(_, le)| parse_value(le))
+                    .ok_or_else(|| {
+                        format!(
+                            "line {}: bucket of '{}' without a valid le label",
+                            line_number, self.name
+                        )
+                    })?;
+                self.buckets.entry(key).or_default().push((le, value));
+            }
+            "_count" => {
+                self.counts.insert(key, value);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Check bucket bounds increase, counts never drop, and `+Inf` matches `_count`.
+    fn check_histogram(&self) -> Result<(), String> {
+        for (labels, buckets) in &self.buckets {
+            for pair in buckets.windows(2) {
+                if pair[1].0 <= pair[0].0 {
+                    return Err(format!(
+                        "histogram '{}' has bucket le={} after le={}",
+                        self.name, pair[1].0, pair[0].0
+                    ));
+                }
+                if pair[1].1 < pair[0].1 {
+                    return Err(format!(
+                        "histogram '{}' bucket le={} counts {} but le={} counts {}",
+                        self.name, pair[1].0, pair[1].1, pair[0].0, pair[0].1
+                    ));
+                }
+            }
+            match buckets.last() {
+                Some((le, inf_count)) if *le == f64::INFINITY => {
+                    if let Some(count) = self.counts.get(labels) {
+                        if count != inf_count {
+                            return Err(format!(
+                                "histogram '{}' has _count {} but +Inf bucket {}",
+                                self.name, count, inf_count
+                            ));
+                        }
+                    }
+                }
+                _ => return Err(format!("histogram '{}' has no +Inf bucket", self.name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HISTOGRAM: &str = "# HELP latency Latency.\n\
+                             # TYPE latency histogram\n\
+                             latency_sum 1.5\n\
+                             latency_count 3\n\
+                             latency_bucket{le=\"0.5\"} 1\n\
+                             latency_bucket{le=\"1.0\"} 2\n\
+                             latency_bucket{le=\"+Inf\"} 3\n\
+                             # EOF\n";
+
+    #[test]
+    fn test_check_exposition_accepts_well_formed_output() {
+        assert_eq!(check_exposition(HISTOGRAM), Ok(()));
+        assert_eq!(
+            check_exposition("# TYPE up gauge\nup{job=\"a\"} 1\nup{job=\"b\"} NaN\n"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_exposition_accepts_text_format_0_0_4() {
+        assert_eq!(
+            check_exposition(
+                "# HELP jobs_total Jobs.\n# TYPE jobs_total counter\njobs_total 5\n\
+                 # TYPE hits_total counter\nhits_total{path=\"/\"} 4 1700000000000\n"
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_exposition_rejects_malformed_output() {
+        let cases = [
+            (
+                HISTOGRAM.replace("{le=\"1.0\"} 2", "{le=\"1.0\"} 0"),
+                "bucket le=1 counts 0",
+            ),
+            (
+                HISTOGRAM.replace("latency_count 3", "latency_count 4"),
+                "_count 4",
+            ),
+            (
+                HISTOGRAM.replace("latency_bucket{le=\"+Inf\"} 3\n", ""),
+                "no +Inf bucket",
+            ),
+            (
+                "# TYPE up gauge\nup 1\n# TYPE down gauge\ndown 1\n# TYPE up gauge\nup 2\n"
+                    .to_string(),
+                "appears more than once",
+            ),
+            (
+                "# TYPE up gauge\nup 1\n# TYPE up gauge\n".to_string(),
+                "duplicate # TYPE",
+            ),
+            (
+                "# TYPE up gauge\nup{a=\"1\",b=\"2\"} 1\nup{b=\"2\",a=\"1\"} 1\n".to_string(),
+                "duplicate series",
+            ),
+            ("# TYPE up gauge\nup one\n".to_string(), "invalid value"),
+            (
+                "# TYPE hits counter\nhits_count 1\n".to_string(),
+                "doesn't belong",
+            ),
+            ("up 1\n".to_string(), "outside of any family"),
+            ("# HELP up Up.\nup 1\n".to_string(), "before its # TYPE"),
+            (
+                "# TYPE up gauge\nup 1\n# EOF\nup 2\n".to_string(),
+                "after # EOF",
+            ),
+        ];
+
+        for (text, expected) in cases {
+            let err = check_exposition(&text).unwrap_err();
+            assert!(err.contains(expected), "{:?}: {}", text, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_exposition_keeps_labels_and_floats() {
+        let text = format!(
+            "{}# TYPE jobs counter\n\
+             jobs_total 12\n\
+             jobs_created 1700000000\n\
+             # TYPE depth gauge\n\
+             depth -3 1700000000\n\
+             # TYPE ratio gauge\n\
+             ratio 0.5\n\
+             # TYPE hits counter\n\
+             hits_total{{path=\"/b\"}} 4\n\
+             hits_total{{path=\"/a\\\"q\\\"\"}} 1.5\n\
+             # TYPE rpc histogram\n\
+             rpc_bucket{{method=\"get\",le=\"+Inf\"}} 1\n\
+             rpc_sum{{method=\"get\"}} 0.2\n\
+             rpc_count{{method=\"get\"}} 1\n",
+            HISTOGRAM.trim_end_matches("# EOF\n")
+        );
+
+        let snapshot = parse_exposition(&text).unwrap();
+
+        let names: Vec<&str> = snapshot.metrics.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["depth", "hits", "hits", "jobs", "latency", "ratio", "rpc"]
+        );
+        assert_eq!(
+            snapshot.get("jobs").unwrap().value,
+            SnapshotValue::Counter(12)
+        );
+        assert_eq!(
+            snapshot.get("depth").unwrap().value,
+            SnapshotValue::Gauge(-3)
+        );
+        assert_eq!(
+            snapshot.get("ratio").unwrap().value,
+            SnapshotValue::Float(0.5)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("hits", &[("path", "/b")])
+                .unwrap()
+                .value,
+            SnapshotValue::Counter(4)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("hits", &[("path", "/a\"q\"")])
+                .unwrap()
+                .value,
+            SnapshotValue::Float(1.5)
+        );
+        assert_eq!(snapshot.get("latency").unwrap().kind, MetricKind::Histogram);
+        let rpc = snapshot.get("rpc").unwrap();
+        assert_eq!(rpc.kind, MetricKind::Histogram);
+        assert_eq!(rpc.labels, [("method".to_string(), "get".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_exposition_reads_text_format_0_0_4() {
+        let text = "# HELP jobs_total Jobs.\n\
+                    # TYPE jobs_total counter\n\
+                    jobs_total 5\n\
+                    # TYPE hits_total counter\n\
+                    hits_total{path=\"/\"} 4 1700000000000\n\
+                    # TYPE depth gauge\n\
+                    depth 2\n";
+
+        let snapshot = parse_exposition(text).unwrap();
+
+        assert_eq!(
+            snapshot.get("jobs_total").unwrap().value,
+            SnapshotValue::Counter(5)
+        );
+        assert_eq!(
+            snapshot
+                .get_series("hits_total", &[("path", "/")])
+                .unwrap()
+                .value,
+            SnapshotValue::Counter(4)
+        );
+        assert_eq!(
+            snapshot.get("depth").unwrap().value,
+            SnapshotValue::Gauge(2)
+        );
+    }
+
+    #[test]
+    fn test_parse_exposition_rejects_what_a_snapshot_cant_hold() {
+        let cases = [
+            ("# TYPE rpc summary\nrpc_count 1\n", 1, "can't be held"),
+            ("# TYPE build info\nbuild_info 1\n", 1, "can't be held"),
+            ("# TYPE up untyped\nup 1\n", 1, "can't be held"),
+            ("# HELP up Up.\nup 1\n", 2, "untyped sample"),
+            ("# TYPE up gauge\nup 1\nloose 1\n", 3, "doesn't belong"),
+            ("loose 1\n", 1, "outside of any family"),
+        ];
+
+        for (text, line, expected) in cases {
+            let err = parse_exposition(text).unwrap_err();
+            assert_eq!(err.line, line, "{:?}", text);
+            assert!(err.message.contains(expected), "{:?}: {}", text, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_exposition_reports_line_numbers() {
+        let cases = [
+            ("# TYPE up gauge\nup one\n", 2, "invalid value"),
+            (
+                "# TYPE up gauge\n\nup{a=\"1\" 1\n",
+                3,
+                "malformed label set",
+            ),
+            ("# TYPE up nonsense\n", 1, "unknown type"),
+            ("# TYPE up gauge\nup 1 soon\n", 2, "invalid timestamp"),
+            (
+                "# TYPE latency histogram\nlatency_bucket{le=\"fast\"} 1\n",
+                2,
+                "without a valid le label",
+            ),
+            (
+                "# TYPE up gauge\nup 1\n# TYPE down gauge\n# TYPE up gauge\n",
+                4,
+                "appears more than once",
+            ),
+        ];
+
+        for (text, line, expected) in cases {
+            let err = parse_exposition(text).unwrap_err();
+            assert_eq!(err.line, line, "{:?}", text);
+            assert!(err.message.contains(expected), "{:?}: {}", text, err);
+        }
+    }
+}
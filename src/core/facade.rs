@@ -0,0 +1,477 @@
+//! Routing the `metrics` crate's macros into a registry.
+//!
+//! Libraries instrumented with the `metrics` facade (`counter!`, `gauge!`,
+//! `histogram!`) don't name a backend. Installing a [`MetricsRecorder`]
+//! sends what they record to an
+//! [`ObservabilityRegistry`](super::registry::ObservabilityRegistry), so it
+//! is exported alongside the registry's own metrics.
+
+use super::collector::SeriesInfo;
+use super::deserialise::DeserializeError;
+use super::metrics::{HistogramTrait, MetricKind};
+use super::registry::{MetricBackend, SharedRegistry};
+use ::metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A `metrics` recorder that records into a [`SharedRegistry`].
+///
+/// Counters and gauges are kept by the recorder and rendered through a
+/// [collector](super::collector::Collector) it adds to the registry, so they
+/// support labels and fractional gauge values but don't show up in
+/// [`snapshot`](super::registry::ObservabilityRegistry::snapshot). Histograms are registered
+/// in the registry itself, with the default latency buckets, and labeled
+/// ones become labeled histograms.
+///
+/// Names and label names are made valid for the exposition format by
+/// replacing other characters with `_`, so `http.requests` is exported as
+/// `http_requests`; a `_total` suffix on a counter name is dropped, as
+/// rendering adds it back. A metric whose name is already taken, by a
+/// registry metric or a recorder metric of another kind, or a histogram
+/// whose family has other label names, is recorded nowhere and a warning is
+/// logged the first time. Each key is resolved once: later uses of the same
+/// key reuse the handle without touching the registry's lock.
+/// Descriptions are used as help text; units are ignored.
+///
+/// # Example
+/// ```ignore
+/// let registry = Arc::new(RwLock::new(ObservabilityRegistry::<PrometheusBackend>::new()));
+/// metrics::set_global_recorder(MetricsRecorder::new(&registry)?)?;
+///
+/// metrics::counter!("jobs.completed", "queue" => "emails").increment(1);
+/// // Rendered as jobs_completed_total{queue="emails"} 1
+/// ```
+pub struct MetricsRecorder<B: MetricBackend> {
+    registry: SharedRegistry<B>,
+    state: Arc<RecorderState>,
+}
+
+/// What the recorder keeps itself, shared with the collector it registers.
+#[derive(Default)]
+struct RecorderState {
+    descriptions: Mutex<HashMap<String, String>>,
+    counters: Mutex<HashMap<Key, Arc<CounterCell>>>,
+    gauges: Mutex<HashMap<Key, Arc<GaugeCell>>>,
+    histograms: Mutex<HashMap<Key, Arc<HistogramCell>>>,
+    /// Keys whose name was taken when first used, recorded nowhere
+    rejected: Mutex<HashSet<Key>>,
+}
+
+impl<B: MetricBackend> MetricsRecorder<B> {
+    /// A recorder for `registry`, whose counters and gauges are rendered
+    /// with the registry from now on.
+    pub fn new(registry: &SharedRegistry<B>) -> Result<Self, DeserializeError> {
+        let state = Arc::new(RecorderState::default());
+        let collected = Arc::clone(&state);
+        registry
+            .write()
+            .map_err(|_| DeserializeError::Backend("registry lock poisoned".to_string()))?
+            .register_collector(move || collected.collect());
+
+        Ok(Self {
+            registry: Arc::clone(registry),
+            state,
+        })
+    }
+
+    fn describe(&self, key: KeyName, description: SharedString) {
+        let name = exposition_name(key.as_str());
+        // Histograms are registered already if described after first use
+        let registered = lock(&self.state.histograms)
+            .keys()
+            .any(|key| labels_of(key).is_empty() && exposition_name(key.name()) == name);
+        if registered {
+            if let Ok(mut registry) = self.registry.write() {
+                let _ = registry.set_description(&name, MetricKind::Histogram, &description);
+            }
+        }
+        lock(&self.state.descriptions).insert(name, description.into_owned());
+    }
+
+    /// Check `name` is free to be exported as a `kind` family: not taken by
+    /// a registry metric, nor by a recorder metric of another kind.
+    ///
+    /// Registry histograms are resolved by [`histogram_for`](Self::histogram_for).
+    fn check_name(&self, name: &str, kind: MetricKind) -> Result<(), DeserializeError> {
+        let family_of = |key: &Key| exposition_name(key.name());
+        let counter_family = |key: &Key| {
+            let name = family_of(key);
+            name.strip_suffix("_total").unwrap_or(&name).to_string()
+        };
+        let taken = (kind != MetricKind::Counter
+            && lock(&self.state.counters)
+                .keys()
+                .any(|key| counter_family(key) == name))
+            || (kind != MetricKind::Gauge
+                && lock(&self.state.gauges)
+                    .keys()
+                    .any(|key| family_of(key) == name))
+            || (kind != MetricKind::Histogram
+                && lock(&self.state.histograms)
+                    .keys()
+                    .any(|key| family_of(key) == name))
+            || (kind != MetricKind::Histogram
+                && self
+                    .registry
+                    .read()
+                    .map_err(|_| DeserializeError::Backend("registry lock poisoned".to_string()))?
+                    .contains(name));
+        if taken {
+            return Err(DeserializeError::DuplicateMetricName(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Look up the handle cached for `key` in `handles`, or create one if
+    /// its name is free, caching the outcome either way.
+    fn resolve<T>(
+        &self,
+        key: &Key,
+        handles: &Mutex<HashMap<Key, Arc<T>>>,
+        create: impl FnOnce() -> Result<Arc<T>, DeserializeError>,
+    ) -> Option<Arc<T>> {
+        if let Some(handle) = lock(handles).get(key) {
+            return Some(Arc::clone(handle));
+        }
+        if lock(&self.state.rejected).contains(key) {
+            return None;
+        }
+        match create() {
+            Ok(handle) => Some(Arc::clone(
+                lock(handles).entry(key.clone()).or_insert(handle),
+            )),
+            Err(e) => {
+                log::warn!("Not recording metric {}: {}", key.name(), e);
+                lock(&self.state.rejected).insert(key.clone());
+                None
+            }
+        }
+    }
+
+    /// Find or register the histogram for `key`, returning its observe function.
+    fn histogram_for(&self, key: &Key) -> Result<RecordFn, DeserializeError> {
+        let name = exposition_name(key.name());
+        let labels = labels_of(key);
+        let help = self.state.help(&name);
+        self.check_name(&name, MetricKind::Histogram)?;
+        let mut registry = self
+            .registry
+            .write()
+            .map_err(|_| DeserializeError::Backend("registry lock poisoned".to_string()))?;
+        let taken = || DeserializeError::DuplicateMetricName(name.clone());
+
+        if labels.is_empty() {
+            let histogram = match registry.find_histogram(&name) {
+                Some(histogram) => histogram.clone(),
+                None if registry.contains(&name) => return Err(taken()),
+                None => registry.histogram(&name, help)?,
+            };
+            return Ok(Box::new(move |value| histogram.observe(value)));
+        }
+
+        let family = match registry.find_labeled_histogram(&name) {
+            Some(family) => family.clone(),
+            None if registry.contains(&name) => return Err(taken()),
+            None => {
+                let label_names = labels.iter().map(|(name, _)| name.clone()).collect();
+                registry.labeled_histogram(&name, help, label_names)?
+            }
+        };
+        // Values in the family's label order; a different set of label names is a mismatch
+        let values = family
+            .label_names()
+            .iter()
+            .map(|label| {
+                labels
+                    .iter()
+                    .find(|(name, _)| name == label)
+                    .map(|(_, value)| value.as_str())
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|values| values.len() == labels.len())
+            .ok_or_else(taken)?;
+        let series = family
+            .histogram_series(&values)
+            .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        Ok(Box::new(move |value| series.observe(value)))
+    }
+}
+
+impl<B: MetricBackend> Recorder for MetricsRecorder<B> {
+    fn describe_counter(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        let cell = self.resolve(key, &self.state.counters, || {
+            let name = exposition_name(key.name());
+            self.check_name(
+                name.strip_suffix("_total").unwrap_or(&name),
+                MetricKind::Counter,
+            )?;
+            Ok(Arc::default())
+        });
+        cell.map_or_else(Counter::noop, Counter::from_arc)
+    }
+
+    fn register_gauge(&self, key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        let cell = self.resolve(key, &self.state.gauges, || {
+            self.check_name(&exposition_name(key.name()), MetricKind::Gauge)?;
+            Ok(Arc::default())
+        });
+        cell.map_or_else(Gauge::noop, Gauge::from_arc)
+    }
+
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        let cell = self.resolve(key, &self.state.histograms, || {
+            Ok(Arc::new(HistogramCell(self.histogram_for(key)?)))
+        });
+        cell.map_or_else(Histogram::noop, Histogram::from_arc)
+    }
+}
+
+impl RecorderState {
+    /// The help text for `name`: its description, or the name if undescribed.
+    fn help(&self, name: &str) -> String {
+        lock(&self.descriptions)
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Report every counter and gauge, sorted by name and labels.
+    fn collect(&self) -> Vec<SeriesInfo> {
+        let mut series = Vec::new();
+        for (key, cell) in lock(&self.counters).iter() {
+            let name = exposition_name(key.name());
+            let help = self.help(&name);
+            let name = name.strip_suffix("_total").unwrap_or(&name);
+            let value = cell.0.load(Ordering::Relaxed) as f64;
+            series.push(with_labels(SeriesInfo::counter(name, help, value), key));
+        }
+        for (key, cell) in lock(&self.gauges).iter() {
+            let name = exposition_name(key.name());
+            let help = self.help(&name);
+            let value = f64::from_bits(cell.0.load(Ordering::Relaxed));
+            series.push(with_labels(SeriesInfo::gauge(name, help, value), key));
+        }
+        series.sort_by(|a, b| (a.name(), a.labels()).cmp(&(b.name(), b.labels())));
+        series
+    }
+}
+
+/// Add the labels of `key` to `sample`.
+fn with_labels(sample: SeriesInfo, key: &Key) -> SeriesInfo {
+    labels_of(key)
+        .into_iter()
+        .fold(sample, |sample, (label, value)| {
+            sample.with_label(label, value)
+        })
+}
+
+/// Label pairs of `key`, with names made valid for the exposition format.
+fn labels_of(key: &Key) -> Vec<(String, String)> {
+    key.labels()
+        .map(|label| (exposition_name(label.key()), label.value().to_string()))
+        .collect()
+}
+
+/// Replace characters not allowed in an exposition name with `_`.
+fn exposition_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' => c,
+            _ => '_',
+        })
+        .collect();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Lock `mutex`, carrying on if a panicking thread poisoned it.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Handles - what the `metrics` macros update
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Default)]
+struct CounterCell(AtomicU64);
+
+impl CounterFn for CounterCell {
+    fn increment(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.0.fetch_max(value, Ordering::Relaxed);
+    }
+}
+
+/// A gauge value, stored as the bits of an `f64`.
+#[derive(Default)]
+struct GaugeCell(AtomicU64);
+
+impl GaugeCell {
+    fn update(&self, f: impl Fn(f64) -> f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some(f(f64::from_bits(bits)).to_bits())
+            });
+    }
+}
+
+impl GaugeFn for GaugeCell {
+    fn increment(&self, value: f64) {
+        self.update(|current| current + value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|current| current - value);
+    }
+
+    fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Observes a value in a registered histogram or histogram series.
+type RecordFn = Box<dyn Fn(f64) + Send + Sync>;
+
+struct HistogramCell(RecordFn);
+
+impl HistogramFn for HistogramCell {
+    fn record(&self, value: f64) {
+        (self.0)(value);
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+    use crate::core::exposition::check_exposition;
+    use crate::core::registry::ObservabilityRegistry;
+    use std::sync::RwLock;
+
+    #[test]
+    fn test_macros_record_into_the_registry() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        registry
+            .write()
+            .unwrap()
+            .counter("native_jobs", "Registered directly")
+            .unwrap()
+            .inc();
+        let recorder = MetricsRecorder::new(&registry).unwrap();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            ::metrics::describe_counter!("jobs.completed", "Jobs completed");
+            ::metrics::counter!("jobs.completed", "queue" => "emails").increment(2);
+            ::metrics::counter!("jobs.completed", "queue" => "emails").increment(1);
+            ::metrics::counter!("retries_total").absolute(4);
+            ::metrics::gauge!("pool.load").set(0.5);
+            ::metrics::gauge!("pool.load").increment(0.25);
+            ::metrics::histogram!("task_seconds").record(0.2);
+            ::metrics::histogram!("fetch_seconds", "source" => "db").record(0.3);
+            // Already registered as a counter, so dropped
+            ::metrics::histogram!("native_jobs").record(1.0);
+        });
+
+        let text = registry.read().unwrap().render_text().unwrap();
+        assert!(text.contains("# HELP jobs_completed Jobs completed.\n"));
+        assert!(text.contains("jobs_completed_total{queue=\"emails\"} 3\n"));
+        assert!(text.contains("retries_total 4\n"));
+        assert!(text.contains("# TYPE pool_load gauge\npool_load 0.75\n"));
+        assert!(text.contains("task_seconds_count 1\n"));
+        assert!(text.contains("fetch_seconds_count{source=\"db\"} 1\n"));
+        assert!(text.contains("native_jobs_total 1\n"));
+        assert!(registry
+            .read()
+            .unwrap()
+            .find_histogram("native_jobs")
+            .is_none());
+    }
+
+    #[test]
+    fn test_taken_names_are_recorded_nowhere() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        registry
+            .write()
+            .unwrap()
+            .gauge("queue_depth", "Registered directly")
+            .unwrap()
+            .set(3);
+        let recorder = MetricsRecorder::new(&registry).unwrap();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            // Taken by the registry's gauge
+            ::metrics::counter!("queue_depth").increment(1);
+            ::metrics::gauge!("queue.depth").set(9.0);
+            // Taken by the recorder's own counter
+            ::metrics::counter!("jobs_total").increment(1);
+            ::metrics::gauge!("jobs").set(2.0);
+            ::metrics::histogram!("jobs").record(0.1);
+        });
+
+        let text = registry.read().unwrap().render_text().unwrap();
+        assert!(text.contains("queue_depth 3\n"));
+        assert!(!text.contains("queue_depth_total"));
+        assert!(text.contains("jobs_total 1\n"));
+        assert!(!text.contains("# TYPE jobs gauge"));
+        assert!(!text.contains("# TYPE jobs histogram"));
+        assert_eq!(check_exposition(&text), Ok(()));
+    }
+
+    #[test]
+    fn test_recording_reuses_handles_without_the_write_lock() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        let recorder = MetricsRecorder::new(&registry).unwrap();
+        ::metrics::with_local_recorder(&recorder, || {
+            ::metrics::histogram!("task_seconds").record(0.2);
+        });
+
+        // A write lock would block while the read lock is held
+        let reading = registry.read().unwrap();
+        ::metrics::with_local_recorder(&recorder, || {
+            ::metrics::histogram!("task_seconds").record(0.3);
+            ::metrics::counter!("jobs").increment(1);
+        });
+        drop(reading);
+
+        let text = registry.read().unwrap().render_text().unwrap();
+        assert!(text.contains("task_seconds_count 2\n"));
+        assert!(text.contains("jobs_total 1\n"));
+    }
+
+    #[test]
+    fn test_exposition_name_replaces_invalid_characters() {
+        assert_eq!(exposition_name("http.requests-in"), "http_requests_in");
+        assert_eq!(exposition_name("2xx"), "_2xx");
+        assert_eq!(exposition_name("ns:metric_1"), "ns:metric_1");
+    }
+}
@@ -0,0 +1,163 @@
+//! Metric family definitions registered from code.
+//!
+//! A [`FamilyDescriptor`] carries the same definition as a config entry, so
+//! plugins and other crates can describe their metrics as data and register
+//! them at runtime with
+//! [`ObservabilityRegistry::register_family`](super::registry::ObservabilityRegistry::register_family).
+
+use super::labeled::Labeled;
+use super::metrics::{Metric, MetricKind};
+use super::registry::MetricBackend;
+
+/// The definition of a metric family, mirroring a config entry.
+///
+/// Starting values, metadata and the enabled flag of a config entry have no
+/// counterpart: a descriptor is registered when it is passed in, and
+/// updates go through the returned [`FamilyHandle`].
+///
+/// # Example
+/// ```ignore
+/// let handle = registry.register_family(FamilyDescriptor::Histogram {
+///     name: "plugin_task_seconds".into(),
+///     description: "Plugin task duration".into(),
+///     buckets: Some(vec![0.1, 1.0, 10.0]),
+///     labels: vec!["task".into()],
+///     summary_only: false,
+///     aliases: vec![],
+/// })?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum FamilyDescriptor {
+    Counter {
+        name: String,
+        description: String,
+        /// Extra names the metric is also exported under
+        aliases: Vec<String>,
+    },
+    Gauge {
+        name: String,
+        description: String,
+        /// Label names; when set, one series is kept per combination of values
+        labels: Vec<String>,
+        /// Extra names the metric is also exported under
+        aliases: Vec<String>,
+    },
+    Histogram {
+        name: String,
+        description: String,
+        /// Bucket upper bounds (default: latency buckets)
+        buckets: Option<Vec<f64>>,
+        /// Label names; when set, one series is kept per combination of values
+        labels: Vec<String>,
+        /// Render only the `_sum` and `_count` series, leaving out the buckets
+        summary_only: bool,
+        /// Extra names the metric is also exported under
+        aliases: Vec<String>,
+    },
+}
+
+impl FamilyDescriptor {
+    /// The metric name.
+    pub fn name(&self) -> &str {
+        match self {
+            FamilyDescriptor::Counter { name, .. }
+            | FamilyDescriptor::Gauge { name, .. }
+            | FamilyDescriptor::Histogram { name, .. } => name,
+        }
+    }
+
+    /// The kind of metric this defines.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            FamilyDescriptor::Counter { .. } => MetricKind::Counter,
+            FamilyDescriptor::Gauge { .. } => MetricKind::Gauge,
+            FamilyDescriptor::Histogram { .. } => MetricKind::Histogram,
+        }
+    }
+
+    /// Extra names the metric is also exported under.
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            FamilyDescriptor::Counter { aliases, .. }
+            | FamilyDescriptor::Gauge { aliases, .. }
+            | FamilyDescriptor::Histogram { aliases, .. } => aliases,
+        }
+    }
+}
+
+/// The metric registered for a [`FamilyDescriptor`].
+///
+/// Which variant is returned follows from the descriptor: its kind, and
+/// whether it declares labels.
+pub enum FamilyHandle<B: MetricBackend> {
+    Counter(Metric<B::Counter>),
+    Gauge(Metric<B::Gauge>),
+    Histogram(Metric<B::Histogram>),
+    LabeledGauge(Labeled<B::LabeledGauge>),
+    LabeledHistogram(Labeled<B::LabeledHistogram>),
+}
+
+impl<B: MetricBackend> FamilyHandle<B> {
+    /// The counter, if the family is a counter.
+    pub fn as_counter(&self) -> Option<&Metric<B::Counter>> {
+        match self {
+            FamilyHandle::Counter(counter) => Some(counter),
+            _ => None,
+        }
+    }
+
+    /// The gauge, if the family is an unlabeled gauge.
+    pub fn as_gauge(&self) -> Option<&Metric<B::Gauge>> {
+        match self {
+            FamilyHandle::Gauge(gauge) => Some(gauge),
+            _ => None,
+        }
+    }
+
+    /// The histogram, if the family is an unlabeled histogram.
+    pub fn as_histogram(&self) -> Option<&Metric<B::Histogram>> {
+        match self {
+            FamilyHandle::Histogram(histogram) => Some(histogram),
+            _ => None,
+        }
+    }
+
+    /// The gauge family, if the family is a labeled gauge.
+    pub fn as_labeled_gauge(&self) -> Option<&Labeled<B::LabeledGauge>> {
+        match self {
+            FamilyHandle::LabeledGauge(family) => Some(family),
+            _ => None,
+        }
+    }
+
+    /// The histogram family, if the family is a labeled histogram.
+    pub fn as_labeled_histogram(&self) -> Option<&Labeled<B::LabeledHistogram>> {
+        match self {
+            FamilyHandle::LabeledHistogram(family) => Some(family),
+            _ => None,
+        }
+    }
+
+    /// The kind of the registered metric.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            FamilyHandle::Counter(_) => MetricKind::Counter,
+            FamilyHandle::Gauge(_) | FamilyHandle::LabeledGauge(_) => MetricKind::Gauge,
+            FamilyHandle::Histogram(_) | FamilyHandle::LabeledHistogram(_) => MetricKind::Histogram,
+        }
+    }
+}
+
+impl<B: MetricBackend> Clone for FamilyHandle<B> {
+    fn clone(&self) -> Self {
+        match self {
+            FamilyHandle::Counter(counter) => FamilyHandle::Counter(counter.clone()),
+            FamilyHandle::Gauge(gauge) => FamilyHandle::Gauge(gauge.clone()),
+            FamilyHandle::Histogram(histogram) => FamilyHandle::Histogram(histogram.clone()),
+            FamilyHandle::LabeledGauge(family) => FamilyHandle::LabeledGauge(family.clone()),
+            FamilyHandle::LabeledHistogram(family) => {
+                FamilyHandle::LabeledHistogram(family.clone())
+            }
+        }
+    }
+}
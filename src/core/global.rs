@@ -0,0 +1,99 @@
+//! Process-wide default registry.
+//!
+//! Small programs and scripts can record metrics without passing a
+//! registry around. The registry is created lazily on first use; call
+//! [`set_global_registry`] before that to install a pre-configured one.
+//!
+//! Libraries should keep taking a registry explicitly; this module is only
+//! compiled with the `global` feature so hidden global state stays opt-in.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use observability_kit::core::global::global_registry;
+//!
+//! let jobs = global_registry()
+//!     .write()
+//!     .unwrap()
+//!     .counter("jobs_total", "Jobs processed")?;
+//! jobs.inc();
+//!
+//! println!("{}", global_registry().read().unwrap().render()?.as_str()?);
+//! ```
+//!
+//! The registry is a [`SharedRegistry`], so it can also be served, e.g.
+//! with `StandaloneServer::builder().registry(Arc::clone(global_registry()))`.
+
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use crate::backends::prometheus::PrometheusBackend;
+use crate::core::registry::{ObservabilityRegistry, SharedRegistry};
+
+/// The shared registry behind [`global_registry`].
+pub type GlobalRegistry = SharedRegistry<PrometheusBackend>;
+
+static GLOBAL_REGISTRY: OnceLock<GlobalRegistry> = OnceLock::new();
+
+/// Get the process-wide registry, creating an empty one on first use.
+pub fn global_registry() -> &'static GlobalRegistry {
+    GLOBAL_REGISTRY.get_or_init(|| Arc::new(RwLock::new(ObservabilityRegistry::new())))
+}
+
+/// Install `registry` as the process-wide registry.
+///
+/// Fails, handing the registry back, if the global registry has already
+/// been set or accessed.
+pub fn set_global_registry(
+    registry: ObservabilityRegistry<PrometheusBackend>,
+) -> Result<(), Box<ObservabilityRegistry<PrometheusBackend>>> {
+    GLOBAL_REGISTRY
+        .set(Arc::new(RwLock::new(registry)))
+        .map_err(|shared| {
+            // The rejected handle was never cloned, so this is the only one
+            let lock = Arc::into_inner(shared).expect("rejected registry is unshared");
+            Box::new(lock.into_inner().unwrap_or_else(PoisonError::into_inner))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_signup() {
+        let mut registry = global_registry().write().unwrap();
+        let signups = match registry.find_counter("global_signups_total") {
+            Some(counter) => counter.clone(),
+            None => registry
+                .counter("global_signups_total", "Signups recorded")
+                .unwrap(),
+        };
+        signups.inc();
+    }
+
+    fn record_bulk_signups(count: u64) {
+        let registry = global_registry().read().unwrap();
+        registry
+            .find_counter("global_signups_total")
+            .unwrap()
+            .inc_by(count);
+    }
+
+    #[test]
+    fn test_global_registry_is_shared() {
+        record_signup();
+        record_bulk_signups(2);
+
+        let registry = global_registry().read().unwrap();
+        assert_eq!(
+            registry
+                .find_counter("global_signups_total")
+                .unwrap()
+                .get_counter(),
+            3
+        );
+        drop(registry);
+
+        // Already initialised, so a replacement is handed back
+        assert!(set_global_registry(ObservabilityRegistry::new()).is_err());
+    }
+}
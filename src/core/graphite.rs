@@ -0,0 +1,92 @@
+//! Converting rendered output to the Graphite plaintext protocol.
+//!
+//! Graphite has no labels: every series is a dotted path. Metric names have
+//! their underscores turned into dots, and each label is appended to the
+//! path as `label.value`, so `http_requests_total{method="GET"}` becomes
+//! `http.requests.total.method.GET`.
+
+use super::renderer::parse_sample;
+use std::fmt::Write;
+
+/// Convert text exposition to Graphite `path value timestamp` lines.
+///
+/// `prefix`, if not empty, starts every path. Samples whose value is `NaN`
+/// or infinite are left out, as Graphite can't store them.
+pub(crate) fn to_graphite(text: &str, prefix: &str, timestamp: u64) -> String {
+    let mut output = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((series, labels, rest)) = parse_sample(line) else {
+            continue;
+        };
+        let Some(value) = rest
+            .split_whitespace()
+            .next()
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite())
+        else {
+            continue;
+        };
+
+        if !prefix.is_empty() {
+            output.push_str(prefix);
+            output.push('.');
+        }
+        output.push_str(&series.replace('_', "."));
+        for (label, label_value) in labels {
+            let _ = write!(output, ".{}.{}", label, path_segment(label_value));
+        }
+        let _ = writeln!(output, " {} {}", value, timestamp);
+    }
+    output
+}
+
+/// Make a label value safe to use as one path segment.
+///
+/// Dots would split the segment and whitespace would end the path, so
+/// anything other than letters, digits, `-` and `_` becomes `_`.
+fn path_segment(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_graphite_flattens_names_and_labels() {
+        let text = "# HELP http_requests Requests.\n\
+                    # TYPE http_requests counter\n\
+                    http_requests_total{method=\"GET\",path=\"/api/v1\"} 3\n\
+                    # TYPE latency histogram\n\
+                    latency_bucket{le=\"0.5\"} 1\n\
+                    latency_bucket{le=\"+Inf\"} 2\n\
+                    latency_sum 0.75\n\
+                    # TYPE ratio gauge\n\
+                    ratio NaN\n\
+                    # EOF\n";
+
+        assert_eq!(
+            to_graphite(text, "app", 1_700_000_000),
+            "app.http.requests.total.method.GET.path._api_v1 3 1700000000\n\
+             app.latency.bucket.le.0_5 1 1700000000\n\
+             app.latency.bucket.le._Inf 2 1700000000\n\
+             app.latency.sum 0.75 1700000000\n"
+        );
+        assert_eq!(
+            to_graphite("up 1\n", "", 10),
+            "up 1 10\n",
+            "an empty prefix adds no leading dot"
+        );
+    }
+}
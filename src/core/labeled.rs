@@ -0,0 +1,587 @@
+//! Labeled metric families declared by name.
+//!
+//! Labels known at compile time are best expressed as a label struct (see
+//! the backend modules). Metrics defined in config only know their label
+//! names at runtime, so [`Labeled`] carries them alongside the family and
+//! checks every call against them.
+
+use super::clock::{Clock, SystemClock};
+use super::metrics::{LabeledGaugeTrait, LabeledHistogramTrait, MetricError};
+use super::snapshot::SnapshotGate;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Label set to `"ok"` or `"err"` by [`Labeled::time_result`].
+pub const RESULT_LABEL: &str = "result";
+
+/// A labeled metric family with metadata and fixed label names.
+///
+/// # Example
+/// ```ignore
+/// let latency = registry.labeled_histogram_with_buckets(
+///     "request_duration_seconds",
+///     "Request latency",
+///     vec!["route".into()],
+///     vec![0.1, 0.5, 1.0],
+/// )?;
+///
+/// latency.observe(&["/users"], 0.042)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Labeled<T> {
+    inner: T,
+    name: String,
+    description: String,
+    label_names: Arc<[String]>,
+    /// Set once the registry is frozen; no new label sets are created after
+    frozen: Arc<AtomicBool>,
+    /// Number of label sets created through this wrapper, shared between clones
+    series_count: Arc<AtomicUsize>,
+    /// Held while creating a label set, so each one is counted once
+    creating: Arc<Mutex<()>>,
+    /// Source of the time measured by `time_result`
+    clock: Arc<dyn Clock>,
+    /// Cleared when the owning registry is dropped or replaced
+    registry_live: Arc<AtomicBool>,
+    /// The owning registry's gate, held shared while updating
+    snapshot_gate: Option<SnapshotGate>,
+}
+
+impl<T> Labeled<T> {
+    /// Wrap a family whose series are keyed by `label_names`.
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        label_names: Vec<String>,
+        inner: T,
+    ) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            description: description.into(),
+            label_names: label_names.into(),
+            frozen: Arc::new(AtomicBool::new(false)),
+            series_count: Arc::new(AtomicUsize::new(0)),
+            creating: Arc::new(Mutex::new(())),
+            clock: Arc::new(SystemClock),
+            registry_live: Arc::new(AtomicBool::new(true)),
+            snapshot_gate: None,
+        }
+    }
+
+    /// Refuse new label sets once `frozen` is set, sharing the registry's flag.
+    pub(crate) fn with_freeze(mut self, frozen: Arc<AtomicBool>) -> Self {
+        self.frozen = frozen;
+        self
+    }
+
+    /// Tie the family to a registry, which clears `live` when it goes away
+    /// and holds off updates through `gate` while taking a consistent snapshot.
+    pub(crate) fn with_registry(mut self, live: Arc<AtomicBool>, gate: SnapshotGate) -> Self {
+        self.registry_live = live;
+        self.snapshot_gate = Some(gate);
+        self
+    }
+
+    /// Time [`time_result`](Self::time_result) with `clock` instead of the system clock.
+    pub(crate) fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the metric name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the metric description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Replace the description, returning the previous one.
+    pub(crate) fn replace_description(&mut self, description: impl Into<String>) -> String {
+        std::mem::replace(&mut self.description, description.into())
+    }
+
+    /// Get the label names, in the order values are expected.
+    pub fn label_names(&self) -> &[String] {
+        &self.label_names
+    }
+
+    /// Access the underlying family.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Number of label sets created so far.
+    ///
+    /// Series created directly on the [`inner`](Self::inner) family are not
+    /// counted.
+    pub fn series_count(&self) -> usize {
+        self.series_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if the registry this family was registered in has been
+    /// dropped or replaced.
+    ///
+    /// Updates and new series through an orphaned family fail with
+    /// [`MetricError::OrphanedHandle`].
+    pub fn is_orphaned(&self) -> bool {
+        !self.registry_live.load(Ordering::Acquire)
+    }
+
+    /// Fail if the registry is gone.
+    fn check_live(&self) -> Result<(), MetricError> {
+        if self.is_orphaned() {
+            return Err(MetricError::OrphanedHandle(self.name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Run `update` through the registry's snapshot gate, if any, failing
+    /// instead if the registry is gone.
+    fn gated<R>(&self, update: impl FnOnce() -> Result<R, MetricError>) -> Result<R, MetricError> {
+        self.check_live()?;
+        match &self.snapshot_gate {
+            Some(gate) => gate.update(update),
+            None => update(),
+        }
+    }
+
+    /// Pair `label_values` with the label names, checking the count matches.
+    fn labels<'a>(
+        &'a self,
+        label_values: &[&'a str],
+    ) -> Result<Vec<(&'a str, &'a str)>, MetricError> {
+        if label_values.len() != self.label_names.len() {
+            return Err(MetricError::LabelCount {
+                expected: self.label_names.len(),
+                actual: label_values.len(),
+            });
+        }
+
+        Ok(self
+            .label_names
+            .iter()
+            .map(String::as_str)
+            .zip(label_values.iter().copied())
+            .collect())
+    }
+
+    /// Put a label set given as `(name, value)` pairs in label name order,
+    /// checking it names every label.
+    #[cfg(feature = "prometheus")]
+    fn ordered_labels<'a>(
+        &'a self,
+        label_set: &[(&str, &'a str)],
+    ) -> Result<Vec<(&'a str, &'a str)>, MetricError> {
+        if label_set.len() != self.label_names.len() {
+            return Err(MetricError::LabelCount {
+                expected: self.label_names.len(),
+                actual: label_set.len(),
+            });
+        }
+
+        self.label_names
+            .iter()
+            .map(|name| {
+                label_set
+                    .iter()
+                    .find(|(label, _)| label == name)
+                    .map(|(_, value)| (name.as_str(), *value))
+                    .ok_or_else(|| MetricError::MissingLabel(name.clone()))
+            })
+            .collect()
+    }
+
+    /// Create the series of every label set that doesn't exist yet.
+    ///
+    /// Every set is checked before any series is created. Like any new label
+    /// set, the series are counted and refused once the registry is frozen.
+    #[cfg(feature = "prometheus")]
+    pub(crate) fn init_series(
+        &self,
+        label_sets: &[&[(&str, &str)]],
+        exists: impl Fn(&[(&str, &str)]) -> bool,
+        create: impl Fn(&[(&str, &str)]),
+    ) -> Result<(), MetricError> {
+        let label_sets = label_sets
+            .iter()
+            .map(|label_set| self.ordered_labels(label_set))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.gated(|| {
+            for labels in &label_sets {
+                self.ensure_series(|| exists(labels), || create(labels))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Create the label set with `create` if it doesn't exist yet, failing
+    /// instead if the registry is frozen.
+    fn ensure_series(
+        &self,
+        exists: impl Fn() -> bool,
+        create: impl FnOnce(),
+    ) -> Result<(), MetricError> {
+        if exists() {
+            return Ok(());
+        }
+        if self.frozen.load(Ordering::Acquire) {
+            return Err(MetricError::RegistryFrozen(self.name.clone()));
+        }
+        let _creating = self.creating.lock().unwrap_or_else(PoisonError::into_inner);
+        if !exists() {
+            create();
+            self.series_count.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+impl<T: LabeledHistogramTrait> Labeled<T> {
+    /// Record an observation in the series for `label_values`.
+    ///
+    /// Values are matched to [`label_names`](Self::label_names) by position.
+    pub fn observe(&self, label_values: &[&str], value: f64) -> Result<(), MetricError> {
+        self.gated(|| {
+            self.inner
+                .observe(&self.histogram_labels(label_values)?, value);
+            Ok(())
+        })
+    }
+
+    /// Get a handle to the series for `label_values`, creating it if needed.
+    ///
+    /// Get-or-create: asking again for the same label values returns a
+    /// handle to the existing series rather than a second one, so
+    /// observations through either handle land in the same buckets.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let users = latency.histogram_series(&["/users"])?;
+    /// users.observe(0.042);
+    /// ```
+    pub fn histogram_series(&self, label_values: &[&str]) -> Result<T::Series, MetricError> {
+        self.gated(|| Ok(self.inner.series(&self.histogram_labels(label_values)?)))
+    }
+
+    /// Pair `label_values` with the label names, creating the series if
+    /// needed and refusing new label sets once the registry is frozen.
+    fn histogram_labels<'a>(
+        &'a self,
+        label_values: &[&'a str],
+    ) -> Result<Vec<(&'a str, &'a str)>, MetricError> {
+        let labels = self.labels(label_values)?;
+        self.ensure_series(
+            || self.inner.contains(&labels),
+            || drop(self.inner.series(&labels)),
+        )?;
+        Ok(labels)
+    }
+
+    /// Run `f`, recording how long it took in seconds under
+    /// `result="ok"` or `result="err"`, and return its result.
+    ///
+    /// The family needs a [`RESULT_LABEL`] label; `label_values` gives the
+    /// other labels in order. On a label mismatch, an orphaned family, or a
+    /// frozen registry missing either outcome's series, `f` is not run.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let latency = registry.labeled_histogram("db_query_seconds", "Query latency", vec!["result".into()])?;
+    /// let rows = latency.time_result(&[], || db.query("SELECT 1"))??;
+    /// ```
+    pub fn time_result<R, E>(
+        &self,
+        label_values: &[&str],
+        f: impl FnOnce() -> Result<R, E>,
+    ) -> Result<Result<R, E>, MetricError> {
+        self.check_live()?;
+        let position = self
+            .label_names
+            .iter()
+            .position(|name| name == RESULT_LABEL)
+            .ok_or_else(|| MetricError::MissingLabel(RESULT_LABEL.to_string()))?;
+        if label_values.len() + 1 != self.label_names.len() {
+            return Err(MetricError::LabelCount {
+                expected: self.label_names.len() - 1,
+                actual: label_values.len(),
+            });
+        }
+
+        let values_for = |outcome| {
+            let mut values = label_values.to_vec();
+            values.insert(position, outcome);
+            values
+        };
+        let (ok, err) = (values_for("ok"), values_for("err"));
+        if self.frozen.load(Ordering::Acquire) {
+            for values in [&ok, &err] {
+                if !self.inner.contains(&self.labels(values)?) {
+                    return Err(MetricError::RegistryFrozen(self.name.clone()));
+                }
+            }
+        }
+
+        let start = self.clock.monotonic();
+        let result = f();
+        let elapsed = self.clock.monotonic().duration_since(start);
+
+        // The labels were checked above, so this only fails if the registry
+        // froze while `f` ran; `f`'s result matters more than its timing
+        let values = if result.is_ok() { ok } else { err };
+        let _ = self.observe(&values, elapsed.as_secs_f64());
+        Ok(result)
+    }
+}
+
+impl<T: LabeledGaugeTrait> Labeled<T> {
+    /// Set the series for `label_values` to `value`.
+    pub fn set(&self, label_values: &[&str], value: i64) -> Result<(), MetricError> {
+        self.gated(|| {
+            self.inner.set(&self.gauge_labels(label_values)?, value);
+            Ok(())
+        })
+    }
+
+    /// Increment the series for `label_values` by 1.
+    pub fn inc(&self, label_values: &[&str]) -> Result<(), MetricError> {
+        self.gated(|| {
+            self.inner.inc(&self.gauge_labels(label_values)?);
+            Ok(())
+        })
+    }
+
+    /// Decrement the series for `label_values` by 1.
+    pub fn dec(&self, label_values: &[&str]) -> Result<(), MetricError> {
+        self.gated(|| {
+            self.inner.dec(&self.gauge_labels(label_values)?);
+            Ok(())
+        })
+    }
+
+    /// Get the current value of the series for `label_values`.
+    pub fn get(&self, label_values: &[&str]) -> Result<i64, MetricError> {
+        Ok(self.inner.get(&self.gauge_labels(label_values)?))
+    }
+
+    /// Get a handle to the series for `label_values`, creating it at 0 if needed.
+    ///
+    /// Get-or-create: asking again for the same label values returns a
+    /// handle to the existing series rather than a second one.
+    pub fn gauge_series(&self, label_values: &[&str]) -> Result<T::Series, MetricError> {
+        self.gated(|| Ok(self.inner.series(&self.gauge_labels(label_values)?)))
+    }
+
+    /// Pair `label_values` with the label names, creating the series if
+    /// needed and refusing new label sets once the registry is frozen.
+    fn gauge_labels<'a>(
+        &'a self,
+        label_values: &[&'a str],
+    ) -> Result<Vec<(&'a str, &'a str)>, MetricError> {
+        let labels = self.labels(label_values)?;
+        self.ensure_series(
+            || self.inner.contains(&labels),
+            || drop(self.inner.series(&labels)),
+        )?;
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Observation = (Vec<(String, String)>, f64);
+
+    #[derive(Clone, Default)]
+    struct RecordingFamily(Arc<Mutex<Vec<Observation>>>);
+
+    fn owned(labels: &[(&str, &str)]) -> Vec<(String, String)> {
+        labels
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    impl LabeledHistogramTrait for RecordingFamily {
+        type Series = RecordingSeries;
+
+        fn observe(&self, labels: &[(&str, &str)], value: f64) {
+            self.0.lock().unwrap().push((owned(labels), value));
+        }
+
+        fn series(&self, labels: &[(&str, &str)]) -> RecordingSeries {
+            RecordingSeries(self.clone(), owned(labels))
+        }
+
+        fn contains(&self, labels: &[(&str, &str)]) -> bool {
+            let labels = owned(labels);
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(seen, _)| *seen == labels)
+        }
+    }
+
+    /// Records observations in its family under fixed labels.
+    #[derive(Clone)]
+    struct RecordingSeries(RecordingFamily, Vec<(String, String)>);
+
+    impl crate::core::metrics::HistogramTrait for RecordingSeries {
+        fn observe(&self, value: f64) {
+            self.0 .0.lock().unwrap().push((self.1.clone(), value));
+        }
+    }
+
+    #[test]
+    fn test_labeled_observe_pairs_values_with_names() {
+        let family = RecordingFamily::default();
+        let latency = Labeled::new(
+            "latency_seconds",
+            "Latency",
+            vec!["route".into(), "method".into()],
+            family.clone(),
+        );
+
+        latency.observe(&["/users", "GET"], 0.5).unwrap();
+        assert_eq!(
+            latency.observe(&["/users"], 0.5),
+            Err(MetricError::LabelCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        let recorded = family.0.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(
+            recorded[0].0,
+            vec![
+                ("route".to_string(), "/users".to_string()),
+                ("method".to_string(), "GET".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_result_labels_outcome() {
+        let family = RecordingFamily::default();
+        let latency = Labeled::new(
+            "job_seconds",
+            "Job duration",
+            vec!["result".into(), "queue".into()],
+            family.clone(),
+        );
+
+        assert_eq!(
+            latency.time_result(&["emails"], || Ok::<_, ()>(7)),
+            Ok(Ok(7))
+        );
+        assert_eq!(
+            latency.time_result(&["emails"], || Err::<(), _>("timeout")),
+            Ok(Err("timeout"))
+        );
+
+        let recorded = family.0.lock().unwrap();
+        let outcomes: Vec<_> = recorded
+            .iter()
+            .map(|(labels, _)| labels[0].1.as_str())
+            .collect();
+        assert_eq!(outcomes, vec!["ok", "err"]);
+        assert_eq!(
+            recorded[1].0[1],
+            ("queue".to_string(), "emails".to_string())
+        );
+    }
+
+    #[test]
+    fn test_time_result_reads_the_injected_clock() {
+        use crate::core::clock::MockClock;
+        use std::time::Duration;
+
+        let family = RecordingFamily::default();
+        let clock = MockClock::default();
+        let latency = Labeled::new(
+            "job_seconds",
+            "Job duration",
+            vec!["result".into()],
+            family.clone(),
+        )
+        .with_clock(Arc::new(clock.clone()));
+
+        latency
+            .time_result(&[], || {
+                clock.advance(Duration::from_millis(1500));
+                // Setting the wall clock back doesn't shorten the timing
+                clock.set(std::time::SystemTime::UNIX_EPOCH);
+                Ok::<_, ()>(())
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(family.0.lock().unwrap()[0].1, 1.5);
+    }
+
+    #[test]
+    fn test_time_result_checks_labels_before_running() {
+        let unlabeled = Labeled::new(
+            "job_seconds",
+            "Job duration",
+            vec![],
+            RecordingFamily::default(),
+        );
+        let labeled = Labeled::new(
+            "job_seconds",
+            "Job duration",
+            vec!["result".into()],
+            RecordingFamily::default(),
+        );
+        let mut ran = false;
+
+        assert_eq!(
+            unlabeled.time_result(&[], || {
+                ran = true;
+                Ok::<_, ()>(())
+            }),
+            Err(MetricError::MissingLabel("result".into()))
+        );
+        assert_eq!(
+            labeled.time_result(&["extra"], || {
+                ran = true;
+                Ok::<_, ()>(())
+            }),
+            Err(MetricError::LabelCount {
+                expected: 0,
+                actual: 1
+            })
+        );
+        assert!(!ran);
+    }
+
+    #[test]
+    fn test_time_result_checks_freeze_before_running() {
+        let frozen = Arc::new(AtomicBool::new(true));
+        let latency = Labeled::new(
+            "job_seconds",
+            "Job duration",
+            vec!["result".into()],
+            RecordingFamily::default(),
+        )
+        .with_freeze(frozen);
+        let mut ran = false;
+
+        assert_eq!(
+            latency.time_result(&[], || {
+                ran = true;
+                Ok::<_, ()>(())
+            }),
+            Err(MetricError::RegistryFrozen("job_seconds".into()))
+        );
+        assert!(!ran);
+    }
+}
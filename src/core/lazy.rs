@@ -0,0 +1,317 @@
+//! Metrics registered on first use.
+//!
+//! Libraries with many optional metrics can declare them all up front as
+//! [`LazyMetric`]s; only the ones that are actually updated get registered,
+//! so unused metrics cost no backend memory and stay out of the output.
+
+use super::deserialise::{validate_metric_name, DeserializeError};
+use super::metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
+use super::registry::{MetricBackend, ObservabilityRegistry, SharedRegistry};
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Registers the metric, or finds one registered under the same name.
+type RegisterFn<T> = Box<dyn Fn() -> Result<Metric<T>, DeserializeError> + Send + Sync>;
+
+/// Registers a metric of one kind in a registry, given its name and help.
+type CreateFn<B, T> =
+    fn(&mut ObservabilityRegistry<B>, &str, &str) -> Result<Metric<T>, DeserializeError>;
+
+/// A metric that is registered in a shared registry on its first update.
+///
+/// Reads never register: [`get_counter`](Self::get_counter) and
+/// [`get_gauge`](Self::get_gauge) return `0` until the first update. If a
+/// metric of the same kind is already registered under the name, the lazy
+/// metric uses it instead of registering a second one. The registry can be
+/// the one a standalone server serves, from `StandaloneServer::registry`.
+///
+/// The first update takes the registry's write lock, so don't update a
+/// lazy metric while holding that lock on the same thread. Registration is
+/// only attempted once: if it fails, e.g. because the registry was frozen
+/// in the meantime, the error is kept in
+/// [`registration_error`](Self::registration_error) and every update is
+/// dropped without touching the registry again.
+///
+/// # Example
+/// ```ignore
+/// let registry = Arc::new(RwLock::new(ObservabilityRegistry::<PrometheusBackend>::new()));
+/// let retries = LazyMetric::counter(&registry, "retries", "Retried requests")?;
+///
+/// // Nothing rendered for `retries` yet
+/// retries.inc();
+/// // Now registered, rendered as retries_total 1
+/// ```
+pub struct LazyMetric<T> {
+    name: String,
+    /// The outcome of the one registration attempt, set on first update
+    metric: OnceLock<Result<Metric<T>, DeserializeError>>,
+    register: RegisterFn<T>,
+}
+
+impl<T: Clone + Send + Sync + 'static> LazyMetric<T> {
+    /// A counter registered in `registry` on its first update.
+    ///
+    /// The name is checked now, so a bad name fails here rather than on
+    /// first use.
+    pub fn counter<B>(
+        registry: &SharedRegistry<B>,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<Self, DeserializeError>
+    where
+        B: MetricBackend<Counter = T>,
+    {
+        Self::new(
+            registry,
+            name.into(),
+            help.into(),
+            |registry, name, help| match registry.find_counter(name) {
+                Some(counter) => Ok(counter.clone()),
+                None => registry.counter(name, help),
+            },
+        )
+    }
+
+    /// A gauge registered in `registry` on its first update.
+    pub fn gauge<B>(
+        registry: &SharedRegistry<B>,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<Self, DeserializeError>
+    where
+        B: MetricBackend<Gauge = T>,
+    {
+        Self::new(
+            registry,
+            name.into(),
+            help.into(),
+            |registry, name, help| match registry.find_gauge(name) {
+                Some(gauge) => Ok(gauge.clone()),
+                None => registry.gauge(name, help),
+            },
+        )
+    }
+
+    /// A histogram with default latency buckets, registered in `registry`
+    /// on its first observation.
+    pub fn histogram<B>(
+        registry: &SharedRegistry<B>,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<Self, DeserializeError>
+    where
+        B: MetricBackend<Histogram = T>,
+    {
+        Self::new(
+            registry,
+            name.into(),
+            help.into(),
+            |registry, name, help| match registry.find_histogram(name) {
+                Some(histogram) => Ok(histogram.clone()),
+                None => registry.histogram(name, help),
+            },
+        )
+    }
+
+    fn new<B: MetricBackend>(
+        registry: &SharedRegistry<B>,
+        name: String,
+        help: String,
+        register: CreateFn<B, T>,
+    ) -> Result<Self, DeserializeError> {
+        let max_name_length = registry
+            .read()
+            .map_err(|_| DeserializeError::Backend("registry lock poisoned".to_string()))?
+            .max_name_length();
+        validate_metric_name(&name, max_name_length)?;
+
+        let registry = Arc::clone(registry);
+        let register_name = name.clone();
+        Ok(Self {
+            name,
+            metric: OnceLock::new(),
+            register: Box::new(move || {
+                let mut registry = registry
+                    .write()
+                    .map_err(|_| DeserializeError::Backend("registry lock poisoned".to_string()))?;
+                register(&mut registry, &register_name, &help)
+            }),
+        })
+    }
+
+    /// Get the metric name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true once the metric has been registered.
+    pub fn is_registered(&self) -> bool {
+        matches!(self.metric.get(), Some(Ok(_)))
+    }
+
+    /// Why registering the metric failed, if its one attempt did.
+    pub fn registration_error(&self) -> Option<&DeserializeError> {
+        self.metric.get().and_then(|metric| metric.as_ref().err())
+    }
+
+    /// Get the registered metric, registering it if this is the first use.
+    ///
+    /// Concurrent first updates wait for a single registration. Returns
+    /// `None` if it failed.
+    fn registered(&self) -> Option<&Metric<T>> {
+        let metric = self.metric.get_or_init(|| {
+            (self.register)().inspect_err(|e| {
+                log::warn!(
+                    "Failed to register lazy metric {}, dropping its updates: {}",
+                    self.name,
+                    e
+                )
+            })
+        });
+        metric.as_ref().ok()
+    }
+
+    /// Get the metric if it has been registered, without registering it.
+    fn existing(&self) -> Option<&Metric<T>> {
+        self.metric.get().and_then(|metric| metric.as_ref().ok())
+    }
+}
+
+impl<T> fmt::Debug for LazyMetric<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyMetric")
+            .field("name", &self.name)
+            .field("registered", &matches!(self.metric.get(), Some(Ok(_))))
+            .finish()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Operations - register on first update, then delegate to the metric
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl<T: CounterTrait> LazyMetric<T> {
+    /// Increment the counter by 1.
+    pub fn inc(&self) {
+        if let Some(metric) = self.registered() {
+            metric.inc();
+        }
+    }
+
+    /// Increment the counter by a specific value.
+    pub fn inc_by(&self, value: u64) {
+        if let Some(metric) = self.registered() {
+            metric.inc_by(value);
+        }
+    }
+
+    /// Get the current counter value, `0` if never updated.
+    pub fn get_counter(&self) -> u64 {
+        self.existing().map_or(0, Metric::get_counter)
+    }
+}
+
+impl<T: GaugeTrait> LazyMetric<T> {
+    /// Set the gauge to a specific value.
+    pub fn set(&self, value: i64) {
+        if let Some(metric) = self.registered() {
+            metric.set(value);
+        }
+    }
+
+    /// Increment the gauge by 1.
+    pub fn gauge_inc(&self) {
+        if let Some(metric) = self.registered() {
+            metric.gauge_inc();
+        }
+    }
+
+    /// Increment the gauge by a specific value.
+    pub fn gauge_inc_by(&self, value: i64) {
+        if let Some(metric) = self.registered() {
+            metric.gauge_inc_by(value);
+        }
+    }
+
+    /// Decrement the gauge by 1.
+    pub fn dec(&self) {
+        if let Some(metric) = self.registered() {
+            metric.dec();
+        }
+    }
+
+    /// Decrement the gauge by a specific value.
+    pub fn dec_by(&self, value: i64) {
+        if let Some(metric) = self.registered() {
+            metric.dec_by(value);
+        }
+    }
+
+    /// Get the current gauge value, `0` if never updated.
+    pub fn get_gauge(&self) -> i64 {
+        self.existing().map_or(0, Metric::get_gauge)
+    }
+}
+
+impl<T: HistogramTrait> LazyMetric<T> {
+    /// Record an observation in the histogram.
+    pub fn observe(&self, value: f64) {
+        if let Some(metric) = self.registered() {
+            metric.observe(value);
+        }
+    }
+
+    /// Record a duration in seconds, the Prometheus base unit for time.
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusRegistry;
+    use std::sync::{Barrier, RwLock};
+
+    #[test]
+    fn test_failed_registration_is_kept_and_updates_dropped() {
+        let registry = Arc::new(RwLock::new(PrometheusRegistry::new()));
+        let retries = LazyMetric::counter(&registry, "retries", "Retried requests").unwrap();
+        registry.write().unwrap().freeze();
+
+        retries.inc();
+        retries.inc_by(2);
+
+        assert!(!retries.is_registered());
+        assert!(matches!(
+            retries.registration_error(),
+            Some(DeserializeError::RegistryFrozen(name)) if name == "retries"
+        ));
+        assert_eq!(retries.get_counter(), 0);
+        assert!(!registry.read().unwrap().contains("retries"));
+    }
+
+    #[test]
+    fn test_concurrent_first_updates_register_once() {
+        let registry = Arc::new(RwLock::new(PrometheusRegistry::new()));
+        let retries = LazyMetric::counter(&registry, "retries", "Retried requests").unwrap();
+        let start = Barrier::new(8);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    start.wait();
+                    retries.inc();
+                });
+            }
+        });
+
+        assert!(retries.is_registered());
+        assert_eq!(retries.get_counter(), 8);
+        let output = registry.read().unwrap().render().unwrap();
+        let text = output.as_str().unwrap();
+        assert_eq!(text.matches("# TYPE retries counter").count(), 1);
+        assert!(text.contains("retries_total 8\n"));
+    }
+}
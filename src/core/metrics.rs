@@ -3,6 +3,12 @@
 //! These traits define the interface for metrics that any backend
 //! (Prometheus, OpenTelemetry, StatsD, etc.) can implement.
 
+use super::clock::{Clock, SystemClock};
+use super::snapshot::SnapshotGate;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 /// A monotonically increasing counter.
 ///
 /// Counters are used for values that only go up, such as:
@@ -60,6 +66,55 @@ pub trait GaugeTrait: Clone + Send + Sync + 'static {
 
     /// Get the current gauge value.
     fn get(&self) -> i64;
+
+    /// Set the gauge to `value`, returning the previous value.
+    ///
+    /// The default implementation reads then sets, so an update landing in
+    /// between is lost; backends should override it with an atomic swap.
+    fn swap(&self, value: i64) -> i64 {
+        let previous = self.get();
+        self.set(value);
+        previous
+    }
+}
+
+/// A gauge holding a floating point value.
+///
+/// Use this over [`GaugeTrait`] for fractional values such as ratios,
+/// temperatures or durations. Implementations must store the value exactly,
+/// so `get` returns the bits passed to `set`.
+///
+/// # Example
+/// ```ignore
+/// gauge.set(0.75);         // Set to specific value
+/// gauge.inc_by(0.1);       // Increment by 0.1
+/// let value = gauge.get(); // Get current value
+/// ```
+pub trait FloatGaugeTrait: Clone + Send + Sync + 'static {
+    /// Set the gauge to a specific value.
+    fn set(&self, value: f64);
+
+    /// Increment the gauge by a specific value.
+    fn inc_by(&self, value: f64);
+
+    /// Decrement the gauge by a specific value.
+    fn dec_by(&self, value: f64);
+
+    /// Get the current gauge value.
+    fn get(&self) -> f64;
+}
+
+/// A counter holding a floating point total.
+///
+/// For totals that aren't whole numbers, such as costs or fractional units.
+/// [`Metric::try_inc_by`] rejects negative increments before they reach
+/// the implementation.
+pub trait FloatCounterTrait: Clone + Send + Sync + 'static {
+    /// Increment the counter by a non-negative value.
+    fn inc_by(&self, value: f64);
+
+    /// Get the current counter value.
+    fn get(&self) -> f64;
 }
 
 /// A histogram for recording distributions of values.
@@ -79,29 +134,264 @@ pub trait GaugeTrait: Clone + Send + Sync + 'static {
 pub trait HistogramTrait: Clone + Send + Sync + 'static {
     /// Record an observation in the histogram.
     fn observe(&self, value: f64);
+
+    /// Record `count` observations of the same value.
+    ///
+    /// The default implementation calls [`observe`](Self::observe) `count`
+    /// times, which takes time proportional to `count`; backends that can add
+    /// to a bucket directly should override it, as the Prometheus one does.
+    fn observe_weighted(&self, value: f64, count: u64) {
+        for _ in 0..count {
+            self.observe(value);
+        }
+    }
+}
+
+/// A histogram family with one series per label set.
+///
+/// Labels are given as `(name, value)` pairs in a fixed order; use
+/// [`Labeled`](super::labeled::Labeled) to check them against the label
+/// names the family was declared with.
+pub trait LabeledHistogramTrait: Clone + Send + Sync + 'static {
+    /// Handle to a single series of the family
+    type Series: HistogramTrait;
+
+    /// Record an observation in the series for `labels`.
+    fn observe(&self, labels: &[(&str, &str)], value: f64);
+
+    /// Get the series for `labels`, creating it if it doesn't exist yet.
+    ///
+    /// Asking twice for the same labels returns two handles to one series.
+    fn series(&self, labels: &[(&str, &str)]) -> Self::Series;
+
+    /// Returns true if the series for `labels` exists, without creating it.
+    fn contains(&self, labels: &[(&str, &str)]) -> bool;
+}
+
+/// A gauge family with one series per label set.
+///
+/// Labels are given as in [`LabeledHistogramTrait`].
+pub trait LabeledGaugeTrait: Clone + Send + Sync + 'static {
+    /// Handle to a single series of the family
+    type Series: GaugeTrait;
+
+    /// Set the series for `labels` to `value`.
+    fn set(&self, labels: &[(&str, &str)], value: i64);
+
+    /// Increment the series for `labels` by 1.
+    fn inc(&self, labels: &[(&str, &str)]);
+
+    /// Decrement the series for `labels` by 1.
+    fn dec(&self, labels: &[(&str, &str)]);
+
+    /// Get the current value of the series for `labels`.
+    ///
+    /// Backends may create the series, at 0, if it doesn't exist yet.
+    fn get(&self, labels: &[(&str, &str)]) -> i64;
+
+    /// Get the series for `labels`, creating it at 0 if it doesn't exist yet.
+    ///
+    /// Asking twice for the same labels returns two handles to one series.
+    fn series(&self, labels: &[(&str, &str)]) -> Self::Series;
+
+    /// Returns true if the series for `labels` exists, without creating it.
+    fn contains(&self, labels: &[(&str, &str)]) -> bool;
+}
+
+/// A histogram whose current state can be read back.
+///
+/// Bucket counts are cumulative, as in the exposition format: each count
+/// includes every observation less than or equal to its upper bound. The
+/// last bucket is always `+Inf`, so its count equals [`count`](Self::count).
+pub trait HistogramStatsTrait: HistogramTrait {
+    /// Upper bound and cumulative count of every bucket, ending with `+Inf`.
+    fn bucket_counts(&self) -> Vec<(f64, u64)>;
+
+    /// Sum of all observed values.
+    fn sum(&self) -> f64;
+
+    /// Number of observations.
+    fn count(&self) -> u64;
 }
 
+/// Errors raised by metric operations.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MetricError {
+    #[error("Counters are monotonic and cannot be decremented")]
+    CountersAreMonotonic,
+    #[error("Expected {expected} label values, got {actual}")]
+    LabelCount { expected: usize, actual: usize },
+    #[error("Metric has no '{0}' label")]
+    MissingLabel(String),
+    #[error("Counters can only be incremented by a non-negative number")]
+    NegativeIncrement,
+    #[error("Registry is frozen: '{0}' can't create a new label set")]
+    RegistryFrozen(String),
+    #[error("Metric '{0}' belongs to a registry that was dropped or replaced")]
+    OrphanedHandle(String),
+}
+
+/// The kind of a metric, used to look metrics up by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    /// The lowercase name used in exposition `# TYPE` lines.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+impl std::fmt::Display for MetricKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Marks an update whose time hasn't been read from the clock yet.
+const UPDATE_PENDING: u64 = u64::MAX;
+
 /// A metric with metadata (name and description).
 ///
 /// This is a generic wrapper that works with any metric type
 /// implementing the appropriate trait.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Metric<T> {
     inner: T,
     name: String,
     description: String,
+    /// Nanoseconds since the Unix epoch of the last update, 0 if never updated
+    /// and [`UPDATE_PENDING`] until an update's time is first read.
+    /// Shared between clones so every handle sees the same time.
+    last_updated: Arc<AtomicU64>,
+    /// When the metric was created, reported as OpenMetrics `_created`
+    created: SystemTime,
+    /// Source of the creation and update times
+    clock: Arc<dyn Clock>,
+    /// Whole-number sum of a histogram's observations, once tracking is on.
+    /// Shared between clones like `last_updated`.
+    integer_sum: Arc<OnceLock<AtomicU64>>,
+    /// For a one-shot gauge, whether it was updated since it was last rendered.
+    /// Shared between clones like `last_updated`.
+    one_shot_pending: Arc<OnceLock<AtomicBool>>,
+    /// Cleared when the owning registry is dropped or replaced
+    registry_live: Arc<AtomicBool>,
+    /// The owning registry's gate, held shared while updating
+    snapshot_gate: Option<SnapshotGate>,
+    /// Set once a dropped update has been logged, shared between clones
+    dropped_warned: Arc<AtomicBool>,
 }
 
 impl<T> Metric<T> {
     /// Create a new metric with the given name, description, and inner metric.
     pub fn new(name: impl Into<String>, description: impl Into<String>, inner: T) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
         Self {
             inner,
             name: name.into(),
             description: description.into(),
+            last_updated: Arc::new(AtomicU64::new(0)),
+            created: clock.now(),
+            clock,
+            integer_sum: Arc::new(OnceLock::new()),
+            one_shot_pending: Arc::new(OnceLock::new()),
+            registry_live: Arc::new(AtomicBool::new(true)),
+            snapshot_gate: None,
+            dropped_warned: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Read creation and update times from `clock` instead of the system clock.
+    ///
+    /// The creation time is reset to `clock`'s current time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.created = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// The clock creation and update times are read from.
+    pub(crate) fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Tie the handle to a registry, which clears `live` when it goes away
+    /// and holds off updates through `gate` while taking a consistent snapshot.
+    pub(crate) fn with_registry(mut self, live: Arc<AtomicBool>, gate: SnapshotGate) -> Self {
+        self.registry_live = live;
+        self.snapshot_gate = Some(gate);
+        self
+    }
+
+    /// Run `update` through the registry's snapshot gate, if any.
+    fn gated<R>(&self, update: impl FnOnce() -> R) -> R {
+        match &self.snapshot_gate {
+            Some(gate) => gate.update(update),
+            None => update(),
+        }
+    }
+
+    /// Apply `update` and flag the metric as updated, failing instead if
+    /// the registry is gone.
+    fn record(&self, update: impl FnOnce()) -> Result<(), MetricError> {
+        self.live()?;
+        self.gated(|| {
+            update();
+            self.touch();
+        });
+        Ok(())
+    }
+
+    /// Like [`record`](Self::record), logging the update as dropped instead
+    /// of failing.
+    fn record_or_warn(&self, update: impl FnOnce()) {
+        if let Err(e) = self.record(update) {
+            self.warn_dropped(&e);
+        }
+    }
+
+    /// Log an update dropped because of `error`, the first time for this metric.
+    fn warn_dropped(&self, error: &MetricError) {
+        if !self.dropped_warned.swap(true, Ordering::Relaxed) {
+            log::warn!("Dropping update: {}", error);
+        }
+    }
+
+    /// Returns true if the registry this metric was registered in has been
+    /// dropped or replaced.
+    ///
+    /// Updates through an orphaned handle would never reach any output: the
+    /// `try_*` update methods fail with [`MetricError::OrphanedHandle`], and
+    /// the others drop the update, logging a warning the first time. Metrics
+    /// created outside a registry are never orphaned.
+    pub fn is_orphaned(&self) -> bool {
+        !self.registry_live.load(Ordering::Acquire)
+    }
+
+    /// The metric, or an error if its registry has been dropped or replaced.
+    ///
+    /// For checking once before several updates, or before reads; single
+    /// updates can use the `try_*` methods instead.
+    ///
+    /// # Example
+    /// ```ignore
+    /// requests.live()?.inc();
+    /// ```
+    pub fn live(&self) -> Result<&Self, MetricError> {
+        if self.is_orphaned() {
+            return Err(MetricError::OrphanedHandle(self.name.clone()));
+        }
+        Ok(self)
+    }
+
     /// Get the metric name.
     pub fn name(&self) -> &str {
         &self.name
@@ -116,6 +406,63 @@ impl<T> Metric<T> {
     pub fn inner(&self) -> &T {
         &self.inner
     }
+
+    /// When the metric was created.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// When the metric was last updated through this handle or a clone of it.
+    ///
+    /// Returns `None` if it has never been updated. Updates only flag the
+    /// metric, keeping the clock off the hot path: the time is read when an
+    /// update is first noticed, by this method, a
+    /// [`snapshot`](super::registry::ObservabilityRegistry::snapshot) or a
+    /// staleness check. Updates made directly on the [`inner`](Self::inner)
+    /// metric are not tracked.
+    pub fn last_updated(&self) -> Option<SystemTime> {
+        let nanos = match self.last_updated.load(Ordering::Relaxed) {
+            0 => return None,
+            UPDATE_PENDING => {
+                let now = self
+                    .clock
+                    .now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(1, |elapsed| {
+                        elapsed.as_nanos().clamp(1, UPDATE_PENDING as u128 - 1) as u64
+                    });
+                // Another reader may have stamped the update first
+                match self.last_updated.compare_exchange(
+                    UPDATE_PENDING,
+                    now,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => now,
+                    Err(stamped) => stamped,
+                }
+            }
+            nanos => nanos,
+        };
+        Some(UNIX_EPOCH + Duration::from_nanos(nanos))
+    }
+
+    /// Record that the metric was just updated.
+    fn touch(&self) {
+        // Skip the store when already flagged, so hot metrics don't keep
+        // writing to a cache line shared between threads
+        if self.last_updated.load(Ordering::Relaxed) != UPDATE_PENDING {
+            self.last_updated.store(UPDATE_PENDING, Ordering::Relaxed);
+        }
+        if let Some(pending) = self.one_shot_pending.get() {
+            pending.store(true, Ordering::Release);
+        }
+    }
+
+    /// Replace the description, returning the previous one.
+    pub(crate) fn replace_description(&mut self, description: impl Into<String>) -> String {
+        std::mem::replace(&mut self.description, description.into())
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -125,18 +472,64 @@ impl<T> Metric<T> {
 impl<T: CounterTrait> Metric<T> {
     /// Increment the counter by 1.
     pub fn inc(&self) {
-        self.inner.inc();
+        self.record_or_warn(|| self.inner.inc());
+    }
+
+    /// Like [`inc`](Self::inc), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_inc(&self) -> Result<(), MetricError> {
+        self.record(|| self.inner.inc())
     }
 
     /// Increment the counter by a specific value.
     pub fn inc_by(&self, value: u64) {
-        self.inner.inc_by(value);
+        self.record_or_warn(|| self.inner.inc_by(value));
+    }
+
+    /// Like [`inc_by`](Self::inc_by), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_inc_by(&self, value: u64) -> Result<(), MetricError> {
+        self.record(|| self.inner.inc_by(value))
     }
 
     /// Get the current counter value.
     pub fn get_counter(&self) -> u64 {
         self.inner.get()
     }
+
+    /// Attempt to decrement the counter.
+    ///
+    /// Counters only ever go up, so this always fails with
+    /// [`MetricError::CountersAreMonotonic`]. It exists so code that treats
+    /// metrics generically gets an explicit error instead of guessing; use a
+    /// gauge for values that can decrease.
+    pub fn try_dec(&self) -> Result<(), MetricError> {
+        Err(MetricError::CountersAreMonotonic)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Float counter operations - delegated to inner type
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl<T: FloatCounterTrait> Metric<T> {
+    /// Increment the float counter by `value`.
+    ///
+    /// Fails with [`MetricError::NegativeIncrement`] if `value` is negative
+    /// or NaN, and with [`MetricError::OrphanedHandle`] if the registry is
+    /// gone, leaving the counter unchanged.
+    pub fn try_inc_by_f64(&self, value: f64) -> Result<(), MetricError> {
+        if value < 0.0 || value.is_nan() {
+            self.live()?;
+            return Err(MetricError::NegativeIncrement);
+        }
+        self.record(|| self.inner.inc_by(value))
+    }
+
+    /// Get the current float counter value.
+    pub fn get_counter_f64(&self) -> f64 {
+        self.inner.get()
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -146,33 +539,163 @@ impl<T: CounterTrait> Metric<T> {
 impl<T: GaugeTrait> Metric<T> {
     /// Set the gauge to a specific value.
     pub fn set(&self, value: i64) {
-        self.inner.set(value);
+        self.record_or_warn(|| self.inner.set(value));
+    }
+
+    /// Like [`set`](Self::set), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_set(&self, value: i64) -> Result<(), MetricError> {
+        self.record(|| self.inner.set(value))
     }
 
     /// Increment the gauge by 1.
     pub fn gauge_inc(&self) {
-        self.inner.inc();
+        self.record_or_warn(|| self.inner.inc());
+    }
+
+    /// Like [`gauge_inc`](Self::gauge_inc), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_inc(&self) -> Result<(), MetricError> {
+        self.record(|| self.inner.inc())
     }
 
     /// Increment the gauge by a specific value.
     pub fn gauge_inc_by(&self, value: i64) {
-        self.inner.inc_by(value);
+        self.record_or_warn(|| self.inner.inc_by(value));
+    }
+
+    /// Like [`gauge_inc_by`](Self::gauge_inc_by), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_inc_by(&self, value: i64) -> Result<(), MetricError> {
+        self.record(|| self.inner.inc_by(value))
     }
 
     /// Decrement the gauge by 1.
     pub fn dec(&self) {
-        self.inner.dec();
+        self.record_or_warn(|| self.inner.dec());
+    }
+
+    /// Like [`dec`](Self::dec), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_dec(&self) -> Result<(), MetricError> {
+        self.record(|| self.inner.dec())
     }
 
     /// Decrement the gauge by a specific value.
     pub fn dec_by(&self, value: i64) {
-        self.inner.dec_by(value);
+        self.record_or_warn(|| self.inner.dec_by(value));
+    }
+
+    /// Like [`dec_by`](Self::dec_by), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_dec_by(&self, value: i64) -> Result<(), MetricError> {
+        self.record(|| self.inner.dec_by(value))
     }
 
     /// Get the current gauge value.
     pub fn get_gauge(&self) -> i64 {
         self.inner.get()
     }
+
+    /// Make this a one-shot gauge, exported only by the first render after
+    /// each update. Updates made before this call are not exported.
+    pub(crate) fn set_one_shot(&self) {
+        self.one_shot_pending.get_or_init(|| AtomicBool::new(false));
+    }
+
+    /// Returns true if the one-shot gauge was updated since it was last taken.
+    pub(crate) fn has_one_shot_update(&self) -> bool {
+        self.one_shot_pending
+            .get()
+            .is_some_and(|pending| pending.load(Ordering::Acquire))
+    }
+
+    /// Take the one-shot gauge's pending update, resetting the gauge to zero.
+    ///
+    /// Returns the value to export, or `None` if there was no update. The
+    /// value is swapped out atomically, so an update racing with this one is
+    /// kept for the next take rather than lost.
+    pub(crate) fn take_one_shot(&self) -> Option<i64> {
+        let pending = self.one_shot_pending.get()?;
+        if !pending.swap(false, Ordering::AcqRel) {
+            return None;
+        }
+        Some(self.inner.swap(0))
+    }
+
+    /// Increment the gauge now and decrement it when the returned guard drops.
+    ///
+    /// The decrement also happens on early returns and panics, so the gauge
+    /// stays accurate as an in-flight count.
+    ///
+    /// # Example
+    /// ```ignore
+    /// async fn handle(metrics: &AppMetrics) {
+    ///     let _inflight = metrics.requests_in_flight.track_inflight();
+    ///     // ... handle the request ...
+    /// }
+    /// ```
+    pub fn track_inflight(&self) -> InflightGuard<'_, T> {
+        self.gauge_inc();
+        InflightGuard { gauge: self }
+    }
+}
+
+/// Guard returned by [`Metric::track_inflight`]; decrements the gauge on drop.
+#[must_use = "the gauge is decremented as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct InflightGuard<'a, T: GaugeTrait> {
+    gauge: &'a Metric<T>,
+}
+
+impl<T: GaugeTrait> Drop for InflightGuard<'_, T> {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Float gauge operations - delegated to inner type
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl<T: FloatGaugeTrait> Metric<T> {
+    /// Set the float gauge to a specific value.
+    pub fn set_f64(&self, value: f64) {
+        self.record_or_warn(|| self.inner.set(value));
+    }
+
+    /// Like [`set_f64`](Self::set_f64), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_set_f64(&self, value: f64) -> Result<(), MetricError> {
+        self.record(|| self.inner.set(value))
+    }
+
+    /// Increment the float gauge by a specific value.
+    pub fn inc_by_f64(&self, value: f64) {
+        self.record_or_warn(|| self.inner.inc_by(value));
+    }
+
+    /// Like [`inc_by_f64`](Self::inc_by_f64), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_inc_by_f64(&self, value: f64) -> Result<(), MetricError> {
+        self.record(|| self.inner.inc_by(value))
+    }
+
+    /// Decrement the float gauge by a specific value.
+    pub fn dec_by_f64(&self, value: f64) {
+        self.record_or_warn(|| self.inner.dec_by(value));
+    }
+
+    /// Like [`dec_by_f64`](Self::dec_by_f64), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_gauge_dec_by_f64(&self, value: f64) -> Result<(), MetricError> {
+        self.record(|| self.inner.dec_by(value))
+    }
+
+    /// Get the current float gauge value.
+    pub fn get_gauge_f64(&self) -> f64 {
+        self.inner.get()
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -182,7 +705,133 @@ impl<T: GaugeTrait> Metric<T> {
 impl<T: HistogramTrait> Metric<T> {
     /// Record an observation in the histogram.
     pub fn observe(&self, value: f64) {
-        self.inner.observe(value);
+        self.record_or_warn(|| {
+            self.inner.observe(value);
+            self.add_to_integer_sum(value, 1);
+        });
+    }
+
+    /// Like [`observe`](Self::observe), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_observe(&self, value: f64) -> Result<(), MetricError> {
+        self.record(|| {
+            self.inner.observe(value);
+            self.add_to_integer_sum(value, 1);
+        })
+    }
+
+    /// Record `count` observations of `value`, e.g. from pre-aggregated data.
+    ///
+    /// Adds `count` to the matching buckets and `value * count` to the sum.
+    pub fn observe_weighted(&self, value: f64, count: u64) {
+        if let Err(e) = self.try_observe_weighted(value, count) {
+            self.warn_dropped(&e);
+        }
+    }
+
+    /// Like [`observe_weighted`](Self::observe_weighted), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_observe_weighted(&self, value: f64, count: u64) -> Result<(), MetricError> {
+        if count == 0 {
+            return self.live().map(drop);
+        }
+        self.record(|| {
+            self.inner.observe_weighted(value, count);
+            self.add_to_integer_sum(value, count);
+        })
+    }
+
+    /// Record a duration in seconds, the Prometheus base unit for time.
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+
+    /// Like [`observe_duration`](Self::observe_duration), but fails with
+    /// [`MetricError::OrphanedHandle`] if the registry is gone.
+    pub fn try_observe_duration(&self, duration: Duration) -> Result<(), MetricError> {
+        self.try_observe(duration.as_secs_f64())
+    }
+
+    /// Record every finite value from `values`, skipping `NaN` and `±Inf`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let counts = latency.observe_iter(samples.iter().copied());
+    /// if counts.skipped > 0 {
+    ///     warn!("dropped {} non-finite samples", counts.skipped);
+    /// }
+    /// ```
+    pub fn observe_iter(&self, values: impl IntoIterator<Item = f64>) -> ObserveCounts {
+        let mut counts = ObserveCounts::default();
+        if let Err(e) = self.live() {
+            self.warn_dropped(&e);
+            return counts;
+        }
+        self.gated(|| {
+            for value in values {
+                if value.is_finite() {
+                    self.inner.observe(value);
+                    self.add_to_integer_sum(value, 1);
+                    counts.recorded += 1;
+                } else {
+                    counts.skipped += 1;
+                }
+            }
+            if counts.recorded > 0 {
+                self.touch();
+            }
+        });
+        counts
+    }
+}
+
+impl<T> Metric<T> {
+    /// Start keeping a whole-number sum of the observations made from now on.
+    ///
+    /// Observations are truncated towards zero, and negative ones add
+    /// nothing. Calling it again keeps the sum so far.
+    pub(crate) fn track_integer_sum(&self) {
+        self.integer_sum.get_or_init(|| AtomicU64::new(0));
+    }
+
+    /// Whole-number sum of the observations, if it is being tracked.
+    ///
+    /// See [`ObservabilityRegistry::set_integer_sum`](super::registry::ObservabilityRegistry::set_integer_sum).
+    pub fn integer_sum(&self) -> Option<u64> {
+        self.integer_sum
+            .get()
+            .map(|sum| sum.load(Ordering::Relaxed))
+    }
+
+    fn add_to_integer_sum(&self, value: f64, count: u64) {
+        if let Some(sum) = self.integer_sum.get() {
+            sum.fetch_add((value as u64).saturating_mul(count), Ordering::Relaxed);
+        }
+    }
+}
+
+/// How many values [`Metric::observe_iter`] recorded and skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObserveCounts {
+    pub recorded: usize,
+    /// Values skipped for being `NaN` or infinite
+    pub skipped: usize,
+}
+
+impl<T: HistogramStatsTrait> Metric<T> {
+    /// Upper bound and cumulative count of every bucket, ending with `+Inf`.
+    pub fn bucket_counts(&self) -> Vec<(f64, u64)> {
+        self.inner.bucket_counts()
+    }
+
+    /// Sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.inner.sum()
+    }
+
+    /// Number of observations.
+    pub fn count(&self) -> u64 {
+        self.inner.count()
     }
 }
 
@@ -227,4 +876,108 @@ mod tests {
         counter.inc_by(10);
         assert_eq!(counter.get_counter(), 11);
     }
+
+    #[test]
+    fn test_last_updated_is_shared_between_clones() {
+        let counter = Metric::new("test_total", "A counter", TestCounter::default());
+        let clone = counter.clone();
+        assert_eq!(counter.last_updated(), None);
+
+        let before = SystemTime::now();
+        clone.inc();
+        let updated = counter.last_updated().unwrap();
+        assert!(updated >= before);
+        assert!(updated <= SystemTime::now());
+    }
+
+    #[derive(Clone, Default)]
+    struct TestGauge(std::sync::Arc<std::sync::atomic::AtomicI64>);
+
+    impl GaugeTrait for TestGauge {
+        fn set(&self, value: i64) {
+            self.0.store(value, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn inc(&self) {
+            self.inc_by(1);
+        }
+
+        fn inc_by(&self, value: i64) {
+            self.0
+                .fetch_add(value, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn dec(&self) {
+            self.dec_by(1);
+        }
+
+        fn dec_by(&self, value: i64) {
+            self.0
+                .fetch_sub(value, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn get(&self) -> i64 {
+            self.0.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn test_track_inflight_returns_to_zero() {
+        let gauge = Metric::new("in_flight", "In-flight requests", TestGauge::default());
+
+        let outer = gauge.track_inflight();
+        {
+            let _a = gauge.track_inflight();
+            let _b = gauge.track_inflight();
+            assert_eq!(gauge.get_gauge(), 3);
+        }
+        assert_eq!(gauge.get_gauge(), 1);
+
+        let early_return = |fail: bool| -> Result<(), ()> {
+            let _guard = gauge.track_inflight();
+            if fail {
+                return Err(());
+            }
+            Ok(())
+        };
+        assert!(early_return(true).is_err());
+        assert!(early_return(false).is_ok());
+        assert_eq!(gauge.get_gauge(), 1);
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = gauge.track_inflight();
+            panic!("handler failed");
+        }));
+        assert!(panicked.is_err());
+
+        drop(outer);
+        assert_eq!(gauge.get_gauge(), 0);
+    }
+
+    #[test]
+    fn test_take_one_shot_swaps_out_the_value() {
+        let gauge = Metric::new("evicted", "Entries evicted", TestGauge::default());
+        gauge.set(5);
+        gauge.set_one_shot();
+        assert_eq!(gauge.take_one_shot(), None, "updates before set_one_shot");
+
+        gauge.set(12);
+        assert!(gauge.has_one_shot_update());
+        assert_eq!(gauge.take_one_shot(), Some(12));
+        assert_eq!(gauge.get_gauge(), 0);
+        assert_eq!(gauge.take_one_shot(), None);
+
+        // An update after the take is kept for the next one
+        gauge.gauge_inc_by(3);
+        assert_eq!(gauge.take_one_shot(), Some(3));
+    }
+
+    #[test]
+    fn test_counter_try_dec_is_rejected() {
+        let counter = Metric::new("test_total", "A counter", TestCounter::default());
+        counter.inc_by(3);
+
+        assert_eq!(counter.try_dec(), Err(MetricError::CountersAreMonotonic));
+        assert_eq!(counter.get_counter(), 3);
+    }
 }
@@ -3,10 +3,63 @@
 //! This module contains backend-agnostic abstractions that any metric
 //! system can implement.
 
+pub mod builder;
+pub mod clock;
+pub mod collector;
+pub mod composite;
+pub mod deserialise;
+#[cfg(feature = "tokio")]
+pub mod export;
+pub mod exposition;
+#[cfg(feature = "metrics-facade")]
+pub mod facade;
+pub mod family;
+#[cfg(feature = "global")]
+pub mod global;
+#[cfg(feature = "graphite")]
+pub mod graphite;
+pub mod labeled;
+pub mod lazy;
 pub mod metrics;
+pub mod rate;
 pub mod registry;
 pub mod renderer;
+pub mod smoothed;
+pub mod snapshot;
+pub mod tracking;
 
-pub use metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
-pub use registry::{MetricBackend, ObservabilityRegistry};
-pub use renderer::{MetricsRenderer, RenderedMetrics};
+pub use builder::{Empty, Populated, RegistryBuilder};
+#[cfg(feature = "test-utils")]
+pub use clock::MockClock;
+pub use clock::{Clock, SystemClock};
+pub use collector::{Collector, SeriesInfo};
+pub use composite::CompositeRegistry;
+pub use deserialise::DeserializeError;
+#[cfg(feature = "tokio")]
+pub use export::{spawn_periodic_exporter, ExporterHandle};
+pub use exposition::{check_exposition, parse_exposition, ParseError};
+#[cfg(feature = "metrics-facade")]
+pub use facade::MetricsRecorder;
+pub use family::{FamilyDescriptor, FamilyHandle};
+#[cfg(feature = "global")]
+pub use global::{global_registry, set_global_registry, GlobalRegistry};
+pub use labeled::{Labeled, RESULT_LABEL};
+pub use lazy::LazyMetric;
+pub use metrics::{
+    CounterTrait, FloatCounterTrait, FloatGaugeTrait, GaugeTrait, HistogramStatsTrait,
+    HistogramTrait, InflightGuard, LabeledGaugeTrait, LabeledHistogramTrait, Metric, MetricError,
+    MetricKind, ObserveCounts,
+};
+pub use rate::RateCounter;
+pub use registry::{
+    openmetrics_content_type, prometheus_content_type, MetricBackend, ObservabilityRegistry,
+    Register, SharedRegistry, BUILD_INFO_METRIC, OPENMETRICS_CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE,
+};
+#[cfg(feature = "redaction")]
+pub use renderer::REDACTED_LABEL_VALUE;
+pub use renderer::{MetricsRenderer, RenderError, RenderedMetrics};
+pub use smoothed::SmoothedGauge;
+pub use snapshot::{
+    MetricDescription, MetricSnapshot, MetricsSnapshot, SnapshotGate, SnapshotValue,
+};
+pub use tracking::TrackingGauge;
@@ -0,0 +1,151 @@
+//! Application-side rate estimation for counters.
+//!
+//! Prometheus computes rates at query time, but dashboards that read values
+//! straight from the process need the rate computed locally.
+
+use super::clock::Clock;
+use super::metrics::{CounterTrait, Metric};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of slots the window is divided into when recording increments.
+///
+/// Increments landing in the same slot are merged, which bounds memory use
+/// regardless of how often the counter is incremented.
+const WINDOW_SLOTS: u32 = 64;
+
+/// A counter that can report its per-second rate over a sliding window.
+///
+/// Increments are forwarded to the wrapped counter, so the exported value is
+/// unaffected. The rate is computed lazily when [`rate_per_sec`](Self::rate_per_sec)
+/// is called; no background task is involved.
+///
+/// # Example
+/// ```ignore
+/// let requests = RateCounter::new(counter("requests_total", "Requests"), Duration::from_secs(10));
+///
+/// requests.inc();
+/// println!("{:.1} req/s", requests.rate_per_sec());
+/// ```
+#[derive(Debug)]
+pub struct RateCounter<T: CounterTrait> {
+    counter: Metric<T>,
+    window: Duration,
+    clock: Arc<dyn Clock>,
+    created: Instant,
+    samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl<T: CounterTrait> RateCounter<T> {
+    /// Wrap a counter, estimating its rate over `window`.
+    pub fn new(counter: Metric<T>, window: Duration) -> Self {
+        let clock = Arc::clone(counter.clock());
+        Self {
+            counter,
+            window,
+            created: clock.monotonic(),
+            clock,
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Increment the counter by 1.
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    /// Increment the counter by a specific value.
+    pub fn inc_by(&self, value: u64) {
+        self.counter.inc_by(value);
+
+        let now = self.clock.monotonic();
+        let slot = self.window / WINDOW_SLOTS;
+        let mut samples = self.samples.lock().unwrap();
+
+        match samples.back_mut() {
+            Some((started, total)) if now.duration_since(*started) < slot => *total += value,
+            _ => samples.push_back((now, value)),
+        }
+        Self::prune(&mut samples, now, self.window);
+    }
+
+    /// Get the current counter value.
+    pub fn get(&self) -> u64 {
+        self.counter.get_counter()
+    }
+
+    /// Average increments per second over the window.
+    ///
+    /// Until a full window has elapsed since creation, the average is taken
+    /// over the time elapsed so far.
+    pub fn rate_per_sec(&self) -> f64 {
+        let now = self.clock.monotonic();
+        let mut samples = self.samples.lock().unwrap();
+        Self::prune(&mut samples, now, self.window);
+
+        let span = now.duration_since(self.created).min(self.window);
+        if span.is_zero() {
+            return 0.0;
+        }
+
+        let total: u64 = samples.iter().map(|(_, value)| value).sum();
+        total as f64 / span.as_secs_f64()
+    }
+
+    /// Access the wrapped counter.
+    pub fn metric(&self) -> &Metric<T> {
+        &self.counter
+    }
+
+    /// Drop samples that fell out of the window.
+    fn prune(samples: &mut VecDeque<(Instant, u64)>, now: Instant, window: Duration) {
+        while let Some((at, _)) = samples.front() {
+            if now.duration_since(*at) > window {
+                samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::mock::MockCounter;
+    use crate::core::clock::MockClock;
+
+    fn rate_counter(clock: &MockClock, window: Duration) -> RateCounter<MockCounter> {
+        let counter = Metric::new("requests_total", "Requests", MockCounter::new())
+            .with_clock(Arc::new(clock.clone()));
+        RateCounter::new(counter, window)
+    }
+
+    #[test]
+    fn test_rate_counter_tracks_cadence() {
+        let clock = MockClock::default();
+        let counter = rate_counter(&clock, Duration::from_secs(5));
+
+        // 20 increments 10ms apart is 100/s
+        for _ in 0..20 {
+            counter.inc();
+            clock.advance(Duration::from_millis(10));
+        }
+
+        assert_eq!(counter.rate_per_sec(), 100.0);
+        assert_eq!(counter.get(), 20);
+    }
+
+    #[test]
+    fn test_rate_counter_forgets_old_increments() {
+        let clock = MockClock::default();
+        let counter = rate_counter(&clock, Duration::from_millis(50));
+
+        counter.inc_by(100);
+        clock.advance(Duration::from_millis(80));
+
+        assert_eq!(counter.rate_per_sec(), 0.0);
+        assert_eq!(counter.metric().get_counter(), 100);
+    }
+}
@@ -3,8 +3,68 @@
 //! This module provides a unified interface for creating, registering,
 //! and rendering metrics across different backends.
 
-use super::metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
-use super::renderer::{MetricsRenderer, RenderedMetrics};
+use super::clock::{Clock, SystemClock};
+use super::collector::{render_series, Collector};
+use super::deserialise::{
+    escape_help, escape_label_value, normalize_and_validate_buckets, validate_label_name,
+    validate_metric_name, DeserializeError, DEFAULT_MAX_BUCKETS, DEFAULT_MAX_NAME_LENGTH,
+};
+use super::exposition::{check_exposition, parse_exposition};
+use super::family::{FamilyDescriptor, FamilyHandle};
+use super::labeled::Labeled;
+use super::metrics::{
+    CounterTrait, FloatCounterTrait, GaugeTrait, HistogramStatsTrait, HistogramTrait,
+    LabeledGaugeTrait, LabeledHistogramTrait, Metric, MetricKind,
+};
+use super::renderer::{
+    append_family, drop_bucket_samples, drop_samples, mirror_families, replace_gauge_values,
+    replace_help_text, replace_sum_values, sort_labels, split_families, MetricsRenderer,
+    RenderError, RenderedMetrics,
+};
+use super::snapshot::{
+    MetricDescription, MetricSnapshot, MetricsSnapshot, SnapshotGate, SnapshotValue,
+};
+use super::tracking::{TrackingGauge, MAX_SUFFIX, MIN_SUFFIX};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Content type of the Prometheus text exposition format, as served by
+/// [`ObservabilityRegistry::render`] output.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Content type of [`ObservabilityRegistry::render_openmetrics`] output.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// The `Content-Type` header value for Prometheus text exposition.
+///
+/// For handlers that render metrics themselves and set headers by hand.
+pub const fn prometheus_content_type() -> &'static str {
+    PROMETHEUS_CONTENT_TYPE
+}
+
+/// The `Content-Type` header value for OpenMetrics exposition.
+pub const fn openmetrics_content_type() -> &'static str {
+    OPENMETRICS_CONTENT_TYPE
+}
+
+/// Name of the gauge added by [`ObservabilityRegistry::with_build_info`].
+pub const BUILD_INFO_METRIC: &str = "build_info";
+
+/// Sequence number that keeps temporary files from concurrent
+/// [`ObservabilityRegistry::write_to_file`] calls apart.
+static TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Latency buckets used by histograms registered without explicit buckets.
+const DEFAULT_HISTOGRAM_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
 
 /// Trait that defines what a backend must provide.
 ///
@@ -17,12 +77,21 @@ pub trait MetricBackend: Send + Sync + 'static {
     /// The counter type for this backend
     type Counter: CounterTrait;
 
+    /// The floating point counter type for this backend
+    type FloatCounter: FloatCounterTrait;
+
     /// The gauge type for this backend
     type Gauge: GaugeTrait;
 
     /// The histogram type for this backend
     type Histogram: HistogramTrait;
 
+    /// The labeled histogram family type for this backend
+    type LabeledHistogram: LabeledHistogramTrait;
+
+    /// The labeled gauge family type for this backend
+    type LabeledGauge: LabeledGaugeTrait;
+
     /// Error type for registration failures
     type Error: std::error::Error + Send + Sync;
 
@@ -36,6 +105,13 @@ pub trait MetricBackend: Send + Sync + 'static {
         help: &str,
     ) -> Result<Self::Counter, Self::Error>;
 
+    /// Create and register a floating point counter
+    fn register_float_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+    ) -> Result<Self::FloatCounter, Self::Error>;
+
     /// Create and register a gauge
     fn register_gauge(
         registry: &mut Self::Registry,
@@ -50,8 +126,94 @@ pub trait MetricBackend: Send + Sync + 'static {
         help: &str,
         buckets: Vec<f64>,
     ) -> Result<Self::Histogram, Self::Error>;
+
+    /// Create and register a histogram family keyed by `label_names`
+    fn register_labeled_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        label_names: &[String],
+        buckets: Vec<f64>,
+    ) -> Result<Self::LabeledHistogram, Self::Error>;
+
+    /// Create and register a gauge family keyed by `label_names`
+    fn register_labeled_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        label_names: &[String],
+    ) -> Result<Self::LabeledGauge, Self::Error>;
+
+    /// Register an existing counter, sharing its state
+    fn register_existing_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        counter: &Self::Counter,
+    ) -> Result<(), Self::Error>;
+
+    /// Register an existing floating point counter, sharing its state
+    fn register_existing_float_counter(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        counter: &Self::FloatCounter,
+    ) -> Result<(), Self::Error>;
+
+    /// Register an existing gauge, sharing its state
+    fn register_existing_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        gauge: &Self::Gauge,
+    ) -> Result<(), Self::Error>;
+
+    /// Register an existing histogram, sharing its state
+    fn register_existing_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        histogram: &Self::Histogram,
+    ) -> Result<(), Self::Error>;
+
+    /// Register an existing histogram family, sharing its state
+    fn register_existing_labeled_histogram(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        family: &Self::LabeledHistogram,
+    ) -> Result<(), Self::Error>;
+
+    /// Register an existing gauge family, sharing its state
+    fn register_existing_labeled_gauge(
+        registry: &mut Self::Registry,
+        name: &str,
+        help: &str,
+        family: &Self::LabeledGauge,
+    ) -> Result<(), Self::Error>;
 }
 
+/// Metric handles that can create and register themselves in a registry.
+///
+/// This is what `#[derive(Metrics)]` (feature: `derive`) calls for each field.
+pub trait Register<B: MetricBackend>: Sized {
+    /// Register a metric called `name` in `registry` and return its handle.
+    fn register(
+        registry: &mut ObservabilityRegistry<B>,
+        name: &str,
+        help: &str,
+    ) -> Result<Self, DeserializeError>;
+}
+
+/// Adds a family registered with `register_external` to a backend registry.
+type RegisterExternal<R> = Box<dyn Fn(&mut R) + Send + Sync>;
+
+/// A registry shared between threads, e.g. with a server rendering it.
+///
+/// Take the write lock to register metrics and the read lock to render;
+/// updates through metric handles need neither.
+pub type SharedRegistry<B> = Arc<RwLock<ObservabilityRegistry<B>>>;
+
 /// A wrapper around a metric backend's registry.
 ///
 /// Provides a unified API for creating metrics that are automatically
@@ -75,26 +237,280 @@ pub trait MetricBackend: Send + Sync + 'static {
 /// ```
 pub struct ObservabilityRegistry<B: MetricBackend> {
     inner: B::Registry,
+    max_name_length: usize,
+    max_buckets: usize,
+    counters: HashMap<String, Metric<B::Counter>>,
+    counters_f64: HashMap<String, Metric<B::FloatCounter>>,
+    gauges: HashMap<String, Metric<B::Gauge>>,
+    histograms: HashMap<String, Metric<B::Histogram>>,
+    labeled_histograms: HashMap<String, Labeled<B::LabeledHistogram>>,
+    labeled_gauges: HashMap<String, Labeled<B::LabeledGauge>>,
+    histogram_buckets: HashMap<String, Vec<f64>>,
+    /// Help text handed to the backend, for metrics whose description changed since.
+    registered_help: HashMap<String, String>,
+    /// Source of metric creation and update times
+    clock: Arc<dyn Clock>,
+    /// Sample values as of the last `render_delta`, keyed by series
+    delta_baseline: HashMap<String, String>,
+    /// Extra names each metric is exported under, keyed by the metric name
+    aliases: HashMap<String, Vec<String>>,
+    /// Histograms rendered with `_sum` and `_count` only
+    summary_only: HashSet<String>,
+    /// Histograms whose `_sum` is rendered from a whole-number sum
+    integer_sums: HashSet<String>,
+    /// Gauges exported only by the first render after each update
+    one_shot: HashSet<String>,
+    /// Pre-rendered `build_info` family, appended to every render
+    build_info: Option<String>,
+    /// Sources of series read on every render
+    collectors: Vec<Box<dyn Collector>>,
+    /// Families added by `register_external`, with how to add each to a backend registry
+    external: HashMap<String, RegisterExternal<B::Registry>>,
+    /// Hooks run at the start of every render, in registration order
+    before_scrape: Vec<Box<dyn Fn() + Send + Sync>>,
+    /// Held shared by handle updates and exclusively by `consistent_snapshot`
+    snapshot_gate: SnapshotGate,
+    /// Set by `freeze`, shared with labeled families to refuse new label sets
+    frozen: Arc<AtomicBool>,
+    /// Shared with every metric handle; cleared on drop or `retire`
+    live: Arc<AtomicBool>,
+    /// Whether labels are sorted by name within each rendered series
+    sort_labels: bool,
+    /// Label values matching this are redacted at render time
+    #[cfg(feature = "redaction")]
+    redaction_pattern: Option<regex::Regex>,
+}
+
+impl<B: MetricBackend> Drop for ObservabilityRegistry<B> {
+    fn drop(&mut self) {
+        self.retire();
+    }
 }
 
 impl<B: MetricBackend> ObservabilityRegistry<B> {
     /// Create a new registry.
     pub fn new() -> Self {
+        Self::with_capacity(0, 0, 0)
+    }
+
+    /// Create a registry pre-sized for the expected number of each metric kind.
+    ///
+    /// Avoids rehashing while registering large sets of metrics, e.g. from
+    /// config. Backends without a way to pre-size their registry ignore the hint.
+    pub fn with_capacity(counters: usize, gauges: usize, histograms: usize) -> Self {
         Self {
             inner: B::create_registry(),
+            max_name_length: DEFAULT_MAX_NAME_LENGTH,
+            max_buckets: DEFAULT_MAX_BUCKETS,
+            counters: HashMap::with_capacity(counters),
+            counters_f64: HashMap::new(),
+            gauges: HashMap::with_capacity(gauges),
+            histograms: HashMap::with_capacity(histograms),
+            labeled_histograms: HashMap::new(),
+            labeled_gauges: HashMap::new(),
+            histogram_buckets: HashMap::with_capacity(histograms),
+            registered_help: HashMap::new(),
+            clock: Arc::new(SystemClock),
+            delta_baseline: HashMap::new(),
+            aliases: HashMap::new(),
+            summary_only: HashSet::new(),
+            integer_sums: HashSet::new(),
+            one_shot: HashSet::new(),
+            build_info: None,
+            collectors: Vec::new(),
+            external: HashMap::new(),
+            before_scrape: Vec::new(),
+            snapshot_gate: SnapshotGate::default(),
+            frozen: Arc::new(AtomicBool::new(false)),
+            live: Arc::new(AtomicBool::new(true)),
+            sort_labels: false,
+            #[cfg(feature = "redaction")]
+            redaction_pattern: None,
+        }
+    }
+
+    /// Refuse to create any more metrics or label sets.
+    ///
+    /// For strict schemas: call it once initialisation is done, so that a
+    /// metric or label set created on the fly fails instead of adding
+    /// cardinality. Registering afterwards returns
+    /// [`DeserializeError::RegistryFrozen`], and a labeled family given a
+    /// label set it doesn't have yet returns
+    /// [`MetricError::RegistryFrozen`](super::metrics::MetricError::RegistryFrozen).
+    /// Existing metrics and series keep updating. Freezing can't be undone.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let requests = registry.labeled_gauge("in_flight", "In-flight requests", vec!["route".into()])?;
+    /// requests.set(&["/users"], 0)?;
+    /// registry.freeze();
+    ///
+    /// requests.inc(&["/users"])?; // fine
+    /// requests.inc(&["/admin"])?; // Err(MetricError::RegistryFrozen)
+    /// ```
+    pub fn freeze(&mut self) {
+        self.frozen.store(true, Ordering::Release);
+    }
+
+    /// Returns true once [`freeze`](Self::freeze) has been called.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    /// Mark the registry as replaced, orphaning the metric handles it gave out.
+    ///
+    /// Dropping the registry does the same. Call it when a registry is
+    /// swapped out but kept around, so updates through handles still
+    /// pointing at it fail with
+    /// [`MetricError::OrphanedHandle`](super::metrics::MetricError::OrphanedHandle),
+    /// or are dropped with a logged warning. The registry itself still
+    /// renders, with the values it had when retired.
+    pub fn retire(&self) {
+        self.live.store(false, Ordering::Release);
+    }
+
+    fn check_not_frozen(&self, name: &str) -> Result<(), DeserializeError> {
+        if self.is_frozen() {
+            return Err(DeserializeError::RegistryFrozen(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Set the maximum metric name length accepted at registration (default: 200).
+    pub fn with_max_name_length(mut self, max_name_length: usize) -> Self {
+        self.max_name_length = max_name_length;
+        self
+    }
+
+    /// Read metric creation and update times from `clock`.
+    ///
+    /// Applies to metrics registered afterwards. Tests pass a
+    /// [`MockClock`](super::clock::MockClock) (feature: `test-utils`) to
+    /// control time without sleeping.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the clock metric times are read from.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Expose a constant `build_info` gauge labeled with a version and commit.
+    ///
+    /// The crate can't know the version of the binary using it, so pass it
+    /// in, e.g. `env!("CARGO_PKG_VERSION")`. An empty `commit` leaves the
+    /// `commit` label off. Calling this again replaces the labels.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let registry = ObservabilityRegistry::<PrometheusBackend>::new()
+    ///     .with_build_info(env!("CARGO_PKG_VERSION"), "3f2c1ab");
+    /// // build_info{version="0.1.0",commit="3f2c1ab"} 1
+    /// ```
+    pub fn with_build_info(mut self, version: &str, commit: &str) -> Self {
+        let mut labels = format!("version=\"{}\"", escape_label_value(version));
+        if !commit.is_empty() {
+            labels.push_str(&format!(",commit=\"{}\"", escape_label_value(commit)));
         }
+        self.build_info = Some(format!(
+            "# HELP {name} Version information of the running build.\n\
+             # TYPE {name} gauge\n\
+             {name}{{{labels}}} 1\n",
+            name = BUILD_INFO_METRIC,
+        ));
+        self
+    }
+
+    /// Render the labels of each series sorted alphabetically by name.
+    ///
+    /// Backends may order labels by insertion or hashing; sorting keeps the
+    /// output stable for golden tests and diffs.
+    pub fn with_sorted_labels(mut self) -> Self {
+        self.sort_labels = true;
+        self
+    }
+
+    /// Redact label values matching `pattern` when rendering (feature: `redaction`).
+    ///
+    /// Matching values are replaced with
+    /// [`REDACTED_LABEL_VALUE`](super::renderer::REDACTED_LABEL_VALUE) in
+    /// every render; the recorded series are left as they are. Anchor the
+    /// pattern with `^...$` to match whole values only.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let registry = ObservabilityRegistry::<PrometheusBackend>::new()
+    ///     .with_label_redaction(r"[^@\s]+@[^@\s]+")?;
+    /// ```
+    #[cfg(feature = "redaction")]
+    pub fn with_label_redaction(mut self, pattern: &str) -> Result<Self, DeserializeError> {
+        let regex = regex::Regex::new(pattern).map_err(|e| DeserializeError::InvalidPattern {
+            pattern: pattern.to_string(),
+            reason: e.to_string(),
+        })?;
+        self.redaction_pattern = Some(regex);
+        Ok(self)
+    }
+
+    /// Get the maximum metric name length accepted at registration.
+    pub fn max_name_length(&self) -> usize {
+        self.max_name_length
+    }
+
+    /// Set the maximum number of histogram buckets accepted at registration (default: 64).
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets;
+        self
+    }
+
+    /// Get the maximum number of histogram buckets accepted at registration.
+    pub fn max_buckets(&self) -> usize {
+        self.max_buckets
     }
 
     /// Create and register a counter.
+    ///
+    /// Backslashes and newlines in `help` are escaped in the exposition output,
+    /// so the metric handle keeps the description as given.
     pub fn counter(
         &mut self,
         name: impl Into<String>,
         help: impl Into<String>,
-    ) -> Result<Metric<B::Counter>, B::Error> {
+    ) -> Result<Metric<B::Counter>, DeserializeError> {
+        let name = name.into();
+        let help = help.into();
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        let counter = B::register_counter(&mut self.inner, &name, &escape_help(&help))
+            .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Metric::new(name.clone(), help, counter)
+            .with_clock(self.clock.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone());
+        self.counters.insert(name, metric.clone());
+        Ok(metric)
+    }
+
+    /// Create and register a counter holding a floating point total.
+    ///
+    /// Increment it with [`Metric::try_inc_by_f64`], which rejects negative values.
+    pub fn float_counter(
+        &mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<Metric<B::FloatCounter>, DeserializeError> {
         let name = name.into();
         let help = help.into();
-        let counter = B::register_counter(&mut self.inner, &name, &help)?;
-        Ok(Metric::new(name, help, counter))
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        let counter = B::register_float_counter(&mut self.inner, &name, &escape_help(&help))
+            .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Metric::new(name.clone(), help, counter)
+            .with_clock(self.clock.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone());
+        self.counters_f64.insert(name, metric.clone());
+        Ok(metric)
     }
 
     /// Create and register a gauge.
@@ -102,11 +518,106 @@ impl<B: MetricBackend> ObservabilityRegistry<B> {
         &mut self,
         name: impl Into<String>,
         help: impl Into<String>,
-    ) -> Result<Metric<B::Gauge>, B::Error> {
+    ) -> Result<Metric<B::Gauge>, DeserializeError> {
+        let name = name.into();
+        let help = help.into();
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        let gauge = B::register_gauge(&mut self.inner, &name, &escape_help(&help))
+            .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Metric::new(name.clone(), help, gauge)
+            .with_clock(self.clock.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone());
+        self.gauges.insert(name, metric.clone());
+        Ok(metric)
+    }
+
+    /// Create and register a gauge with sibling `<name>_min` and
+    /// `<name>_max` gauges tracking its extremes.
+    ///
+    /// Fails without registering anything if any of the three names is
+    /// invalid or already taken.
+    pub fn tracking_gauge(
+        &mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Result<TrackingGauge<B::Gauge>, DeserializeError> {
         let name = name.into();
         let help = help.into();
-        let gauge = B::register_gauge(&mut self.inner, &name, &help)?;
-        Ok(Metric::new(name, help, gauge))
+        let min_name = format!("{}{}", name, MIN_SUFFIX);
+        let max_name = format!("{}{}", name, MAX_SUFFIX);
+        // Check every name first so a failure leaves nothing half-registered
+        self.check_not_frozen(&name)?;
+        for name in [&name, &min_name, &max_name] {
+            validate_metric_name(name, self.max_name_length)?;
+            if self.contains(name) {
+                return Err(DeserializeError::DuplicateMetricName(name.clone()));
+            }
+        }
+
+        let min = self.gauge(min_name, format!("{} (minimum)", help))?;
+        let max = self.gauge(max_name, format!("{} (maximum)", help))?;
+        let current = self.gauge(name, help)?;
+        Ok(TrackingGauge::new(current, min, max))
+    }
+
+    /// Add a collector whose series are read on every render.
+    ///
+    /// The collector isn't called until the registry is rendered. Its series
+    /// are not checked against registered metrics, so keep its names apart.
+    pub fn register_collector(&mut self, collector: impl Collector) {
+        self.collectors.push(Box::new(collector));
+    }
+
+    /// Register a family this registry has no handle type for, such as a
+    /// backend's labeled counter, under `name`.
+    ///
+    /// `register` adds the family to the backend registry. It gets the same
+    /// checks as any other metric: the name must be valid and not taken, and
+    /// the registry not frozen. The name is reserved from then on, and
+    /// `register` is called again if the backend registry is rebuilt by
+    /// [`remove_metrics`](Self::remove_metrics). External families are left
+    /// out of [`snapshot`](Self::snapshot) and [`describe`](Self::describe).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let requests: LabeledCounter<DynamicLabels> = labeled_counter();
+    /// let family = requests.clone();
+    /// registry.register_external("http_requests", move |inner| {
+    ///     inner.register("http_requests", "HTTP requests served", family.clone())
+    /// })?;
+    /// ```
+    pub fn register_external(
+        &mut self,
+        name: impl Into<String>,
+        register: impl Fn(&mut B::Registry) + Send + Sync + 'static,
+    ) -> Result<(), DeserializeError> {
+        let name = name.into();
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        if self.contains(&name) {
+            return Err(DeserializeError::DuplicateMetricName(name));
+        }
+        register(&mut self.inner);
+        self.external.insert(name, Box::new(register));
+        Ok(())
+    }
+
+    /// Add a hook that runs at the start of every render.
+    ///
+    /// For refreshing a batch of registered metrics right before they are
+    /// exposed, e.g. by polling an external system. Unlike a collector, a
+    /// hook reports no series itself: it updates metrics through their
+    /// handles. Hooks run in the order they were added, on the rendering
+    /// thread, so keep them quick.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let queue_depth = registry.gauge("queue_depth", "Jobs waiting")?;
+    /// registry.on_before_scrape(move || queue_depth.set(queue.len() as i64));
+    /// ```
+    pub fn on_before_scrape(&mut self, hook: impl Fn() + Send + Sync + 'static) {
+        self.before_scrape.push(Box::new(hook));
     }
 
     /// Create and register a histogram with default latency buckets.
@@ -114,32 +625,1226 @@ impl<B: MetricBackend> ObservabilityRegistry<B> {
         &mut self,
         name: impl Into<String>,
         help: impl Into<String>,
-    ) -> Result<Metric<B::Histogram>, B::Error> {
-        self.histogram_with_buckets(
+    ) -> Result<Metric<B::Histogram>, DeserializeError> {
+        self.histogram_with_buckets(name, help, DEFAULT_HISTOGRAM_BUCKETS.to_vec())
+    }
+
+    /// Create and register a histogram with custom buckets.
+    ///
+    /// Buckets are checked with [`normalize_and_validate_buckets`] against
+    /// [`max_buckets`](Self::max_buckets).
+    pub fn histogram_with_buckets(
+        &mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+        buckets: Vec<f64>,
+    ) -> Result<Metric<B::Histogram>, DeserializeError> {
+        let name = name.into();
+        let help = help.into();
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        let buckets = normalize_and_validate_buckets(&name, buckets, self.max_buckets)?;
+        let histogram =
+            B::register_histogram(&mut self.inner, &name, &escape_help(&help), buckets.clone())
+                .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Metric::new(name.clone(), help, histogram)
+            .with_clock(self.clock.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone());
+        self.histograms.insert(name.clone(), metric.clone());
+        self.histogram_buckets.insert(name, buckets);
+        Ok(metric)
+    }
+
+    /// Create and register a labeled histogram with default latency buckets.
+    pub fn labeled_histogram(
+        &mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+        label_names: Vec<String>,
+    ) -> Result<Labeled<B::LabeledHistogram>, DeserializeError> {
+        self.labeled_histogram_with_buckets(
             name,
             help,
-            vec![
-                0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-            ],
+            label_names,
+            DEFAULT_HISTOGRAM_BUCKETS.to_vec(),
         )
     }
 
-    /// Create and register a histogram with custom buckets.
-    pub fn histogram_with_buckets(
+    /// Create and register a histogram with one series per combination of labels.
+    ///
+    /// Every series shares `buckets` and is rendered under a single family
+    /// header. Buckets are checked as in
+    /// [`histogram_with_buckets`](Self::histogram_with_buckets); label names
+    /// starting with `__` are rejected.
+    pub fn labeled_histogram_with_buckets(
         &mut self,
         name: impl Into<String>,
         help: impl Into<String>,
+        label_names: Vec<String>,
         buckets: Vec<f64>,
-    ) -> Result<Metric<B::Histogram>, B::Error> {
+    ) -> Result<Labeled<B::LabeledHistogram>, DeserializeError> {
         let name = name.into();
         let help = help.into();
-        let histogram = B::register_histogram(&mut self.inner, &name, &help, buckets)?;
-        Ok(Metric::new(name, help, histogram))
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        let buckets = normalize_and_validate_buckets(&name, buckets, self.max_buckets)?;
+        for label in &label_names {
+            validate_label_name(label)?;
+        }
+        let family = B::register_labeled_histogram(
+            &mut self.inner,
+            &name,
+            &escape_help(&help),
+            &label_names,
+            buckets.clone(),
+        )
+        .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Labeled::new(name.clone(), help, label_names, family)
+            .with_freeze(self.frozen.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone())
+            .with_clock(self.clock.clone());
+        self.labeled_histograms.insert(name.clone(), metric.clone());
+        self.histogram_buckets.insert(name, buckets);
+        Ok(metric)
     }
 
-    /// Render the metrics in the backend's format.
+    /// Create and register a gauge with one series per combination of labels.
+    ///
+    /// Every series is rendered under a single family header. Label names
+    /// starting with `__` are rejected.
+    pub fn labeled_gauge(
+        &mut self,
+        name: impl Into<String>,
+        help: impl Into<String>,
+        label_names: Vec<String>,
+    ) -> Result<Labeled<B::LabeledGauge>, DeserializeError> {
+        let name = name.into();
+        let help = help.into();
+        self.check_not_frozen(&name)?;
+        validate_metric_name(&name, self.max_name_length)?;
+        for label in &label_names {
+            validate_label_name(label)?;
+        }
+        let family =
+            B::register_labeled_gauge(&mut self.inner, &name, &escape_help(&help), &label_names)
+                .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        let metric = Labeled::new(name.clone(), help, label_names, family)
+            .with_freeze(self.frozen.clone())
+            .with_registry(self.live.clone(), self.snapshot_gate.clone())
+            .with_clock(self.clock.clone());
+        self.labeled_gauges.insert(name, metric.clone());
+        Ok(metric)
+    }
+
+    /// Update the description of a registered metric.
+    ///
+    /// Float counters and labeled families are found under their kind like
+    /// any other metric. The new text replaces the `# HELP` line emitted by [`render`](Self::render).
+    /// Handles returned at registration keep the description they were created with.
+    pub fn set_description(
+        &mut self,
+        name: &str,
+        kind: MetricKind,
+        description: &str,
+    ) -> Result<(), DeserializeError> {
+        let previous = match kind {
+            MetricKind::Counter => self
+                .counters
+                .get_mut(name)
+                .map(|m| m.replace_description(description))
+                .or_else(|| {
+                    self.counters_f64
+                        .get_mut(name)
+                        .map(|m| m.replace_description(description))
+                }),
+            MetricKind::Gauge => self
+                .gauges
+                .get_mut(name)
+                .map(|m| m.replace_description(description))
+                .or_else(|| {
+                    self.labeled_gauges
+                        .get_mut(name)
+                        .map(|m| m.replace_description(description))
+                }),
+            MetricKind::Histogram => self
+                .histograms
+                .get_mut(name)
+                .map(|m| m.replace_description(description))
+                .or_else(|| {
+                    self.labeled_histograms
+                        .get_mut(name)
+                        .map(|m| m.replace_description(description))
+                }),
+        }
+        .ok_or_else(|| DeserializeError::MetricNotFound {
+            name: name.to_string(),
+            kind: Some(kind),
+        })?;
+
+        self.registered_help
+            .entry(name.to_string())
+            .or_insert(previous);
+        Ok(())
+    }
+
+    /// Remove the metrics called `names`, along with their aliases and
+    /// summary-only flags. Names that aren't registered are ignored.
+    ///
+    /// Backends can't unregister, so the backend registry is rebuilt from
+    /// the remaining handles in their current output order. Those share
+    /// state with the handles already handed out, so their values carry over;
+    /// handles to removed metrics keep working but are no longer rendered.
+    ///
+    /// # Example
+    /// ```ignore
+    /// registry.remove_metrics(["legacy_requests_total", "legacy_latency_seconds"])?;
+    /// ```
+    pub fn remove_metrics<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), DeserializeError> {
+        let names: HashSet<&str> = names.into_iter().collect();
+        let text = self.render_backend_text()?;
+        let mut order: Vec<String> = split_families(&text)
+            .families
+            .iter()
+            .map(|family| family.name.to_string())
+            .collect();
+        let mut unrendered: Vec<String> = self
+            .metric_names()
+            .filter(|name| !self.is_alias(name) && *name != BUILD_INFO_METRIC)
+            .filter(|name| !order.iter().any(|rendered| rendered == name))
+            .map(str::to_string)
+            .collect();
+        unrendered.sort_unstable();
+        order.extend(unrendered);
+
+        let mut inner = B::create_registry();
+        for name in order.iter().filter(|name| !names.contains(name.as_str())) {
+            self.register_existing(&mut inner, name)
+                .map_err(|e| DeserializeError::Backend(e.to_string()))?;
+        }
+
+        let keep = |name: &String| !names.contains(name.as_str());
+        self.counters.retain(|name, _| keep(name));
+        self.counters_f64.retain(|name, _| keep(name));
+        self.gauges.retain(|name, _| keep(name));
+        self.histograms.retain(|name, _| keep(name));
+        self.labeled_histograms.retain(|name, _| keep(name));
+        self.labeled_gauges.retain(|name, _| keep(name));
+        self.external.retain(|name, _| keep(name));
+        self.histogram_buckets.retain(|name, _| keep(name));
+        self.aliases.retain(|name, _| keep(name));
+        self.summary_only.retain(keep);
+        self.integer_sums.retain(keep);
+        self.one_shot.retain(keep);
+        // Remaining metrics were registered with their current descriptions
+        self.registered_help.clear();
+        self.inner = inner;
+        Ok(())
+    }
+
+    /// Register the handle of the metric `name`, if any, in `inner`.
+    fn register_existing(&self, inner: &mut B::Registry, name: &str) -> Result<(), B::Error> {
+        if let Some(metric) = self.counters.get(name) {
+            let help = escape_help(metric.description());
+            B::register_existing_counter(inner, name, &help, metric.inner())?;
+        }
+        if let Some(metric) = self.counters_f64.get(name) {
+            let help = escape_help(metric.description());
+            B::register_existing_float_counter(inner, name, &help, metric.inner())?;
+        }
+        if let Some(metric) = self.gauges.get(name) {
+            let help = escape_help(metric.description());
+            B::register_existing_gauge(inner, name, &help, metric.inner())?;
+        }
+        if let Some(metric) = self.histograms.get(name) {
+            let help = escape_help(metric.description());
+            B::register_existing_histogram(inner, name, &help, metric.inner())?;
+        }
+        if let Some(family) = self.labeled_histograms.get(name) {
+            let help = escape_help(family.description());
+            B::register_existing_labeled_histogram(inner, name, &help, family.inner())?;
+        }
+        if let Some(family) = self.labeled_gauges.get(name) {
+            let help = escape_help(family.description());
+            B::register_existing_labeled_gauge(inner, name, &help, family.inner())?;
+        }
+        if let Some(register) = self.external.get(name) {
+            register(inner);
+        }
+        Ok(())
+    }
+
+    /// Register the metric family described by `desc`.
+    ///
+    /// The programmatic form of a config entry: the family gets the same
+    /// checks as one loaded from config, including that neither its name
+    /// nor its aliases are taken, and nothing is registered if any fails.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let handle = registry.register_family(FamilyDescriptor::Counter {
+    ///     name: "plugin_events".into(),
+    ///     description: "Events handled by the plugin".into(),
+    ///     aliases: vec![],
+    /// })?;
+    /// handle.as_counter().unwrap().inc();
+    /// ```
+    pub fn register_family(
+        &mut self,
+        desc: FamilyDescriptor,
+    ) -> Result<FamilyHandle<B>, DeserializeError> {
+        let mut own_names = HashSet::with_capacity(1 + desc.aliases().len());
+        for name in std::iter::once(desc.name()).chain(desc.aliases().iter().map(String::as_str)) {
+            validate_metric_name(name, self.max_name_length)?;
+            if self.contains(name) || !own_names.insert(name) {
+                return Err(DeserializeError::DuplicateMetricName(name.to_string()));
+            }
+        }
+
+        let name = desc.name().to_string();
+        let aliases = desc.aliases().to_vec();
+        let handle = match desc {
+            FamilyDescriptor::Counter { description, .. } => {
+                FamilyHandle::Counter(self.counter(name.clone(), description)?)
+            }
+            FamilyDescriptor::Gauge {
+                description,
+                labels,
+                ..
+            } => {
+                if labels.is_empty() {
+                    FamilyHandle::Gauge(self.gauge(name.clone(), description)?)
+                } else {
+                    FamilyHandle::LabeledGauge(self.labeled_gauge(
+                        name.clone(),
+                        description,
+                        labels,
+                    )?)
+                }
+            }
+            FamilyDescriptor::Histogram {
+                description,
+                buckets,
+                labels,
+                summary_only,
+                ..
+            } => {
+                let buckets = buckets.unwrap_or_else(|| DEFAULT_HISTOGRAM_BUCKETS.to_vec());
+                let handle = if labels.is_empty() {
+                    FamilyHandle::Histogram(self.histogram_with_buckets(
+                        name.clone(),
+                        description,
+                        buckets,
+                    )?)
+                } else {
+                    FamilyHandle::LabeledHistogram(self.labeled_histogram_with_buckets(
+                        name.clone(),
+                        description,
+                        labels,
+                        buckets,
+                    )?)
+                };
+                if summary_only {
+                    self.set_summary_only(&name)?;
+                }
+                handle
+            }
+        };
+
+        for alias in aliases {
+            self.add_alias(&name, alias)?;
+        }
+        Ok(handle)
+    }
+
+    /// Look up a registered counter by name.
+    pub fn find_counter(&self, name: &str) -> Option<&Metric<B::Counter>> {
+        self.counters.get(name)
+    }
+
+    /// Look up a registered floating point counter by name.
+    pub fn find_float_counter(&self, name: &str) -> Option<&Metric<B::FloatCounter>> {
+        self.counters_f64.get(name)
+    }
+
+    /// Look up a registered gauge by name.
+    pub fn find_gauge(&self, name: &str) -> Option<&Metric<B::Gauge>> {
+        self.gauges.get(name)
+    }
+
+    /// Look up a registered histogram by name.
+    pub fn find_histogram(&self, name: &str) -> Option<&Metric<B::Histogram>> {
+        self.histograms.get(name)
+    }
+
+    /// Look up a registered labeled gauge by name.
+    pub fn find_labeled_gauge(&self, name: &str) -> Option<&Labeled<B::LabeledGauge>> {
+        self.labeled_gauges.get(name)
+    }
+
+    /// Look up a registered labeled histogram by name.
+    pub fn find_labeled_histogram(&self, name: &str) -> Option<&Labeled<B::LabeledHistogram>> {
+        self.labeled_histograms.get(name)
+    }
+
+    /// Get the bucket bounds a histogram was registered with.
+    pub fn histogram_buckets(&self, name: &str) -> Option<&[f64]> {
+        self.histogram_buckets.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns true if a metric of any kind is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.counters.contains_key(name)
+            || self.counters_f64.contains_key(name)
+            || self.gauges.contains_key(name)
+            || self.histograms.contains_key(name)
+            || self.labeled_histograms.contains_key(name)
+            || self.labeled_gauges.contains_key(name)
+            || self.external.contains_key(name)
+            || (self.build_info.is_some() && name == BUILD_INFO_METRIC)
+            || self.is_alias(name)
+    }
+
+    /// Iterate over the names of every registered metric.
+    pub fn metric_names(&self) -> impl Iterator<Item = &str> {
+        self.counters
+            .keys()
+            .chain(self.counters_f64.keys())
+            .chain(self.gauges.keys())
+            .chain(self.histograms.keys())
+            .chain(self.labeled_histograms.keys())
+            .chain(self.labeled_gauges.keys())
+            .chain(self.external.keys())
+            .map(String::as_str)
+            .chain(self.aliases.values().flatten().map(String::as_str))
+            .chain(self.build_info.as_ref().map(|_| BUILD_INFO_METRIC))
+    }
+
+    /// Also export the metric `name` under `alias`, e.g. during a rename.
+    ///
+    /// The alias is rendered as a copy of the metric's family at render time,
+    /// so it always reports the same values. Aliases are included in
+    /// [`contains`](Self::contains) and [`metric_names`](Self::metric_names);
+    /// an alias can't reuse a taken name or point at another alias.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let requests = registry.counter("http_requests_total", "Requests")?;
+    /// registry.add_alias("http_requests_total", "requests_total")?;
+    /// ```
+    pub fn add_alias(
+        &mut self,
+        name: &str,
+        alias: impl Into<String>,
+    ) -> Result<(), DeserializeError> {
+        let alias = alias.into();
+        validate_metric_name(&alias, self.max_name_length)?;
+        if !self.contains(name) || self.is_alias(name) {
+            return Err(DeserializeError::MetricNotFound {
+                name: name.to_string(),
+                kind: None,
+            });
+        }
+        if self.contains(&alias) {
+            return Err(DeserializeError::DuplicateMetricName(alias));
+        }
+
+        self.aliases
+            .entry(name.to_string())
+            .or_default()
+            .push(alias);
+        Ok(())
+    }
+
+    /// The aliases `name` is also exported under, in the order they were added.
+    pub fn aliases_of(&self, name: &str) -> &[String] {
+        self.aliases.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Render the histogram `name` with only its `_sum` and `_count` samples.
+    ///
+    /// The buckets are still recorded but their `_bucket` series are left out
+    /// of every render, which saves a series per bound and label set.
+    pub fn set_summary_only(&mut self, name: &str) -> Result<(), DeserializeError> {
+        if !self.histograms.contains_key(name) && !self.labeled_histograms.contains_key(name) {
+            return Err(DeserializeError::MetricNotFound {
+                name: name.to_string(),
+                kind: Some(MetricKind::Histogram),
+            });
+        }
+        self.summary_only.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Returns true if the histogram `name` is rendered without its buckets.
+    pub fn is_summary_only(&self, name: &str) -> bool {
+        self.summary_only.contains(name)
+    }
+
+    /// Keep the sum of the histogram `name` as a `u64` and render `_sum` from it.
+    ///
+    /// For integer-valued observations such as byte sizes, where a large
+    /// `f64` sum would lose precision; `_sum` is then rendered without a
+    /// decimal point. Only observations made after this call are summed,
+    /// truncated towards zero, so enable it right after registering. Only
+    /// unlabeled histograms are supported.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let sizes = registry.histogram_for_bytes("upload_bytes", "Upload sizes")?;
+    /// registry.set_integer_sum("upload_bytes")?;
+    /// sizes.observe(9_007_199_254_740_993.0);
+    /// ```
+    pub fn set_integer_sum(&mut self, name: &str) -> Result<(), DeserializeError> {
+        let histogram =
+            self.histograms
+                .get(name)
+                .ok_or_else(|| DeserializeError::MetricNotFound {
+                    name: name.to_string(),
+                    kind: Some(MetricKind::Histogram),
+                })?;
+        histogram.track_integer_sum();
+        self.integer_sums.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Returns true if the histogram `name` renders a whole-number `_sum`.
+    pub fn has_integer_sum(&self, name: &str) -> bool {
+        self.integer_sums.contains(name)
+    }
+
+    /// Export the gauge `name` once after each update, instead of on every render.
+    ///
+    /// For push-style, event-like values: the first scrape after an update
+    /// includes the sample and resets the gauge to zero, and later scrapes
+    /// leave the sample out until the gauge is updated again. The gauge's
+    /// `# HELP` and `# TYPE` lines are always rendered. Only unlabeled gauges
+    /// are supported.
+    ///
+    /// Only [`render`](Self::render), which the HTTP scrape handlers and
+    /// [`render_with_timeout`](Self::render_with_timeout) call, takes the
+    /// update. The other `render_*` methods, [`write_to_file`](Self::write_to_file),
+    /// [`validate_output`](Self::validate_output) and the SSE stream include
+    /// a pending update without taking it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let evicted = registry.gauge("cache_evicted", "Entries evicted in the last sweep")?;
+    /// registry.set_one_shot("cache_evicted")?;
+    /// evicted.set(12);
+    /// registry.render()?; // cache_evicted 12
+    /// registry.render()?; // no cache_evicted sample
+    /// ```
+    pub fn set_one_shot(&mut self, name: &str) -> Result<(), DeserializeError> {
+        let gauge = self
+            .gauges
+            .get(name)
+            .ok_or_else(|| DeserializeError::MetricNotFound {
+                name: name.to_string(),
+                kind: Some(MetricKind::Gauge),
+            })?;
+        gauge.set_one_shot();
+        self.one_shot.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Returns true if the gauge `name` is exported once per update.
+    pub fn is_one_shot(&self, name: &str) -> bool {
+        self.one_shot.contains(name)
+    }
+
+    /// Returns true if `name` was added with [`add_alias`](Self::add_alias).
+    fn is_alias(&self, name: &str) -> bool {
+        self.aliases.values().flatten().any(|alias| alias == name)
+    }
+
+    /// Names of metrics not updated within `ttl`, sorted.
+    ///
+    /// Ages are measured on the registry's [`clock`](Self::clock). A metric
+    /// that was never updated ages from its creation time.
+    ///
+    /// # Example
+    /// ```ignore
+    /// for name in registry.stale_metrics(Duration::from_secs(300)) {
+    ///     tracing::warn!(metric = name, "no updates in 5 minutes");
+    /// }
+    /// ```
+    pub fn stale_metrics(&self, ttl: Duration) -> Vec<&str> {
+        fn is_stale<T>(metric: &Metric<T>, now: SystemTime, ttl: Duration) -> bool {
+            let last_seen = metric.last_updated().unwrap_or_else(|| metric.created());
+            now.duration_since(last_seen).is_ok_and(|age| age > ttl)
+        }
+
+        let now = self.clock.now();
+        let mut stale: Vec<&str> = self
+            .counters
+            .values()
+            .filter(|m| is_stale(m, now, ttl))
+            .map(Metric::name)
+            .chain(
+                self.gauges
+                    .values()
+                    .filter(|m| is_stale(m, now, ttl))
+                    .map(Metric::name),
+            )
+            .chain(
+                self.histograms
+                    .values()
+                    .filter(|m| is_stale(m, now, ttl))
+                    .map(Metric::name),
+            )
+            .collect();
+        stale.sort_unstable();
+        stale
+    }
+
+    /// Capture the current value and last update time of every metric.
+    ///
+    /// Labeled families get one entry per series, read back from the
+    /// rendered output, with no update time.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let snapshot = registry.snapshot();
+    /// if let Some(depth) = snapshot.get("queue_depth") {
+    ///     println!("{:?} (updated {:?})", depth.value, depth.last_updated);
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let counters = self.counters.iter().map(|(name, m)| MetricSnapshot {
+            name: name.clone(),
+            kind: MetricKind::Counter,
+            value: SnapshotValue::Counter(m.get_counter()),
+            labels: Vec::new(),
+            last_updated: m.last_updated(),
+        });
+        let gauges = self.gauges.iter().map(|(name, m)| MetricSnapshot {
+            name: name.clone(),
+            kind: MetricKind::Gauge,
+            value: SnapshotValue::Gauge(m.get_gauge()),
+            labels: Vec::new(),
+            last_updated: m.last_updated(),
+        });
+        let float_counters = self.counters_f64.iter().map(|(name, m)| MetricSnapshot {
+            name: name.clone(),
+            kind: MetricKind::Counter,
+            value: SnapshotValue::Float(m.get_counter_f64()),
+            labels: Vec::new(),
+            last_updated: m.last_updated(),
+        });
+        let histograms = self.histograms.iter().map(|(name, m)| MetricSnapshot {
+            name: name.clone(),
+            kind: MetricKind::Histogram,
+            value: SnapshotValue::Histogram,
+            labels: Vec::new(),
+            last_updated: m.last_updated(),
+        });
+
+        let mut metrics: Vec<_> = counters
+            .chain(float_counters)
+            .chain(gauges)
+            .chain(histograms)
+            .chain(self.labeled_series())
+            .collect();
+        metrics.sort_by(|a, b| (&a.name, &a.labels).cmp(&(&b.name, &b.labels)));
+        MetricsSnapshot { metrics }
+    }
+
+    /// Every series of the labeled families, parsed from the backend output.
+    ///
+    /// The families hand out series but can't list them, so this is the
+    /// only way to read them all back.
+    fn labeled_series(&self) -> Vec<MetricSnapshot> {
+        if self.labeled_gauges.is_empty() && self.labeled_histograms.is_empty() {
+            return Vec::new();
+        }
+        let Ok(text) = self.render_backend_text() else {
+            return Vec::new();
+        };
+
+        let mut exposition = split_families(&text);
+        exposition.families.retain(|family| {
+            self.labeled_gauges.contains_key(family.name)
+                || self.labeled_histograms.contains_key(family.name)
+        });
+        exposition.trailer.clear();
+        parse_exposition(&exposition.to_text())
+            .map(|snapshot| snapshot.metrics)
+            .unwrap_or_default()
+    }
+
+    /// Capture every metric at a single instant.
+    ///
+    /// Waits for the updates in progress through this registry's handles
+    /// to finish and holds off new ones while reading, so no update, and no
+    /// group of updates run through the [`snapshot_gate`](Self::snapshot_gate),
+    /// is seen half-applied. Every handle update pays for this with a shared
+    /// lock; see [`SnapshotGate`] for the cost and the updates it can't see.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let gate = registry.snapshot_gate();
+    /// gate.update(|| {
+    ///     requests.inc();
+    ///     responses.inc();
+    /// });
+    /// let snapshot = registry.consistent_snapshot();
+    /// ```
+    pub fn consistent_snapshot(&self) -> MetricsSnapshot {
+        let _exclusive = self.snapshot_gate.exclusive();
+        self.snapshot()
+    }
+
+    /// The gate to group updates through, so a
+    /// [`consistent_snapshot`](Self::consistent_snapshot) sees them together.
+    ///
+    /// Clones share one gate, so it can be handed to the threads doing the
+    /// updates.
+    pub fn snapshot_gate(&self) -> SnapshotGate {
+        self.snapshot_gate.clone()
+    }
+
+    /// List the name, kind and description of every registered metric, sorted by name.
+    ///
+    /// Aliases are left out.
+    pub fn describe(&self) -> Vec<MetricDescription> {
+        let describe = |name: &String, kind, description: &str| MetricDescription {
+            name: name.clone(),
+            kind,
+            description: description.to_string(),
+        };
+        let mut metrics: Vec<_> = self
+            .counters
+            .iter()
+            .map(|(name, m)| describe(name, MetricKind::Counter, m.description()))
+            .chain(
+                self.counters_f64
+                    .iter()
+                    .map(|(name, m)| describe(name, MetricKind::Counter, m.description())),
+            )
+            .chain(
+                self.gauges
+                    .iter()
+                    .map(|(name, m)| describe(name, MetricKind::Gauge, m.description())),
+            )
+            .chain(
+                self.labeled_gauges
+                    .iter()
+                    .map(|(name, m)| describe(name, MetricKind::Gauge, m.description())),
+            )
+            .chain(
+                self.histograms
+                    .iter()
+                    .map(|(name, m)| describe(name, MetricKind::Histogram, m.description())),
+            )
+            .chain(
+                self.labeled_histograms
+                    .iter()
+                    .map(|(name, m)| describe(name, MetricKind::Histogram, m.description())),
+            )
+            .collect();
+        metrics.sort_by(|a, b| a.name.cmp(&b.name));
+        metrics
+    }
+
+    /// Look up the current description of a registered metric by name.
+    fn description_of(&self, name: &str) -> Option<&str> {
+        self.counters
+            .get(name)
+            .map(|m| m.description())
+            .or_else(|| self.counters_f64.get(name).map(|m| m.description()))
+            .or_else(|| self.gauges.get(name).map(|m| m.description()))
+            .or_else(|| self.histograms.get(name).map(|m| m.description()))
+            .or_else(|| self.labeled_histograms.get(name).map(|m| m.description()))
+            .or_else(|| self.labeled_gauges.get(name).map(|m| m.description()))
+    }
+
+    /// Render the metrics in the backend's format, as served to a scrape.
+    ///
+    /// Runs the [`on_before_scrape`](Self::on_before_scrape) hooks first.
+    /// This is the only render that takes one-shot gauges' updates; see
+    /// [`set_one_shot`](Self::set_one_shot).
     pub fn render(&self) -> Result<RenderedMetrics, <B::Registry as MetricsRenderer>::Error> {
-        self.inner.render()
+        self.render_with(true)
+    }
+
+    /// Render like [`render`](Self::render), but leave one-shot gauges'
+    /// updates pending, so the next scrape still exports them.
+    pub(crate) fn render_peek(
+        &self,
+    ) -> Result<RenderedMetrics, <B::Registry as MetricsRenderer>::Error> {
+        self.render_with(false)
+    }
+
+    fn render_with(
+        &self,
+        take_one_shot: bool,
+    ) -> Result<RenderedMetrics, <B::Registry as MetricsRenderer>::Error> {
+        for hook in &self.before_scrape {
+            hook();
+        }
+
+        // Taken before rendering, so an update made while rendering is also exported next time
+        let mut one_shot_values: HashMap<&str, i64> = HashMap::new();
+        let mut one_shot_updated: HashSet<&str> = HashSet::new();
+        for name in &self.one_shot {
+            let Some(gauge) = self.gauges.get(name) else {
+                continue;
+            };
+            if take_one_shot {
+                if let Some(value) = gauge.take_one_shot() {
+                    one_shot_values.insert(name, value);
+                    one_shot_updated.insert(name);
+                }
+            } else if gauge.has_one_shot_update() {
+                one_shot_updated.insert(name);
+            }
+        }
+
+        let mut rendered = self.inner.render()?;
+
+        if !self.one_shot.is_empty() {
+            let not_updated: HashSet<&str> = self
+                .one_shot
+                .iter()
+                .map(String::as_str)
+                .filter(|name| !one_shot_updated.contains(name))
+                .collect();
+            rendered = drop_samples(rendered, &not_updated);
+            rendered = replace_gauge_values(rendered, &one_shot_values);
+        }
+
+        for (name, registered) in &self.registered_help {
+            if let Some(current) = self.description_of(name) {
+                rendered = replace_help_text(
+                    rendered,
+                    name,
+                    &escape_help(registered),
+                    &escape_help(current),
+                );
+            }
+        }
+
+        if !self.summary_only.is_empty() {
+            rendered = drop_bucket_samples(rendered, &self.summary_only);
+        }
+
+        if !self.integer_sums.is_empty() {
+            let sums: HashMap<&str, u64> = self
+                .integer_sums
+                .iter()
+                .filter_map(|name| {
+                    let sum = self.histograms.get(name)?.integer_sum()?;
+                    Some((name.as_str(), sum))
+                })
+                .collect();
+            rendered = replace_sum_values(rendered, &sums);
+        }
+
+        if !self.aliases.is_empty() {
+            rendered = mirror_families(rendered, &self.aliases);
+        }
+
+        if let Some(build_info) = &self.build_info {
+            rendered = append_family(rendered, build_info);
+        }
+
+        if !self.collectors.is_empty() {
+            let series: Vec<_> = self.collectors.iter().flat_map(|c| c.collect()).collect();
+            rendered = append_family(rendered, &render_series(&series));
+        }
+
+        if self.sort_labels {
+            rendered = sort_labels(rendered);
+        }
+
+        #[cfg(feature = "redaction")]
+        if let Some(pattern) = &self.redaction_pattern {
+            rendered = super::renderer::redact_label_values(rendered, pattern);
+        }
+
+        Ok(rendered)
+    }
+
+    /// Render the metrics, giving up if it takes longer than `timeout`.
+    ///
+    /// Rendering runs on a separate thread so that a slow backend or custom
+    /// collector can't hold up the caller past the deadline. On timeout the
+    /// abandoned render still runs to completion in the background.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let registry = Arc::new(registry);
+    /// let output = registry.render_with_timeout(Duration::from_secs(2))?;
+    /// ```
+    pub fn render_with_timeout(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<RenderedMetrics, RenderError> {
+        let registry = Arc::clone(self);
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            // The receiver is gone if the caller already timed out.
+            let _ = tx.send(registry.render().map_err(|e| e.to_string()));
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result.map_err(RenderError::Failed),
+            Err(RecvTimeoutError::Timeout) => Err(RenderError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(RenderError::Failed("render thread panicked".to_string()))
+            }
+        }
+    }
+
+    /// Render a single metric family (its `# HELP` / `# TYPE` lines and series).
+    ///
+    /// Any trailing end-of-output marker (e.g. OpenMetrics `# EOF`) is kept so
+    /// the result is still a complete exposition.
+    pub fn render_one(&self, name: &str) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        let exposition = split_families(&text);
+        let family = exposition
+            .families
+            .iter()
+            .find(|family| family.name == name)
+            .ok_or_else(|| DeserializeError::MetricNotFound {
+                name: name.to_string(),
+                kind: None,
+            })?;
+
+        Ok(family
+            .lines
+            .iter()
+            .chain(exposition.trailer.iter())
+            .copied()
+            .collect())
+    }
+
+    /// Render with metric families sorted alphabetically by name.
+    ///
+    /// Exposition formats don't require any ordering, but a stable one keeps
+    /// diffs readable and makes golden-file tests possible.
+    pub fn render_sorted(&self) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        let mut exposition = split_families(&text);
+        exposition.families.sort_by(|a, b| a.name.cmp(b.name));
+        Ok(exposition.to_text())
+    }
+
+    /// Count the series the registry exports, across every family and label set.
+    ///
+    /// Counted without rendering, so no hooks run and one-shot gauges keep
+    /// their pending updates. Aliases and `build_info` are included, and
+    /// each histogram counts one series per bucket plus `_sum` and `_count`.
+    /// Families added with [`register_external`](Self::register_external)
+    /// are counted from the backend's output, and collectors are called to
+    /// count the series they report.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Fail CI when a change blows the cardinality budget
+    /// assert!(registry.series_count() <= 10_000);
+    /// ```
+    pub fn series_count(&self) -> usize {
+        let histogram_series = |name: &str| {
+            if self.summary_only.contains(name) {
+                2
+            } else {
+                // Finite buckets, +Inf, _sum and _count
+                self.histogram_buckets.get(name).map_or(0, Vec::len) + 3
+            }
+        };
+        let family_series = |name: &str| -> usize {
+            if self.counters.contains_key(name)
+                || self.counters_f64.contains_key(name)
+                || self.gauges.contains_key(name)
+            {
+                1
+            } else if self.histograms.contains_key(name) {
+                histogram_series(name)
+            } else if let Some(family) = self.labeled_histograms.get(name) {
+                family.series_count() * histogram_series(name)
+            } else if let Some(family) = self.labeled_gauges.get(name) {
+                family.series_count()
+            } else {
+                0
+            }
+        };
+
+        let names = self
+            .counters
+            .keys()
+            .chain(self.counters_f64.keys())
+            .chain(self.gauges.keys())
+            .chain(self.histograms.keys())
+            .chain(self.labeled_histograms.keys())
+            .chain(self.labeled_gauges.keys());
+        let registered: usize = names
+            .map(|name| {
+                let copies = 1 + self.aliases.get(name).map_or(0, Vec::len);
+                family_series(name) * copies
+            })
+            .sum();
+        let external: usize = if self.external.is_empty() {
+            0
+        } else {
+            let text = self.render_backend_text().unwrap_or_default();
+            split_families(&text)
+                .families
+                .iter()
+                .filter(|family| self.external.contains_key(family.name))
+                .map(|family| {
+                    let samples = family
+                        .lines
+                        .iter()
+                        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+                        .count();
+                    samples * (1 + self.aliases.get(family.name).map_or(0, Vec::len))
+                })
+                .sum()
+        };
+        let collected: usize = self.collectors.iter().map(|c| c.collect().len()).sum();
+
+        registered + external + collected + usize::from(self.build_info.is_some())
+    }
+
+    /// Render and check the output is well-formed text exposition.
+    ///
+    /// A self-test against rendering regressions and collectors reporting
+    /// clashing series; see [`check_exposition`] for what is checked, and
+    /// call it directly to check text from elsewhere. Renders like
+    /// [`render_text`](Self::render_text), so pending one-shot gauge updates
+    /// are left for the next scrape.
+    ///
+    /// # Example
+    /// ```ignore
+    /// registry.validate_output().expect("registry renders malformed output");
+    /// ```
+    pub fn validate_output(&self) -> Result<(), String> {
+        let text = self.render_text().map_err(|e| e.to_string())?;
+        check_exposition(&text)
+    }
+
+    /// Render in OpenMetrics form, with a `_created` sample per counter and histogram.
+    ///
+    /// `_created` holds the Unix time in seconds the metric was registered, so
+    /// consumers can tell a reset counter from one that never moved. Plain
+    /// [`render`](Self::render) output leaves these samples out, as the
+    /// classic Prometheus text format has no place for them. Serve the result
+    /// with [`OPENMETRICS_CONTENT_TYPE`].
+    pub fn render_openmetrics(&self) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        let exposition = split_families(&text);
+        let mut output = String::with_capacity(text.len() + exposition.families.len() * 48);
+
+        for family in &exposition.families {
+            output.extend(family.lines.iter().copied());
+
+            let created = self
+                .counters
+                .get(family.name)
+                .map(Metric::created)
+                .or_else(|| self.histograms.get(family.name).map(Metric::created));
+            if let Some(created) = created {
+                let seconds = created
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64();
+                output.push_str(&format!("{}_created {}\n", family.name, seconds));
+            }
+        }
+
+        output.extend(exposition.trailer.iter().copied());
+        if !exposition
+            .trailer
+            .iter()
+            .any(|line| line.trim_end() == "# EOF")
+        {
+            output.push_str("# EOF\n");
+        }
+
+        Ok(output)
+    }
+
+    /// Render only the series whose value changed since the previous call.
+    ///
+    /// The first call renders everything. Each call then makes its output the
+    /// baseline for the next one. Families with no changed series are left
+    /// out entirely; histograms are emitted whole when any of their samples
+    /// changed so bucket counts stay consistent with `_sum` and `_count`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let full = registry.render_delta()?;
+    /// requests.inc();
+    /// let delta = registry.render_delta()?; // only `requests_total`
+    /// ```
+    pub fn render_delta(&mut self) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        let exposition = split_families(&text);
+        let mut output = String::with_capacity(text.len());
+
+        for family in &exposition.families {
+            let (headers, samples): (Vec<&str>, Vec<&str>) =
+                family.lines.iter().partition(|line| line.starts_with('#'));
+            let is_histogram = headers
+                .iter()
+                .any(|line| line.trim_end().ends_with(" histogram"));
+
+            let mut changed = Vec::with_capacity(samples.len());
+            for line in &samples {
+                let (series, value) = line.trim_end().rsplit_once(' ').unwrap_or((line, ""));
+                if self.delta_baseline.get(series).map(String::as_str) != Some(value) {
+                    self.delta_baseline
+                        .insert(series.to_string(), value.to_string());
+                    changed.push(*line);
+                }
+            }
+
+            if changed.is_empty() {
+                continue;
+            }
+            output.extend(headers.iter().copied());
+            if is_histogram {
+                output.extend(samples.iter().copied());
+            } else {
+                output.extend(changed);
+            }
+        }
+        output.extend(exposition.trailer.iter().copied());
+
+        Ok(output)
+    }
+
+    /// Render with `prefix` and an underscore prepended to every metric name.
+    ///
+    /// Only the output is changed; the registry keeps its names, so the same
+    /// registry can be exposed under several namespaces. Prefixed names must
+    /// still pass [`validate_metric_name`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// // `requests_total` is rendered as `edge_requests_total`
+    /// let text = registry.render_with_prefix("edge")?;
+    /// ```
+    pub fn render_with_prefix(&self, prefix: &str) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        if prefix.is_empty() {
+            return Ok(text);
+        }
+
+        let exposition = split_families(&text);
+        let mut output = String::with_capacity(text.len() + exposition.families.len() * 64);
+
+        for family in &exposition.families {
+            validate_metric_name(&format!("{}_{}", prefix, family.name), self.max_name_length)?;
+
+            for line in &family.lines {
+                match line.strip_prefix("# ") {
+                    // `# HELP name ...`, `# TYPE name ...`, `# UNIT name ...`
+                    Some(header) => {
+                        let (keyword, rest) = header.split_once(' ').unwrap_or((header, ""));
+                        output.push_str(&format!("# {} {}_{}", keyword, prefix, rest));
+                    }
+                    None => {
+                        output.push_str(prefix);
+                        output.push('_');
+                        output.push_str(line);
+                    }
+                }
+            }
+        }
+        output.extend(exposition.trailer.iter().copied());
+
+        Ok(output)
+    }
+
+    /// Render in the Graphite plaintext protocol (feature: `graphite`).
+    ///
+    /// Emits one `path value timestamp` line per series, with `ts` as whole
+    /// seconds since the Unix epoch. Underscores in metric names become dots
+    /// and labels are appended to the path as `label.value`; `prefix`, if not
+    /// empty, starts every path. `NaN` and infinite samples are left out.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // `requests_total 3` is rendered as `edge.requests.total.total 3 1700000000`
+    /// let lines = registry.render_graphite("edge", SystemTime::now())?;
+    /// carbon.write_all(lines.as_bytes())?;
+    /// ```
+    #[cfg(feature = "graphite")]
+    pub fn render_graphite(
+        &self,
+        prefix: &str,
+        ts: SystemTime,
+    ) -> Result<String, DeserializeError> {
+        let text = self.render_text()?;
+        let seconds = ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Ok(super::graphite::to_graphite(&text, prefix, seconds))
+    }
+
+    /// Render the metrics and write them to `path`.
+    ///
+    /// The output is written to a temporary file next to `path` and renamed
+    /// into place, so a process reading `path` never sees a partial file.
+    /// Concurrent calls, from any thread, each write their own temporary file.
+    ///
+    /// # Example
+    /// ```ignore
+    /// registry.write_to_file("/var/lib/node_exporter/textfile/app.prom")?;
+    /// ```
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), DeserializeError> {
+        let path = path.as_ref();
+        let rendered = self
+            .render_peek()
+            .map_err(|e| DeserializeError::Render(e.to_string()))?;
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| DeserializeError::InvalidFilePath(path.display().to_string()))?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(
+            ".tmp-{}-{}",
+            std::process::id(),
+            TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+
+        let result = std::fs::File::create(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(rendered.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|()| std::fs::rename(&temp_path, path));
+
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        Ok(result?)
+    }
+
+    /// Render the backend registry alone, without any render-time changes.
+    fn render_backend_text(&self) -> Result<String, DeserializeError> {
+        let rendered = self
+            .inner
+            .render()
+            .map_err(|e| DeserializeError::Render(e.to_string()))?;
+        String::from_utf8(rendered.into_bytes())
+            .map_err(|e| DeserializeError::Render(e.to_string()))
+    }
+
+    /// Render and decode the output as text, leaving one-shot updates pending.
+    pub(crate) fn render_text(&self) -> Result<String, DeserializeError> {
+        let rendered = self
+            .render_peek()
+            .map_err(|e| DeserializeError::Render(e.to_string()))?;
+        String::from_utf8(rendered.into_bytes())
+            .map_err(|e| DeserializeError::Render(e.to_string()))
     }
 
     /// Get a reference to the underlying registry.
@@ -153,6 +1858,35 @@ impl<B: MetricBackend> ObservabilityRegistry<B> {
     }
 }
 
+impl<B: MetricBackend> ObservabilityRegistry<B>
+where
+    B::Histogram: HistogramStatsTrait,
+{
+    /// Read the current value of the `kind` metric named `name` as an `f64`.
+    ///
+    /// Counters, integer or floating point, and gauges return their value and
+    /// histograms the sum of their observations. Returns `None` if no such metric is registered.
+    ///
+    /// # Example
+    /// ```ignore
+    /// for metric in registry.snapshot().metrics {
+    ///     let value = registry.current_value(&metric.name, metric.kind);
+    ///     println!("{} = {:?}", metric.name, value);
+    /// }
+    /// ```
+    pub fn current_value(&self, name: &str, kind: MetricKind) -> Option<f64> {
+        match kind {
+            MetricKind::Counter => self
+                .counters
+                .get(name)
+                .map(|m| m.get_counter() as f64)
+                .or_else(|| self.counters_f64.get(name).map(Metric::get_counter_f64)),
+            MetricKind::Gauge => self.gauges.get(name).map(|m| m.get_gauge() as f64),
+            MetricKind::Histogram => self.histograms.get(name).map(Metric::sum),
+        }
+    }
+}
+
 impl<B: MetricBackend> Default for ObservabilityRegistry<B> {
     fn default() -> Self {
         Self::new()
@@ -1,9 +1,11 @@
 //! Metrics rendering for different output formats.
 
+use std::collections::{HashMap, HashSet};
+
 /// Trait for registries that can render their metrics.
 pub trait MetricsRenderer {
     /// Error type for rendering failures.
-    type Error;
+    type Error: std::error::Error;
 
     /// Render metrics in the appropriate format (Prometheus text, JSON, etc.)
     fn render(&self) -> Result<RenderedMetrics, Self::Error>;
@@ -45,6 +47,444 @@ impl RenderedMetrics {
     }
 }
 
+/// Errors returned when rendering is bounded by a deadline.
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("Rendering did not complete within {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("Failed to render metrics: {0}")]
+    Failed(String),
+}
+
+/// Replace the help text on a metric's `# HELP` line.
+///
+/// Only the help text itself is swapped, so any formatting the backend adds
+/// around it is preserved. Non UTF-8 bodies are returned unchanged.
+pub(crate) fn replace_help_text(
+    rendered: RenderedMetrics,
+    name: &str,
+    old_help: &str,
+    new_help: &str,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let old_prefix = format!("# HELP {} {}", name, old_help);
+    let new_prefix = format!("# HELP {} {}", name, new_help);
+    let mut output = String::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        match line.strip_prefix(&old_prefix) {
+            Some(rest) => {
+                output.push_str(&new_prefix);
+                output.push_str(rest);
+            }
+            None => output.push_str(line),
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Replacement written in place of redacted label values.
+#[cfg(feature = "redaction")]
+pub const REDACTED_LABEL_VALUE: &str = "<redacted>";
+
+/// Replace label values matching `pattern` with [`REDACTED_LABEL_VALUE`].
+///
+/// Values are matched unescaped, so the pattern sees what was recorded.
+/// Sample lines whose label set can't be parsed, and non UTF-8 bodies, are
+/// returned unchanged.
+#[cfg(feature = "redaction")]
+pub(crate) fn redact_label_values(
+    rendered: RenderedMetrics,
+    pattern: &regex::Regex,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        match redact_sample_line(line, pattern) {
+            Some(redacted) => output.push_str(&redacted),
+            None => output.push_str(line),
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Redact one sample line, or `None` if it has no label set to redact.
+#[cfg(feature = "redaction")]
+fn redact_sample_line(line: &str, pattern: &regex::Regex) -> Option<String> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let open = line.find('{')?;
+    if line[..open].contains(char::is_whitespace) {
+        return None;
+    }
+
+    let mut output = String::with_capacity(line.len());
+    output.push_str(&line[..=open]);
+    let mut rest = &line[open + 1..];
+    let mut redacted_any = false;
+
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            output.push('}');
+            output.push_str(after);
+            return redacted_any.then_some(output);
+        }
+
+        let (label, after) = rest.split_once("=\"")?;
+        output.push_str(label);
+        output.push_str("=\"");
+
+        // Find the closing quote, skipping escaped characters
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    other => value.push(other),
+                },
+                (index, '"') => break index,
+                (_, other) => value.push(other),
+            }
+        };
+
+        if pattern.is_match(&value) {
+            output.push_str(REDACTED_LABEL_VALUE);
+            redacted_any = true;
+        } else {
+            output.push_str(&after[..end]);
+        }
+        output.push('"');
+
+        rest = &after[end + 1..];
+        if let Some(after) = rest.strip_prefix(',') {
+            output.push(',');
+            rest = after;
+        }
+    }
+}
+
+/// Drop the `_bucket` samples of the histograms named in `histograms`.
+///
+/// Their `_sum` and `_count` samples are kept. Non UTF-8 bodies are
+/// returned unchanged.
+pub(crate) fn drop_bucket_samples(
+    rendered: RenderedMetrics,
+    histograms: &HashSet<String>,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let series = line
+            .split(|c: char| c == '{' || c.is_whitespace())
+            .next()
+            .unwrap_or(line);
+        let is_dropped = !line.starts_with('#')
+            && series
+                .strip_suffix("_bucket")
+                .is_some_and(|name| histograms.contains(name));
+        if !is_dropped {
+            output.push_str(line);
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Remove the samples of the metrics in `names`, keeping their metadata lines.
+///
+/// Non UTF-8 bodies are returned unchanged.
+pub(crate) fn drop_samples(rendered: RenderedMetrics, names: &HashSet<&str>) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let series = line
+            .split(|c: char| c == '{' || c.is_whitespace())
+            .next()
+            .unwrap_or(line);
+        if line.starts_with('#') || !names.contains(series) {
+            output.push_str(line);
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Replace the value of the unlabeled sample of each gauge in `values`.
+///
+/// Non UTF-8 bodies are returned unchanged.
+pub(crate) fn replace_gauge_values(
+    rendered: RenderedMetrics,
+    values: &HashMap<&str, i64>,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let replaced = line.split_once(' ').and_then(|(series, _)| {
+            let value = values.get(series)?;
+            Some(format!("{} {}\n", series, value))
+        });
+        match replaced {
+            Some(replaced) if !line.starts_with('#') => output.push_str(&replaced),
+            _ => output.push_str(line),
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Replace the value of the unlabeled `_sum` sample of each histogram in `sums`.
+///
+/// Non UTF-8 bodies are returned unchanged.
+pub(crate) fn replace_sum_values(
+    rendered: RenderedMetrics,
+    sums: &HashMap<&str, u64>,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let replaced = line.split_once(' ').and_then(|(series, _)| {
+            let sum = sums.get(series.strip_suffix("_sum")?)?;
+            Some(format!("{} {}\n", series, sum))
+        });
+        match replaced {
+            Some(replaced) if !line.starts_with('#') => output.push_str(&replaced),
+            _ => output.push_str(line),
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Sort the labels of every sample line alphabetically by name.
+///
+/// Label values are moved as written, escapes included. Sample lines whose
+/// label set can't be parsed, and non UTF-8 bodies, are returned unchanged.
+pub(crate) fn sort_labels(rendered: RenderedMetrics) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        match sort_sample_labels(line) {
+            Some(sorted) => output.push_str(&sorted),
+            None => output.push_str(line),
+        }
+    }
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Sort the labels of one sample line, or `None` if it has no label set.
+fn sort_sample_labels(line: &str) -> Option<String> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let (name, mut labels, tail) = parse_sample(line)?;
+    if !line[name.len()..].starts_with('{') {
+        return None;
+    }
+
+    labels.sort_by(|a, b| a.0.cmp(b.0));
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, value))
+        .collect();
+    Some(format!("{}{{{}}}{}", name, pairs.join(","), tail))
+}
+
+/// Label names and values of a sample, as written.
+pub(crate) type LabelPairs<'a> = Vec<(&'a str, &'a str)>;
+
+/// Split a sample line into its series name, label pairs and the rest of
+/// the line after the label set.
+///
+/// Label values are returned as written, escapes included. Returns `None`
+/// if the label set can't be parsed.
+pub(crate) fn parse_sample(line: &str) -> Option<(&str, LabelPairs<'_>, &str)> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let (name, rest) = line.split_at(name_end);
+    let mut labels = Vec::new();
+    let Some(mut rest) = rest.strip_prefix('{') else {
+        return Some((name, labels, rest));
+    };
+
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            return Some((name, labels, after));
+        }
+
+        let (label, after) = rest.split_once("=\"")?;
+        // Find the closing quote, skipping escaped characters
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (_, '\\') => {
+                    chars.next()?;
+                }
+                (index, '"') => break index,
+                _ => {}
+            }
+        };
+        labels.push((label, &after[..end]));
+
+        rest = &after[end + 1..];
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after;
+        }
+    }
+}
+
+/// Repeat each family listed in `aliases` under every one of its alias names.
+///
+/// The copies follow the original family and carry the same samples, so the
+/// aliases always match it. Non UTF-8 bodies are returned unchanged.
+pub(crate) fn mirror_families(
+    rendered: RenderedMetrics,
+    aliases: &HashMap<String, Vec<String>>,
+) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let exposition = split_families(text);
+    let mut output = String::with_capacity(text.len() * 2);
+    for family in &exposition.families {
+        output.extend(family.lines.iter().copied());
+        for alias in aliases.get(family.name).into_iter().flatten() {
+            for line in &family.lines {
+                rename_family_line(&mut output, line, family.name, alias);
+            }
+        }
+    }
+    output.extend(exposition.trailer.iter().copied());
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// Push `line` of family `name` to `output` with the family renamed to `alias`.
+fn rename_family_line(output: &mut String, line: &str, name: &str, alias: &str) {
+    let renamed = match line.strip_prefix("# ") {
+        // `# HELP name ...`, `# TYPE name ...`, `# UNIT name ...`
+        Some(header) => header.split_once(' ').and_then(|(keyword, rest)| {
+            let rest = rest.strip_prefix(name)?;
+            Some(format!("# {} {}{}", keyword, alias, rest))
+        }),
+        None => line
+            .strip_prefix(name)
+            .map(|rest| format!("{}{}", alias, rest)),
+    };
+    output.push_str(renamed.as_deref().unwrap_or(line));
+}
+
+/// Append a metric family's lines to text exposition output.
+///
+/// The family goes before a trailing OpenMetrics `# EOF` marker if there is
+/// one. Non UTF-8 bodies are returned unchanged.
+pub(crate) fn append_family(rendered: RenderedMetrics, family: &str) -> RenderedMetrics {
+    let Ok(text) = rendered.as_str() else {
+        return rendered;
+    };
+
+    let (body, trailer) = match text.rfind("# EOF") {
+        Some(index) if text[index..].trim_end() == "# EOF" => text.split_at(index),
+        _ => (text, ""),
+    };
+    let mut output = String::with_capacity(text.len() + family.len());
+    output.push_str(body);
+    output.push_str(family);
+    output.push_str(trailer);
+
+    RenderedMetrics::new(rendered.content_type, output.into_bytes())
+}
+
+/// The lines of a single metric family in text exposition output.
+pub(crate) struct FamilyBlock<'a> {
+    /// The family name as it appears on its `# HELP` / `# TYPE` lines
+    pub name: &'a str,
+    /// Every line of the family, newline terminators included
+    pub lines: Vec<&'a str>,
+}
+
+/// Text exposition output split into metric families.
+pub(crate) struct Exposition<'a> {
+    pub families: Vec<FamilyBlock<'a>>,
+    /// Lines after the last family, such as the OpenMetrics `# EOF` marker
+    pub trailer: Vec<&'a str>,
+}
+
+impl Exposition<'_> {
+    /// Reassemble the families and trailer into exposition text.
+    pub fn to_text(&self) -> String {
+        self.families
+            .iter()
+            .flat_map(|family| family.lines.iter())
+            .chain(self.trailer.iter())
+            .copied()
+            .collect()
+    }
+}
+
+/// Split text exposition output into its metric families.
+///
+/// A family starts at the first `# HELP`, `# TYPE` or `# UNIT` line naming it
+/// and owns every sample line up to the next family header.
+pub(crate) fn split_families(text: &str) -> Exposition<'_> {
+    let mut families: Vec<FamilyBlock<'_>> = Vec::new();
+    let mut trailer = Vec::new();
+
+    for line in text.split_inclusive('\n') {
+        if let Some(name) = family_header_name(line) {
+            match families.last_mut() {
+                Some(family) if family.name == name => family.lines.push(line),
+                _ => families.push(FamilyBlock {
+                    name,
+                    lines: vec![line],
+                }),
+            }
+        } else if line.starts_with('#') {
+            trailer.push(line);
+        } else if let Some(family) = families.last_mut() {
+            family.lines.push(line);
+        }
+    }
+
+    Exposition { families, trailer }
+}
+
+/// Returns the metric name if `line` is a `# HELP`, `# TYPE` or `# UNIT` line.
+fn family_header_name(line: &str) -> Option<&str> {
+    let rest = line
+        .strip_prefix("# HELP ")
+        .or_else(|| line.strip_prefix("# TYPE "))
+        .or_else(|| line.strip_prefix("# UNIT "))?;
+    rest.split_whitespace().next()
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Prometheus MetricsRenderer implementation
 // ═══════════════════════════════════════════════════════════════════════════
@@ -58,8 +498,97 @@ impl MetricsRenderer for prometheus_client::registry::Registry {
         prometheus_client::encoding::text::encode(&mut buffer, self)?;
 
         Ok(RenderedMetrics::new(
-            "text/plain; version=0.0.4; charset=utf-8",
+            super::registry::PROMETHEUS_CONTENT_TYPE,
             buffer.into_bytes(),
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# HELP requests Requests.\n# TYPE requests counter\nrequests_total 3\n# HELP depth Depth.\n# TYPE depth gauge\ndepth 7\n# EOF\n";
+
+    #[test]
+    fn test_split_families() {
+        let exposition = split_families(SAMPLE);
+
+        assert_eq!(exposition.families.len(), 2);
+        assert_eq!(exposition.families[0].name, "requests");
+        assert_eq!(
+            exposition.families[0].lines,
+            vec![
+                "# HELP requests Requests.\n",
+                "# TYPE requests counter\n",
+                "requests_total 3\n"
+            ]
+        );
+        assert_eq!(exposition.families[1].name, "depth");
+        assert_eq!(exposition.trailer, vec!["# EOF\n"]);
+        assert_eq!(exposition.to_text(), SAMPLE);
+    }
+
+    #[test]
+    fn test_sort_labels() {
+        let text = "# HELP hits Hits.\n# TYPE hits counter\nhits_total{b=\"2\",a=\"x,\\\"}\",c=\"3\"} 1\nhits_total 4\n";
+        let rendered = sort_labels(RenderedMetrics::new("text/plain", text.as_bytes().to_vec()));
+
+        assert_eq!(
+            rendered.as_str().unwrap(),
+            "# HELP hits Hits.\n# TYPE hits counter\nhits_total{a=\"x,\\\"}\",b=\"2\",c=\"3\"} 1\nhits_total 4\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_help_text() {
+        let rendered = RenderedMetrics::new("text/plain", SAMPLE.as_bytes().to_vec());
+        let rendered = replace_help_text(rendered, "depth", "Depth", "Queue depth");
+
+        let text = rendered.as_str().unwrap();
+        assert!(text.contains("# HELP depth Queue depth.\n"));
+        assert!(text.contains("# HELP requests Requests.\n"));
+    }
+
+    #[test]
+    fn test_mirror_families() {
+        let rendered = RenderedMetrics::new("text/plain", SAMPLE.as_bytes().to_vec());
+        let aliases = HashMap::from([("requests".to_string(), vec!["legacy".to_string()])]);
+
+        let rendered = mirror_families(rendered, &aliases);
+        assert_eq!(
+            rendered.as_str().unwrap(),
+            "# HELP requests Requests.\n# TYPE requests counter\nrequests_total 3\n\
+             # HELP legacy Requests.\n# TYPE legacy counter\nlegacy_total 3\n\
+             # HELP depth Depth.\n# TYPE depth gauge\ndepth 7\n# EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_append_family_keeps_eof_last() {
+        let rendered = RenderedMetrics::new("text/plain", SAMPLE.as_bytes().to_vec());
+        let rendered = append_family(rendered, "# TYPE up gauge\nup 1\n");
+        assert!(rendered
+            .as_str()
+            .unwrap()
+            .ends_with("depth 7\n# TYPE up gauge\nup 1\n# EOF\n"));
+
+        let rendered = RenderedMetrics::new("text/plain", b"depth 7\n".to_vec());
+        let rendered = append_family(rendered, "up 1\n");
+        assert_eq!(rendered.as_str().unwrap(), "depth 7\nup 1\n");
+    }
+
+    #[cfg(feature = "redaction")]
+    #[test]
+    fn test_redact_label_values() {
+        let text = "# TYPE logins counter\nlogins_total{user=\"ann@example.com\",region=\"eu\"} 1\nlogins_total{user=\"say \\\"hi\\\"\",region=\"us\"} 2\nlogins_total 3\n";
+        let rendered = RenderedMetrics::new("text/plain", text.as_bytes().to_vec());
+        let pattern = regex::Regex::new(r"@|hi").unwrap();
+
+        let rendered = redact_label_values(rendered, &pattern);
+        assert_eq!(
+            rendered.as_str().unwrap(),
+            "# TYPE logins counter\nlogins_total{user=\"<redacted>\",region=\"eu\"} 1\nlogins_total{user=\"<redacted>\",region=\"us\"} 2\nlogins_total 3\n"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Exponentially-weighted smoothing for noisy gauges.
+//!
+//! Some gauges (queue depths, CPU load) jump around between scrapes, so the
+//! last value set says little about the trend. Exporting a moving average
+//! gives dashboards a steadier signal.
+
+use super::metrics::{FloatGaugeTrait, Metric};
+use std::sync::Mutex;
+
+/// A float gauge that exports an exponentially-weighted moving average.
+///
+/// Each [`set`](Self::set) updates the average as
+/// `smoothed = alpha * value + (1 - alpha) * smoothed` and writes it to the
+/// wrapped gauge, so the smoothed value is what gets rendered. The first
+/// value is taken as is. The raw value is kept and available through
+/// [`raw`](Self::raw).
+///
+/// # Example
+/// ```ignore
+/// let load = SmoothedGauge::new(float_gauge("cpu_load", "Smoothed CPU load"), 0.2);
+///
+/// load.set(sample_cpu_load());
+/// println!("raw {} smoothed {}", load.raw().unwrap_or_default(), load.smoothed());
+/// ```
+#[derive(Debug)]
+pub struct SmoothedGauge<T: FloatGaugeTrait> {
+    gauge: Metric<T>,
+    alpha: f64,
+    raw: Mutex<Option<f64>>,
+}
+
+impl<T: FloatGaugeTrait> SmoothedGauge<T> {
+    /// Wrap a float gauge, weighting each new value by `alpha`.
+    ///
+    /// Higher values of `alpha` follow changes faster; `1.0` disables
+    /// smoothing altogether.
+    ///
+    /// # Panics
+    /// Panics if `alpha` is not in `(0.0, 1.0]`.
+    pub fn new(gauge: Metric<T>, alpha: f64) -> Self {
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "smoothing factor must be in (0, 1], got {alpha}"
+        );
+        Self {
+            gauge,
+            alpha,
+            raw: Mutex::new(None),
+        }
+    }
+
+    /// Record a new raw value and update the exported average.
+    ///
+    /// NaN and infinite values are ignored: once folded in, the average
+    /// could never recover from them.
+    pub fn set(&self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let mut raw = self.raw.lock().unwrap();
+        let smoothed = match *raw {
+            None => value,
+            Some(_) => self.alpha * value + (1.0 - self.alpha) * self.gauge.get_gauge_f64(),
+        };
+        *raw = Some(value);
+        self.gauge.set_f64(smoothed);
+    }
+
+    /// The last raw value set, `None` before the first [`set`](Self::set).
+    pub fn raw(&self) -> Option<f64> {
+        *self.raw.lock().unwrap()
+    }
+
+    /// The current smoothed value, as exported by the wrapped gauge.
+    pub fn smoothed(&self) -> f64 {
+        self.gauge.get_gauge_f64()
+    }
+
+    /// The smoothing factor.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Access the wrapped gauge.
+    pub fn metric(&self) -> &Metric<T> {
+        &self.gauge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::mock::MockFloatGauge;
+
+    fn smoothed(alpha: f64) -> SmoothedGauge<MockFloatGauge> {
+        SmoothedGauge::new(Metric::new("load", "Load", MockFloatGauge::new()), alpha)
+    }
+
+    #[test]
+    fn test_smoothed_gauge_keeps_raw_value() {
+        let gauge = smoothed(0.5);
+        assert_eq!(gauge.raw(), None);
+
+        gauge.set(10.0);
+        assert_eq!(gauge.smoothed(), 10.0);
+
+        gauge.set(20.0);
+        assert_eq!(gauge.raw(), Some(20.0));
+        assert_eq!(gauge.smoothed(), 15.0);
+        assert_eq!(gauge.metric().get_gauge_f64(), 15.0);
+    }
+
+    #[test]
+    fn test_alpha_of_one_disables_smoothing() {
+        let gauge = smoothed(1.0);
+        gauge.set(3.0);
+        gauge.set(7.0);
+        assert_eq!(gauge.smoothed(), 7.0);
+    }
+
+    #[test]
+    fn test_non_finite_values_are_ignored() {
+        let gauge = smoothed(0.5);
+        gauge.set(f64::NAN);
+        assert_eq!(gauge.raw(), None);
+
+        gauge.set(10.0);
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            gauge.set(value);
+        }
+        assert_eq!(gauge.raw(), Some(10.0));
+        assert_eq!(gauge.smoothed(), 10.0);
+
+        gauge.set(20.0);
+        assert_eq!(gauge.smoothed(), 15.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "smoothing factor")]
+    fn test_invalid_alpha_panics() {
+        smoothed(0.0);
+    }
+}
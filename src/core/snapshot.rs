@@ -0,0 +1,180 @@
+//! Point-in-time views of registry contents.
+//!
+//! A snapshot copies metric values out of a registry so they can be
+//! inspected, compared or serialised without holding on to the registry.
+
+use super::metrics::MetricKind;
+use std::cell::RefCell;
+use std::sync::{Arc, PoisonError, RwLock, RwLockWriteGuard};
+use std::time::SystemTime;
+
+/// The value of a metric at snapshot time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotValue {
+    Counter(u64),
+    Gauge(i64),
+    /// A counter or gauge read from text exposition that isn't a whole number
+    Float(f64),
+    /// Histograms expose no readable value through the core traits
+    Histogram,
+}
+
+/// A single metric captured by [`ObservabilityRegistry::snapshot`].
+///
+/// [`ObservabilityRegistry::snapshot`]: super::registry::ObservabilityRegistry::snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSnapshot {
+    pub name: String,
+    pub kind: MetricKind,
+    pub value: SnapshotValue,
+    /// Sorted label pairs of the series, empty for unlabeled metrics
+    pub labels: Vec<(String, String)>,
+    /// When the metric was last updated, `None` if it never was
+    pub last_updated: Option<SystemTime>,
+}
+
+/// A registered metric's definition, as listed by [`ObservabilityRegistry::describe`].
+///
+/// [`ObservabilityRegistry::describe`]: super::registry::ObservabilityRegistry::describe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricDescription {
+    pub name: String,
+    pub kind: MetricKind,
+    pub description: String,
+}
+
+/// Every metric of a registry, sorted by name and then labels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    pub metrics: Vec<MetricSnapshot>,
+}
+
+impl MetricsSnapshot {
+    /// Look up a metric in the snapshot by name.
+    ///
+    /// For a labeled family this is the series with the lowest labels; use
+    /// [`get_series`](Self::get_series) to pick one.
+    pub fn get(&self, name: &str) -> Option<&MetricSnapshot> {
+        let index = self
+            .metrics
+            .partition_point(|metric| metric.name.as_str() < name);
+        self.metrics.get(index).filter(|metric| metric.name == name)
+    }
+
+    /// Look up one series of a metric by name and labels, in any order.
+    pub fn get_series(&self, name: &str, labels: &[(&str, &str)]) -> Option<&MetricSnapshot> {
+        let mut labels = labels.to_vec();
+        labels.sort_unstable();
+        self.metrics.iter().find(|metric| {
+            metric.name == name
+                && metric.labels.len() == labels.len()
+                && metric
+                    .labels
+                    .iter()
+                    .zip(&labels)
+                    .all(|((k, v), (key, value))| k == key && v == value)
+        })
+    }
+
+    /// Number of metrics in the snapshot.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Returns true if the snapshot holds no metrics.
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+/// Holds off metric updates while a consistent snapshot is read.
+///
+/// Every update made through a registry's [`Metric`] and [`Labeled`]
+/// handles holds the registry's gate shared, and
+/// [`ObservabilityRegistry::consistent_snapshot`] takes it exclusively while
+/// reading, so no update is caught half-applied. Updates that must be seen
+/// together, such as two counters that move in step, can be grouped with
+/// [`update`](Self::update); handle updates inside a group don't take the
+/// gate again. Updates made on a handle's
+/// [`inner`](super::metrics::Metric::inner) metric, or on series handles
+/// from a labeled family, bypass the gate.
+///
+/// The cost is on the update side: every handle update takes an
+/// uncontended read lock, which threads updating the same registry contend
+/// on, and waits while a consistent snapshot is being read. A consistent
+/// snapshot waits for the updates in progress to finish. Take consistent
+/// snapshots sparingly; [`snapshot`] takes no lock. Don't take a consistent
+/// snapshot from inside [`update`](Self::update), which would deadlock.
+///
+/// # Example
+/// ```ignore
+/// let gate = registry.snapshot_gate();
+/// gate.update(|| {
+///     sent.inc();
+///     in_flight.gauge_inc();
+/// });
+/// ```
+///
+/// [`Metric`]: super::metrics::Metric
+/// [`Labeled`]: super::labeled::Labeled
+/// [`ObservabilityRegistry::consistent_snapshot`]: super::registry::ObservabilityRegistry::consistent_snapshot
+/// [`snapshot`]: super::registry::ObservabilityRegistry::snapshot
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotGate {
+    lock: Arc<RwLock<()>>,
+}
+
+thread_local! {
+    /// Gates the current thread is updating through, so nested updates
+    /// don't take the same gate twice
+    static HELD_GATES: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+impl SnapshotGate {
+    /// Run `updates` so that no consistent snapshot is taken part-way through.
+    pub fn update<R>(&self, updates: impl FnOnce() -> R) -> R {
+        let key = self.key();
+        if HELD_GATES.with(|held| held.borrow().contains(&key)) {
+            return updates();
+        }
+        // The lock guards no data, so a panic while holding it leaves nothing broken
+        let _shared = self.lock.read().unwrap_or_else(PoisonError::into_inner);
+        let _held = HeldGate::enter(key);
+        updates()
+    }
+
+    /// Wait for the updates in progress and hold off new ones.
+    pub(crate) fn exclusive(&self) -> RwLockWriteGuard<'_, ()> {
+        debug_assert!(
+            !HELD_GATES.with(|held| held.borrow().contains(&self.key())),
+            "consistent snapshot taken inside a gated update"
+        );
+        self.lock.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Identifies the gate shared between clones.
+    fn key(&self) -> usize {
+        Arc::as_ptr(&self.lock) as usize
+    }
+}
+
+/// Marks a gate as held by the current thread until dropped.
+struct HeldGate(usize);
+
+impl HeldGate {
+    fn enter(key: usize) -> Self {
+        HELD_GATES.with(|held| held.borrow_mut().push(key));
+        Self(key)
+    }
+}
+
+impl Drop for HeldGate {
+    fn drop(&mut self) {
+        HELD_GATES.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(position) = held.iter().rposition(|key| *key == self.0) {
+                held.swap_remove(position);
+            }
+        });
+    }
+}
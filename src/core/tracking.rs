@@ -0,0 +1,111 @@
+//! Gauges that also export their lowest and highest value.
+//!
+//! A scrape only sees the gauge value at that moment, so short spikes and
+//! dips between scrapes are lost. Tracking the extremes since start keeps
+//! them visible on dashboards.
+
+use super::metrics::{GaugeTrait, Metric};
+use std::sync::Mutex;
+
+/// Suffix of the gauge holding the lowest value seen.
+pub const MIN_SUFFIX: &str = "_min";
+
+/// Suffix of the gauge holding the highest value seen.
+pub const MAX_SUFFIX: &str = "_max";
+
+/// A gauge that keeps sibling `_min` and `_max` gauges up to date.
+///
+/// Each [`set`](Self::set) writes the value to the current gauge and
+/// widens the min and max gauges if needed. Before the first value is set
+/// all three read `0`.
+///
+/// # Example
+/// ```ignore
+/// let depth = registry.tracking_gauge("queue_depth", "Queue depth")?;
+///
+/// depth.set(queue.len() as i64);
+/// // renders queue_depth, queue_depth_min and queue_depth_max
+/// ```
+#[derive(Debug)]
+pub struct TrackingGauge<T: GaugeTrait> {
+    current: Metric<T>,
+    min: Metric<T>,
+    max: Metric<T>,
+    seen: Mutex<bool>,
+}
+
+impl<T: GaugeTrait> TrackingGauge<T> {
+    /// Track `current`, exporting its extremes through `min` and `max`.
+    pub fn new(current: Metric<T>, min: Metric<T>, max: Metric<T>) -> Self {
+        Self {
+            current,
+            min,
+            max,
+            seen: Mutex::new(false),
+        }
+    }
+
+    /// Set the gauge to `value`, updating the min and max.
+    pub fn set(&self, value: i64) {
+        let mut seen = self.seen.lock().unwrap();
+        if !*seen || value < self.min.get_gauge() {
+            self.min.set(value);
+        }
+        if !*seen || value > self.max.get_gauge() {
+            self.max.set(value);
+        }
+        *seen = true;
+        self.current.set(value);
+    }
+
+    /// The current value.
+    pub fn get(&self) -> i64 {
+        self.current.get_gauge()
+    }
+
+    /// The lowest value set so far.
+    pub fn min(&self) -> i64 {
+        self.min.get_gauge()
+    }
+
+    /// The highest value set so far.
+    pub fn max(&self) -> i64 {
+        self.max.get_gauge()
+    }
+
+    /// Access the gauge holding the current value.
+    pub fn metric(&self) -> &Metric<T> {
+        &self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::mock::MockGauge;
+
+    fn gauge(name: &str) -> Metric<MockGauge> {
+        Metric::new(name, "Depth", MockGauge::new())
+    }
+
+    #[test]
+    fn test_tracking_gauge_keeps_extremes() {
+        let depth = TrackingGauge::new(gauge("depth"), gauge("depth_min"), gauge("depth_max"));
+
+        for value in [5, 2, 9] {
+            depth.set(value);
+        }
+        assert_eq!((depth.min(), depth.max(), depth.get()), (2, 9, 9));
+    }
+
+    #[test]
+    fn test_first_value_sets_both_extremes() {
+        let depth = TrackingGauge::new(gauge("depth"), gauge("depth_min"), gauge("depth_max"));
+
+        // Both extremes start from the first value, not from zero
+        depth.set(7);
+        assert_eq!((depth.min(), depth.max()), (7, 7));
+        depth.set(-3);
+        assert_eq!((depth.min(), depth.max()), (-3, 7));
+    }
+}
@@ -0,0 +1,340 @@
+//! Blocking metrics server for programs without an async runtime.
+//!
+//! [`BlockingServer`] binds a `std::net::TcpListener` and answers scrapes
+//! on a background thread, one connection at a time. It only serves
+//! `GET` on the metrics path; everything else gets a `404` or `405`. Use
+//! the standalone server when health endpoints or concurrency are needed.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use observability_kit::http::blocking::BlockingServer;
+//! use observability_kit::backends::prometheus::PrometheusBackend;
+//! use observability_kit::core::registry::ObservabilityRegistry;
+//! use std::sync::{Arc, RwLock};
+//!
+//! let registry = Arc::new(RwLock::new(ObservabilityRegistry::<PrometheusBackend>::new()));
+//! let jobs = registry.write().unwrap().counter("jobs_total", "Jobs processed")?;
+//!
+//! let server = BlockingServer::start("0.0.0.0:9090", registry.clone())?;
+//! jobs.inc();
+//! // ...
+//! server.shutdown();
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::scrape::{scrape, ScrapeResponse};
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use crate::core::renderer::MetricsRenderer;
+
+/// Path the metrics are served on unless configured otherwise.
+pub const DEFAULT_METRICS_PATH: &str = "/metrics";
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request line accepted, in bytes.
+const MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Content type of non-scrape responses.
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// A metrics server running on a background thread.
+///
+/// The server stops when [`shutdown`](Self::shutdown) is called or the
+/// handle is dropped.
+#[derive(Debug)]
+pub struct BlockingServer {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BlockingServer {
+    /// Serve `registry` on `addr` at [`DEFAULT_METRICS_PATH`].
+    pub fn start<B>(
+        addr: impl ToSocketAddrs,
+        registry: Arc<RwLock<ObservabilityRegistry<B>>>,
+    ) -> io::Result<Self>
+    where
+        B: MetricBackend,
+        <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+    {
+        Self::start_with_path(addr, DEFAULT_METRICS_PATH, registry)
+    }
+
+    /// Serve `registry` on `addr` at `path`.
+    pub fn start_with_path<B>(
+        addr: impl ToSocketAddrs,
+        path: &str,
+        registry: Arc<RwLock<ObservabilityRegistry<B>>>,
+    ) -> io::Result<Self>
+    where
+        B: MetricBackend,
+        <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+    {
+        if !path.starts_with('/') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("metrics path '{}' must start with '/'", path),
+            ));
+        }
+
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let path = path.to_string();
+
+        let thread = std::thread::Builder::new()
+            .name("metrics-server".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || accept_loop(listener, &path, &registry, &stop)
+            })?;
+
+        Ok(Self {
+            local_addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and wait for the server thread to exit.
+    pub fn shutdown(mut self) {
+        self.stop_thread();
+    }
+
+    fn stop_thread(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the blocked accept() so the thread sees the stop flag
+        let _ = TcpStream::connect(wake_addr(self.local_addr));
+        let _ = thread.join();
+    }
+}
+
+impl Drop for BlockingServer {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// The address to connect to in order to reach a listener bound to `addr`.
+fn wake_addr(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(v4) if v4.ip().is_unspecified() => {
+            SocketAddr::from((Ipv4Addr::LOCALHOST, v4.port()))
+        }
+        SocketAddr::V6(v6) if v6.ip().is_unspecified() => {
+            SocketAddr::from((Ipv6Addr::LOCALHOST, v6.port()))
+        }
+        addr => addr,
+    }
+}
+
+fn accept_loop<B>(
+    listener: TcpListener,
+    path: &str,
+    registry: &RwLock<ObservabilityRegistry<B>>,
+    stop: &AtomicBool,
+) where
+    B: MetricBackend,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        // A misbehaving client only loses its own connection
+        if let Ok(stream) = stream {
+            let _ = handle_connection(stream, path, registry);
+        }
+    }
+}
+
+fn handle_connection<B>(
+    stream: TcpStream,
+    path: &str,
+    registry: &RwLock<ObservabilityRegistry<B>>,
+) -> io::Result<()>
+where
+    B: MetricBackend,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    (&mut reader)
+        .take(MAX_REQUEST_LINE as u64)
+        .read_line(&mut request_line)?;
+    // Drain the headers; the request has no body we care about
+    let mut header = String::new();
+    loop {
+        header.clear();
+        let read = (&mut reader)
+            .take(MAX_REQUEST_LINE as u64)
+            .read_line(&mut header)?;
+        if read == 0 || header == "\r\n" || header == "\n" {
+            break;
+        }
+    }
+
+    let response = route(&request_line, path, registry);
+    write_response(&stream, &response)
+}
+
+/// Work out the response to `request_line`.
+fn route<B>(
+    request_line: &str,
+    path: &str,
+    registry: &RwLock<ObservabilityRegistry<B>>,
+) -> ScrapeResponse
+where
+    B: MetricBackend,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => return text_response(400, "Bad Request"),
+    };
+    // Ignore any query string, as scrapers may add one
+    let target = target.split('?').next().unwrap_or(target);
+
+    if target != path {
+        return text_response(404, "Not Found");
+    }
+    if method != "GET" {
+        return text_response(405, "Method Not Allowed");
+    }
+    match registry.read() {
+        Ok(registry) => scrape(&registry),
+        Err(_) => text_response(500, "Registry lock poisoned"),
+    }
+}
+
+fn text_response(status: u16, body: &str) -> ScrapeResponse {
+    ScrapeResponse {
+        status,
+        content_type: TEXT_CONTENT_TYPE.to_string(),
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+fn write_response(mut stream: &TcpStream, response: &ScrapeResponse) -> io::Result<()> {
+    let allow = if response.status == 405 {
+        "Allow: GET\r\n"
+    } else {
+        ""
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len(),
+        allow,
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+
+    fn request(addr: SocketAddr, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_blocking_server_serves_metrics() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        let jobs = registry
+            .write()
+            .unwrap()
+            .counter("jobs", "Jobs processed")
+            .unwrap();
+        jobs.inc_by(3);
+
+        let server = BlockingServer::start("127.0.0.1:0", registry.clone()).unwrap();
+        let response = request(server.local_addr(), "GET", "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("Connection: close\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(body.contains("# HELP jobs Jobs processed."));
+        assert!(body.contains("jobs_total 3"));
+
+        // Later updates show up in the next scrape
+        jobs.inc();
+        let response = request(server.local_addr(), "GET", "/metrics?debug=1");
+        assert!(response.contains("jobs_total 4"));
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_blocking_server_rejects_other_requests() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        let server = BlockingServer::start_with_path("127.0.0.1:0", "/stats", registry).unwrap();
+        let addr = server.local_addr();
+
+        assert!(request(addr, "GET", "/metrics").starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let response = request(addr, "POST", "/stats");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(response.contains("Allow: GET\r\n"));
+        assert!(request(addr, "GET", "/stats").starts_with("HTTP/1.1 200 OK\r\n"));
+
+        // Dropping the handle stops the server
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_blocking_server_rejects_relative_path() {
+        let registry = Arc::new(RwLock::new(
+            ObservabilityRegistry::<PrometheusBackend>::new(),
+        ));
+        let err = BlockingServer::start_with_path("127.0.0.1:0", "metrics", registry).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
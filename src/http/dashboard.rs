@@ -0,0 +1,98 @@
+//! A minimal HTML page listing the registered metrics.
+//!
+//! Meant for a quick look at a process during local development, not as a
+//! replacement for a real dashboard. The page is self-contained, with inline
+//! CSS and no scripts, and reloads itself via a `<meta http-equiv="refresh">`
+//! tag.
+
+use std::fmt::Write;
+
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use crate::core::snapshot::SnapshotValue;
+
+/// How often the dashboard page reloads itself, in seconds.
+pub const DASHBOARD_REFRESH_SECS: u64 = 5;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+                     table{border-collapse:collapse}\
+                     th,td{padding:.3em .8em;border-bottom:1px solid #ddd;text-align:left}\
+                     td.value{font-family:monospace;text-align:right}";
+
+/// Render the dashboard page for `registry`.
+///
+/// Lists every metric from [`describe`](ObservabilityRegistry::describe),
+/// with its value from [`snapshot`](ObservabilityRegistry::snapshot) where
+/// one is available.
+pub(crate) fn render_dashboard<B: MetricBackend>(registry: &ObservabilityRegistry<B>) -> String {
+    let snapshot = registry.snapshot();
+    let mut rows = String::new();
+
+    for metric in registry.describe() {
+        let value = match snapshot.get(&metric.name).map(|m| m.value) {
+            Some(SnapshotValue::Counter(value)) => value.to_string(),
+            Some(SnapshotValue::Gauge(value)) => value.to_string(),
+            Some(SnapshotValue::Float(value)) => value.to_string(),
+            Some(SnapshotValue::Histogram) | None => "&ndash;".to_string(),
+        };
+        let _ = write!(
+            rows,
+            "<tr><td>{}</td><td>{}</td><td class=\"value\">{}</td><td>{}</td></tr>",
+            escape_html(&metric.name),
+            metric.kind,
+            value,
+            escape_html(&metric.description)
+        );
+    }
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"{}\">\
+         <title>Metrics</title><style>{}</style></head>\
+         <body><h1>Metrics</h1><table>\
+         <tr><th>Name</th><th>Type</th><th>Value</th><th>Description</th></tr>\
+         {}</table></body></html>\n",
+        DASHBOARD_REFRESH_SECS, STYLE, rows
+    )
+}
+
+/// Escape text for use in HTML element content.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+
+    #[test]
+    fn test_render_dashboard_lists_metrics_and_values() {
+        let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+        registry
+            .gauge("queue_depth", "Jobs <waiting>")
+            .unwrap()
+            .set(7);
+        registry.histogram("latency_seconds", "Latency").unwrap();
+
+        let page = render_dashboard(&registry);
+
+        assert!(page.contains("<meta http-equiv=\"refresh\" content=\"5\">"));
+        assert!(page.contains(
+            "<tr><td>queue_depth</td><td>gauge</td><td class=\"value\">7</td>\
+             <td>Jobs &lt;waiting&gt;</td></tr>"
+        ));
+        assert!(page.contains("<td>latency_seconds</td><td>histogram</td>"));
+        assert!(!page.contains("<script") && !page.contains("<link"));
+    }
+}
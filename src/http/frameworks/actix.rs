@@ -0,0 +1,98 @@
+//! Actix-web integration.
+//!
+//! Mounts a metrics endpoint in an existing Actix app, answering scrapes
+//! exactly like the standalone server does.
+//!
+//! # Example
+//! ```ignore
+//! use observability_kit::http::frameworks::actix::metrics_scope;
+//!
+//! let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+//! let requests = registry.counter("requests_total", "Requests")?;
+//! let registry = Arc::new(registry);
+//!
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .service(metrics_scope("/metrics", Arc::clone(&registry)))
+//!         .service(my_api())
+//! })
+//! .bind(("0.0.0.0", 8080))?
+//! .run()
+//! .await?;
+//! ```
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Scope};
+use std::sync::Arc;
+
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use crate::core::renderer::MetricsRenderer;
+use crate::http::scrape::scrape;
+
+/// A scope serving `registry` on `GET path`.
+///
+/// Metrics are registered before the registry is shared; their handles keep
+/// working afterwards, and each scrape renders the current values.
+pub fn metrics_scope<B>(path: &str, registry: Arc<ObservabilityRegistry<B>>) -> Scope
+where
+    B: MetricBackend + 'static,
+    ObservabilityRegistry<B>: Send + Sync,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    web::scope(path)
+        .app_data(web::Data::from(registry))
+        .route("", web::get().to(metrics_handler::<B>))
+}
+
+async fn metrics_handler<B>(registry: web::Data<ObservabilityRegistry<B>>) -> HttpResponse
+where
+    B: MetricBackend + 'static,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    let response = scrape(&registry);
+
+    HttpResponse::build(
+        StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    )
+    .content_type(response.content_type)
+    .body(response.body)
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn test_metrics_scope_serves_exposition() {
+        let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+        let requests = registry.counter("requests", "Requests").unwrap();
+        let registry = Arc::new(registry);
+
+        let app = test::init_service(App::new().service(metrics_scope("/metrics", registry))).await;
+
+        requests.inc_by(3);
+        let response =
+            test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response
+            .headers()
+            .get(actix_web::http::header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(content_type.starts_with("text/plain"), "{content_type}");
+
+        let body = test::read_body(response).await;
+        let text = std::str::from_utf8(&body).unwrap();
+        assert!(text.contains("# HELP requests Requests."));
+        assert!(text.contains("requests_total 3"));
+
+        let missing =
+            test::call_service(&app, test::TestRequest::get().uri("/other").to_request()).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,176 @@
+//! Axum integration.
+//!
+//! Counts the requests an existing Axum app serves, as
+//! `http_requests_total{method, path, status}`. Paths are taken from the
+//! matched route, so `/users/{id}` stays one series; requests no route
+//! matched, such as 404s, share the path [`UNMATCHED_PATH`].
+//!
+//! # Example
+//! ```ignore
+//! use axum::{middleware, routing::get, Router};
+//! use observability_kit::http::frameworks::axum::{track_requests, RequestMetrics, RequestMetricsConfig};
+//!
+//! let mut registry = PrometheusRegistry::new();
+//! let metrics = RequestMetrics::register(&mut registry, RequestMetricsConfig::default())?;
+//!
+//! let app = Router::new()
+//!     .route("/users/{id}", get(get_user))
+//!     .layer(middleware::from_fn_with_state(metrics, track_requests));
+//! ```
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+
+use crate::backends::prometheus::{
+    labeled_counter, DynamicLabels, LabeledCounter, PrometheusRegistry,
+};
+use crate::core::deserialise::DeserializeError;
+
+/// Name the request counter is registered under; rendered with a `_total` suffix.
+pub const HTTP_REQUESTS_METRIC: &str = "http_requests";
+
+/// `path` label of requests that matched no route, so unknown URLs can't
+/// add series.
+pub const UNMATCHED_PATH: &str = "unmatched";
+
+/// Options for [`RequestMetrics`].
+#[derive(Debug, Clone)]
+pub struct RequestMetricsConfig {
+    /// Request paths that are not counted at all, matched exactly
+    /// (default: `/metrics`, `/healthz`, `/readyz`)
+    pub exclude_paths: Vec<String>,
+}
+
+impl Default for RequestMetricsConfig {
+    fn default() -> Self {
+        Self {
+            exclude_paths: vec!["/metrics".into(), "/healthz".into(), "/readyz".into()],
+        }
+    }
+}
+
+/// The request counter shared by every [`track_requests`] call.
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    requests: LabeledCounter<DynamicLabels>,
+    exclude_paths: Arc<[String]>,
+}
+
+impl RequestMetrics {
+    /// Register the request counter in `registry`.
+    ///
+    /// Fails if the registry is frozen or [`HTTP_REQUESTS_METRIC`] is taken.
+    pub fn register(
+        registry: &mut PrometheusRegistry,
+        config: RequestMetricsConfig,
+    ) -> Result<Self, DeserializeError> {
+        let requests: LabeledCounter<DynamicLabels> = labeled_counter();
+        let family = requests.clone();
+        registry.register_external(HTTP_REQUESTS_METRIC, move |inner| {
+            inner.register(HTTP_REQUESTS_METRIC, "HTTP requests served", family.clone())
+        })?;
+        Ok(Self {
+            requests,
+            exclude_paths: config.exclude_paths.into(),
+        })
+    }
+
+    /// Returns true if requests to `path` are not counted.
+    pub fn is_excluded(&self, path: &str) -> bool {
+        self.exclude_paths.iter().any(|excluded| excluded == path)
+    }
+}
+
+/// Middleware counting each request once its response is ready.
+///
+/// Install with `axum::middleware::from_fn_with_state`.
+pub async fn track_requests(
+    State(metrics): State<RequestMetrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if metrics.is_excluded(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let method = request.method().to_string();
+    let path = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str().to_string(),
+        None => UNMATCHED_PATH.to_string(),
+    };
+    let response = next.run(request).await;
+
+    metrics
+        .requests
+        .get_or_create(&vec![
+            ("method".to_string(), method),
+            ("path".to_string(), path),
+            ("status".to_string(), response.status().as_u16().to_string()),
+        ])
+        .inc();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+
+    #[tokio::test]
+    async fn test_track_requests_skips_excluded_paths() {
+        let mut registry = PrometheusRegistry::new();
+        let metrics =
+            RequestMetrics::register(&mut registry, RequestMetricsConfig::default()).unwrap();
+        let registry = Arc::new(registry);
+
+        let app = Router::new()
+            .route("/users/{id}", get(|| async { "user" }))
+            .route(
+                "/metrics",
+                get({
+                    let registry = Arc::clone(&registry);
+                    move || async move { registry.render().unwrap().as_str().unwrap().to_string() }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(metrics, track_requests));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        reqwest::get(format!("{}/users/1", base)).await.unwrap();
+        reqwest::get(format!("{}/users/2", base)).await.unwrap();
+        let missing = reqwest::get(format!("{}/no/such/page", base))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        let mut body = String::new();
+        for _ in 0..3 {
+            body = reqwest::get(format!("{}/metrics", base))
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+        }
+
+        assert!(body
+            .contains("http_requests_total{method=\"GET\",path=\"/users/{id}\",status=\"200\"} 2"));
+        assert!(!body.contains("path=\"/metrics\""));
+        assert!(body
+            .contains("http_requests_total{method=\"GET\",path=\"unmatched\",status=\"404\"} 1"));
+        assert!(!body.contains("/no/such/page"));
+    }
+
+    #[test]
+    fn test_register_refuses_a_taken_name() {
+        let mut registry = PrometheusRegistry::new();
+        registry.gauge(HTTP_REQUESTS_METRIC, "Taken").unwrap();
+
+        let err =
+            RequestMetrics::register(&mut registry, RequestMetricsConfig::default()).unwrap_err();
+        assert!(matches!(err, DeserializeError::DuplicateMetricName(_)));
+    }
+}
@@ -1 +1,7 @@
-pub mod axum;
\ No newline at end of file
+//! Integrations that mount the metrics endpoint in an existing web app.
+
+#[cfg(all(feature = "axum-integration", feature = "prometheus"))]
+pub mod axum;
+
+#[cfg(feature = "actix")]
+pub mod actix;
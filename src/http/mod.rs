@@ -2,13 +2,43 @@
 //!
 //! This module contains:
 //! - Standalone HTTP server (feature: `standalone`)
+//! - Blocking std-only metrics server (feature: `blocking-server`)
+//! - Server-Sent Events stream of the metrics (feature: `sse`)
+//! - HTML dashboard listing the metrics (feature: `dashboard`)
 //! - Health and readiness endpoints
 //! - Metrics endpoint handlers
+//! - Config reloads for the served registry (features: `json-config` / `yaml-config`)
+//! - Framework integrations (features: `actix`, `axum-integration`)
 
 #[cfg(feature = "standalone")]
 pub mod standalone;
 
+#[cfg(feature = "blocking-server")]
+pub mod blocking;
+
+#[cfg(feature = "sse")]
+pub mod sse;
+
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+
+pub mod frameworks;
 pub mod health;
+#[cfg(any(feature = "standalone", feature = "blocking-server", feature = "actix"))]
+mod scrape;
+
+#[cfg(all(
+    feature = "standalone",
+    any(feature = "json-config", feature = "yaml-config")
+))]
+pub mod reload;
 
 #[cfg(feature = "standalone")]
 pub use standalone::*;
+
+#[cfg(all(
+    unix,
+    feature = "standalone",
+    any(feature = "json-config", feature = "yaml-config")
+))]
+pub use reload::reload_on_sighup;
@@ -0,0 +1,438 @@
+//! Reloading the served registry from a config file.
+//!
+//! A reload re-reads the file and replaces the metrics earlier reloads
+//! registered from config with the ones it defines, in place. Metrics
+//! registered in code are left alone and keep their values. If any step
+//! fails the served registry is left untouched, so a bad edit never takes
+//! metrics offline.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use observability_kit::http::reload::reload_on_sighup;
+//!
+//! let server = StandaloneServer::<PrometheusBackend>::builder().build();
+//! server.handle().reload_from_file("metrics.yaml".as_ref(), None).await?;
+//!
+//! // `kill -HUP <pid>` now re-reads metrics.yaml
+//! reload_on_sighup(server.handle(), "metrics.yaml", None)?;
+//! server.run().await?;
+//! ```
+//!
+//! Every reload attempt is counted in the handle's [`ReloadMetrics`]. Once
+//! registered on the served registry they are exposed as
+//! `config_reload_total{result="success|failure"}` and
+//! `config_last_reload_timestamp_seconds`, so dashboards can alert on failed
+//! reloads.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(unix)]
+use std::{future::Future, path::PathBuf};
+
+use crate::config::registry::ConfigOwned;
+use crate::config::{load_file_async, RegistryConfig};
+use crate::core::collector::{Collector, SeriesInfo};
+use crate::core::deserialise::DeserializeError;
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+
+use super::standalone::RegistryHandle;
+
+/// Name of the counter family tracking reload attempts.
+const RELOADS_METRIC: &str = "config_reload";
+/// Name of the gauge holding the time of the last successful reload.
+const LAST_RELOAD_METRIC: &str = "config_last_reload_timestamp_seconds";
+
+impl<B: MetricBackend> RegistryHandle<B> {
+    /// Load `path` and reload the metrics it defines into the served registry.
+    ///
+    /// Only metrics registered by earlier reloads through this handle or its
+    /// clones are replaced; a name taken by any other metric fails the reload.
+    /// On error the served registry is kept as it was. Either way the attempt
+    /// is recorded in [`reload_metrics`](Self::reload_metrics).
+    pub async fn reload_from_file(
+        &self,
+        path: &Path,
+        extra_base: Option<&Path>,
+    ) -> Result<(), DeserializeError> {
+        let result = match load_file_async(path, extra_base).await {
+            Ok(config) => self.reload(&config),
+            Err(e) => Err(e),
+        };
+        let metrics = self.reload_metrics();
+        match result {
+            Ok(()) => metrics.record(ReloadResult::Success),
+            Err(_) => metrics.record(ReloadResult::Failure),
+        }
+        result
+    }
+
+    /// Replace the metrics loaded from config with those in `config`.
+    fn reload(&self, config: &RegistryConfig) -> Result<(), DeserializeError> {
+        let mut owned = self.reload.lock_owned();
+        let registry = self.registry();
+        let mut registry = registry.write().unwrap_or_else(PoisonError::into_inner);
+        owned.reload(&mut registry, config, None)
+    }
+}
+
+/// Reload state shared by every [`RegistryHandle`] of a server.
+#[derive(Clone, Default)]
+pub(crate) struct ConfigReload {
+    metrics: ReloadMetrics,
+    /// Metrics the served registry got from config through reloads
+    owned: Arc<Mutex<ConfigOwned>>,
+}
+
+impl ConfigReload {
+    pub(crate) fn metrics(&self) -> &ReloadMetrics {
+        &self.metrics
+    }
+
+    /// Run `swap`, after which no served metric has come from config.
+    pub(crate) fn swap<T>(&self, swap: impl FnOnce() -> T) -> T {
+        let mut owned = self.lock_owned();
+        let swapped = swap();
+        *owned = ConfigOwned::default();
+        swapped
+    }
+
+    fn lock_owned(&self) -> MutexGuard<'_, ConfigOwned> {
+        self.owned.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Reload `path` into the served registry every time the process receives SIGHUP.
+///
+/// The signal handler is installed before this returns, so a SIGHUP sent
+/// afterwards can no longer terminate the process. Failed reloads are logged
+/// and leave the served registry intact. Must be called inside a Tokio runtime.
+#[cfg(unix)]
+pub fn reload_on_sighup<B: MetricBackend>(
+    handle: RegistryHandle<B>,
+    path: impl Into<PathBuf>,
+    extra_base: Option<PathBuf>,
+) -> std::io::Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let hangups = signal(SignalKind::hangup())?;
+    Ok(reload_on(hangups, handle, path.into(), extra_base))
+}
+
+/// Something that asks for reloads, such as a stream of signals.
+#[cfg(unix)]
+trait ReloadTrigger: Send + 'static {
+    /// Wait for the next request, or `None` once there are no more.
+    fn recv(&mut self) -> impl Future<Output = Option<()>> + Send;
+}
+
+#[cfg(unix)]
+impl ReloadTrigger for tokio::signal::unix::Signal {
+    fn recv(&mut self) -> impl Future<Output = Option<()>> + Send {
+        tokio::signal::unix::Signal::recv(self)
+    }
+}
+
+#[cfg(all(unix, test))]
+impl ReloadTrigger for tokio::sync::mpsc::Receiver<()> {
+    fn recv(&mut self) -> impl Future<Output = Option<()>> + Send {
+        tokio::sync::mpsc::Receiver::recv(self)
+    }
+}
+
+/// Reload `path` into the served registry on every request from `trigger`,
+/// logging the outcome.
+#[cfg(unix)]
+fn reload_on<B: MetricBackend>(
+    mut trigger: impl ReloadTrigger,
+    handle: RegistryHandle<B>,
+    path: PathBuf,
+    extra_base: Option<PathBuf>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        while trigger.recv().await.is_some() {
+            match handle.reload_from_file(&path, extra_base.as_deref()).await {
+                Ok(()) => log::info!("Reloaded metrics config from {}", path.display()),
+                Err(e) => log::error!(
+                    "Failed to reload metrics config from {}, keeping the current registry: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    })
+}
+
+/// Outcome of a reload attempt, used as the `result` label.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum ReloadResult {
+    Success,
+    Failure,
+}
+
+impl ReloadResult {
+    /// The label value for this outcome.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReloadResult::Success => "success",
+            ReloadResult::Failure => "failure",
+        }
+    }
+}
+
+/// Metrics describing config reloads of the served registry.
+///
+/// Each [`RegistryHandle`] of a server shares one set, updated by every
+/// reload whichever way it was triggered. The values live outside any one
+/// registry, so they carry over when the served registry is swapped.
+///
+/// # Example
+/// ```ignore
+/// let handle = server.handle();
+/// handle.reload_metrics().register(&mut *server.registry().write().unwrap())?;
+///
+/// handle.reload_from_file(path, None).await?;
+/// assert_eq!(handle.reload_metrics().reloads(ReloadResult::Success), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ReloadMetrics {
+    state: Arc<ReloadState>,
+}
+
+#[derive(Debug, Default)]
+struct ReloadState {
+    successes: AtomicU64,
+    failures: AtomicU64,
+    /// `f64` bits of the Unix time of the last successful reload
+    last_success: AtomicU64,
+}
+
+impl ReloadMetrics {
+    /// Expose the reload metrics on `registry`.
+    ///
+    /// Fails if the registry is frozen or one of the names is taken.
+    pub fn register<B: MetricBackend>(
+        &self,
+        registry: &mut ObservabilityRegistry<B>,
+    ) -> Result<(), DeserializeError> {
+        if registry.is_frozen() {
+            return Err(DeserializeError::RegistryFrozen(RELOADS_METRIC.to_string()));
+        }
+        for name in [RELOADS_METRIC, LAST_RELOAD_METRIC] {
+            if registry.contains(name) {
+                return Err(DeserializeError::DuplicateMetricName(name.to_string()));
+            }
+        }
+        registry.register_collector(self.clone());
+        Ok(())
+    }
+
+    /// Number of reload attempts with the given outcome.
+    pub fn reloads(&self, result: ReloadResult) -> u64 {
+        match result {
+            ReloadResult::Success => self.state.successes.load(Ordering::Relaxed),
+            ReloadResult::Failure => self.state.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Unix time in seconds of the last successful reload, or 0 if none.
+    pub fn last_success_timestamp(&self) -> f64 {
+        f64::from_bits(self.state.last_success.load(Ordering::Relaxed))
+    }
+
+    fn record(&self, result: ReloadResult) {
+        match result {
+            ReloadResult::Success => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+                self.state
+                    .last_success
+                    .store(now.to_bits(), Ordering::Relaxed);
+                self.state.successes.fetch_add(1, Ordering::Relaxed);
+            }
+            ReloadResult::Failure => {
+                self.state.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Collector for ReloadMetrics {
+    fn collect(&self) -> Vec<SeriesInfo> {
+        let reloads = |result: ReloadResult| {
+            SeriesInfo::counter(
+                RELOADS_METRIC,
+                "Config reload attempts by result",
+                self.reloads(result) as f64,
+            )
+            .with_label("result", result.as_str())
+        };
+        vec![
+            reloads(ReloadResult::Success),
+            reloads(ReloadResult::Failure),
+            SeriesInfo::gauge(
+                LAST_RELOAD_METRIC,
+                "Unix time of the last successful config reload",
+                self.last_success_timestamp(),
+            ),
+        ]
+    }
+}
+
+#[cfg(all(test, feature = "prometheus", feature = "json-config"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+    use crate::http::standalone::StandaloneServer;
+
+    /// Create a fresh directory under the system temp dir for one test.
+    fn scratch_dir(test_name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "observability-kit-{}-{}",
+            test_name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn counter_json(name: &str) -> String {
+        format!(
+            r#"[{{ "metric_type": "Counter", "name": "{}", "description": "Requests" }}]"#,
+            name
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_file_keeps_registry_on_failure() {
+        let dir = scratch_dir("reload-from-file");
+        let path = dir.join("metrics.json");
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let handle = server.handle();
+
+        std::fs::write(&path, counter_json("first_total")).unwrap();
+        handle.reload_from_file(&path, Some(&dir)).await.unwrap();
+        assert!(server.registry().read().unwrap().contains("first_total"));
+
+        std::fs::write(&path, "not json").unwrap();
+        let result = handle.reload_from_file(&path, Some(&dir)).await;
+        assert!(matches!(result, Err(DeserializeError::Parse(_))));
+        assert!(server.registry().read().unwrap().contains("first_total"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_records_failures() {
+        let dir = scratch_dir("reload-tracked");
+        let path = dir.join("metrics.json");
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let handle = server.handle();
+        let metrics = handle.reload_metrics();
+        metrics
+            .register(&mut *server.registry().write().unwrap())
+            .unwrap();
+
+        std::fs::write(&path, counter_json("requests_total")).unwrap();
+        handle.reload_from_file(&path, Some(&dir)).await.unwrap();
+        let last_success = metrics.last_success_timestamp();
+        assert!(last_success > 0.0);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(handle.reload_from_file(&path, Some(&dir)).await.is_err());
+
+        assert_eq!(metrics.reloads(ReloadResult::Success), 1);
+        assert_eq!(metrics.reloads(ReloadResult::Failure), 1);
+        assert_eq!(metrics.last_success_timestamp(), last_success);
+
+        let registry = server.registry();
+        let registry = registry.read().unwrap();
+        let output = registry.render().unwrap();
+        let text = output.as_str().unwrap();
+        assert!(text.contains("config_reload_total{result=\"success\"} 1"));
+        assert!(text.contains("config_reload_total{result=\"failure\"} 1"));
+        assert!(text.contains("config_last_reload_timestamp_seconds"));
+        assert!(text.contains("requests_total"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_metrics_refuse_taken_names() {
+        let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+        registry.gauge(RELOADS_METRIC, "Taken").unwrap();
+
+        let err = ReloadMetrics::default()
+            .register(&mut registry)
+            .unwrap_err();
+        assert!(matches!(err, DeserializeError::DuplicateMetricName(_)));
+    }
+
+    #[tokio::test]
+    async fn test_reload_replaces_config_metrics_in_place() {
+        let dir = scratch_dir("reload-in-place");
+        let path = dir.join("metrics.json");
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let handle = server.handle();
+        let registry = server.registry();
+        let uptime = registry
+            .write()
+            .unwrap()
+            .gauge("uptime_seconds", "Uptime")
+            .unwrap();
+        uptime.set(30);
+
+        std::fs::write(&path, counter_json("first_total")).unwrap();
+        handle.reload_from_file(&path, Some(&dir)).await.unwrap();
+        std::fs::write(&path, counter_json("second_total")).unwrap();
+        handle.reload_from_file(&path, Some(&dir)).await.unwrap();
+
+        // Same registry, metrics registered in code kept with their values
+        assert!(Arc::ptr_eq(&registry, &server.registry()));
+        assert!(!uptime.is_orphaned());
+        {
+            let registry = registry.read().unwrap();
+            assert!(!registry.contains("first_total"));
+            assert!(registry.contains("second_total"));
+            assert_eq!(
+                registry.find_gauge("uptime_seconds").unwrap().get_gauge(),
+                30
+            );
+        }
+
+        // Names held by metrics registered in code can't be taken
+        std::fs::write(&path, counter_json("uptime_seconds")).unwrap();
+        let result = handle.reload_from_file(&path, Some(&dir)).await;
+        assert!(matches!(
+            result,
+            Err(DeserializeError::DuplicateMetricName(_))
+        ));
+        assert!(server.registry().read().unwrap().contains("second_total"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reload_on_trigger() {
+        let dir = scratch_dir("reload-trigger");
+        let path = dir.join("metrics.json");
+        std::fs::write(&path, counter_json("before_total")).unwrap();
+
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let (trigger, requests) = tokio::sync::mpsc::channel(1);
+        let task = reload_on(requests, server.handle(), path.clone(), Some(dir.clone()));
+
+        std::fs::write(&path, counter_json("after_total")).unwrap();
+        trigger.send(()).await.unwrap();
+        drop(trigger);
+        task.await.unwrap();
+
+        assert!(server.registry().read().unwrap().contains("after_total"));
+        assert_eq!(
+            server
+                .handle()
+                .reload_metrics()
+                .reloads(ReloadResult::Success),
+            1
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Framework-independent handling of scrape requests.
+//!
+//! Every HTTP integration answers a scrape the same way, so the status,
+//! content type and body are worked out here and each framework only
+//! converts the result into its own response type.
+
+use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use crate::core::renderer::MetricsRenderer;
+
+/// Content type of error responses.
+const ERROR_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// The response to a scrape, ready to be converted by an HTTP framework.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScrapeResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+/// Render `registry` into a scrape response.
+///
+/// Render failures become a `500` with the error as a plain text body.
+pub(crate) fn scrape<B>(registry: &ObservabilityRegistry<B>) -> ScrapeResponse
+where
+    B: MetricBackend,
+    <B::Registry as MetricsRenderer>::Error: std::fmt::Display,
+{
+    match registry.render() {
+        Ok(rendered) => ScrapeResponse {
+            status: 200,
+            content_type: rendered.content_type.clone(),
+            body: rendered.into_bytes(),
+        },
+        Err(e) => ScrapeResponse {
+            status: 500,
+            content_type: ERROR_CONTENT_TYPE.to_string(),
+            body: format!("Failed to render metrics: {}", e).into_bytes(),
+        },
+    }
+}
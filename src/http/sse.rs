@@ -0,0 +1,123 @@
+//! Streaming the registry as Server-Sent Events.
+//!
+//! One background task renders the registry every interval and hands the
+//! snapshot to every connected client, so the render cost doesn't grow with
+//! the number of dashboards watching. While no client is connected the task
+//! stops rendering and waits for the next connection.
+
+use axum::response::sse::Event;
+use futures_util::stream::{self, Stream};
+use std::convert::Infallible;
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+use super::standalone::ServedRegistry;
+use crate::core::registry::MetricBackend;
+use crate::core::renderer::MetricsRenderer;
+
+/// Event name of the SSE events carrying a snapshot.
+pub const SNAPSHOT_EVENT: &str = "metrics";
+
+/// The latest rendered snapshot, shared with every connected client.
+pub(crate) struct SnapshotFeed {
+    snapshots: watch::Sender<Arc<str>>,
+    client_connected: Notify,
+}
+
+impl SnapshotFeed {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            snapshots: watch::Sender::new(Arc::from("")),
+            client_connected: Notify::new(),
+        })
+    }
+
+    /// Render the served registry every `interval` for as long as clients are
+    /// connected.
+    pub(crate) async fn run<B>(self: Arc<Self>, registry: ServedRegistry<B>, interval: Duration)
+    where
+        B: MetricBackend,
+        B::Registry: MetricsRenderer<Error = std::fmt::Error>,
+    {
+        loop {
+            if self.snapshots.receiver_count() == 0 {
+                self.client_connected.notified().await;
+                continue;
+            }
+
+            // A failed render skips one event rather than ending every stream
+            let rendered = registry
+                .load()
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .render_peek();
+            if let Ok(rendered) = rendered {
+                if let Ok(text) = String::from_utf8(rendered.into_bytes()) {
+                    self.snapshots.send_replace(Arc::from(text));
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Subscribe a client, waking the renderer if it was idle.
+    ///
+    /// The stream yields one event per snapshot rendered after subscribing.
+    pub(crate) fn subscribe(&self) -> impl Stream<Item = Result<Event, Infallible>> {
+        let receiver = self.snapshots.subscribe();
+        self.client_connected.notify_one();
+
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver.changed().await.ok()?;
+            let snapshot = Arc::clone(&receiver.borrow_and_update());
+            let event = Event::default().event(SNAPSHOT_EVENT).data(&*snapshot);
+            Some((Ok(event), receiver))
+        })
+    }
+}
+
+/// Aborts the renderer task when the server stops.
+pub(crate) struct AbortOnDrop(pub tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+    use super::*;
+    use crate::backends::prometheus::PrometheusBackend;
+    use crate::core::registry::ObservabilityRegistry;
+    use arc_swap::ArcSwap;
+    use futures_util::StreamExt;
+    use std::sync::RwLock;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_feed_only_renders_while_subscribed() {
+        let registry = Arc::new(ArcSwap::from_pointee(RwLock::new(ObservabilityRegistry::<
+            PrometheusBackend,
+        >::new())));
+        registry
+            .load()
+            .write()
+            .unwrap()
+            .counter("ticks", "Ticks")
+            .unwrap();
+
+        let feed = SnapshotFeed::new();
+        let _renderer = AbortOnDrop(tokio::spawn(
+            Arc::clone(&feed).run(registry, Duration::from_secs(1)),
+        ));
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(feed.snapshots.borrow().is_empty());
+
+        let mut events = std::pin::pin!(feed.subscribe());
+        assert!(events.next().await.is_some());
+        assert!(feed.snapshots.borrow().contains("ticks_total 0"));
+        assert!(events.next().await.is_some());
+    }
+}
@@ -16,7 +16,11 @@
 //!         .build();
 //!
 //!     // Create metrics
-//!     let requests = server.registry().counter("http_requests_total", "Total requests")?;
+//!     let requests = server
+//!         .registry()
+//!         .write()
+//!         .unwrap()
+//!         .counter("http_requests_total", "Total requests")?;
 //!     requests.inc();
 //!
 //!     // Run the server
@@ -25,6 +29,7 @@
 //! }
 //! ```
 
+use arc_swap::ArcSwap;
 use axum::{
     extract::State,
     http::{header, StatusCode},
@@ -32,18 +37,40 @@ use axum::{
     routing::get,
     Router,
 };
-use std::sync::Arc;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(feature = "sse")]
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tokio::sync::RwLock;
+use tokio::sync::Semaphore;
 
-use crate::core::registry::{MetricBackend, ObservabilityRegistry};
+use crate::core::registry::{MetricBackend, ObservabilityRegistry, SharedRegistry};
 use crate::core::renderer::MetricsRenderer;
 
 use super::health::{default_health_check, default_readiness_check};
+#[cfg(any(feature = "json-config", feature = "yaml-config"))]
+use super::reload::{ConfigReload, ReloadMetrics};
+use super::scrape::scrape;
+#[cfg(feature = "sse")]
+use super::sse::{AbortOnDrop, SnapshotFeed};
+
+/// Where the standalone server accepts connections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ListenOn {
+    /// A TCP socket on [`ServerConfig::host`] and [`ServerConfig::port`]
+    #[default]
+    Tcp,
+    /// A Unix domain socket at the given path, removed again on shutdown
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
 
 /// Configuration for the standalone server.
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
+    /// Where to accept connections (default: TCP on `host`:`port`)
+    pub listen_on: ListenOn,
     /// The port to bind to (default: 9090)
     pub port: u16,
     /// The host to bind to (default: "0.0.0.0")
@@ -54,31 +81,116 @@ pub struct ServerConfig {
     pub health_path: String,
     /// Path for the readiness endpoint (default: "/ready")
     pub ready_path: String,
+    /// Scrapes rendered at once before further ones get a 503 (default: unlimited)
+    pub max_concurrent_scrapes: Option<usize>,
+    /// Serve the metrics endpoint; turn off for a health-only server (default: true)
+    pub serve_metrics: bool,
+    /// Path for the Server-Sent Events stream (default: "/metrics/stream")
+    #[cfg(feature = "sse")]
+    pub metrics_stream_path: String,
+    /// How often the stream pushes a snapshot; `None` leaves the stream
+    /// unserved (default: None)
+    #[cfg(feature = "sse")]
+    pub metrics_stream_interval: Option<Duration>,
+    /// Path for the HTML dashboard (default: "/")
+    #[cfg(feature = "dashboard")]
+    pub dashboard_path: String,
+}
+
+impl ServerConfig {
+    /// Check the config can be served, before anything is bound.
+    ///
+    /// Endpoint paths must start with `/` and be distinct, and a scrape
+    /// limit must allow at least one scrape.
+    pub fn validate(&self) -> Result<(), ServerError> {
+        let mut paths = vec![
+            ("health_path", &self.health_path),
+            ("ready_path", &self.ready_path),
+        ];
+        if self.serve_metrics {
+            paths.push(("metrics_path", &self.metrics_path));
+        }
+        #[cfg(feature = "sse")]
+        if self.serves_metrics_stream() {
+            paths.push(("metrics_stream_path", &self.metrics_stream_path));
+        }
+        #[cfg(feature = "dashboard")]
+        if self.serve_metrics {
+            paths.push(("dashboard_path", &self.dashboard_path));
+        }
+
+        for (index, (field, path)) in paths.iter().enumerate() {
+            if !path.starts_with('/') {
+                return Err(ServerError::InvalidConfig(format!(
+                    "{} '{}' must start with '/'",
+                    field, path
+                )));
+            }
+            if let Some((other, _)) = paths[..index].iter().find(|(_, p)| p == path) {
+                return Err(ServerError::InvalidConfig(format!(
+                    "{} and {} are both '{}'",
+                    other, field, path
+                )));
+            }
+        }
+
+        if self.max_concurrent_scrapes == Some(0) {
+            return Err(ServerError::InvalidConfig(
+                "max_concurrent_scrapes must be at least 1".to_string(),
+            ));
+        }
+        #[cfg(feature = "sse")]
+        if self.metrics_stream_interval == Some(Duration::ZERO) {
+            return Err(ServerError::InvalidConfig(
+                "metrics_stream_interval must be non-zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns true if the metrics stream is served, which needs the
+    /// metrics endpoint to be served too.
+    #[cfg(feature = "sse")]
+    fn serves_metrics_stream(&self) -> bool {
+        self.serve_metrics && self.metrics_stream_interval.is_some()
+    }
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
+            listen_on: ListenOn::Tcp,
             port: 9090,
             host: "0.0.0.0".to_string(),
             metrics_path: "/metrics".to_string(),
             health_path: "/health".to_string(),
             ready_path: "/ready".to_string(),
+            max_concurrent_scrapes: None,
+            serve_metrics: true,
+            #[cfg(feature = "sse")]
+            metrics_stream_path: "/metrics/stream".to_string(),
+            #[cfg(feature = "sse")]
+            metrics_stream_interval: None,
+            #[cfg(feature = "dashboard")]
+            dashboard_path: "/".to_string(),
         }
     }
 }
 
+/// The registry a server currently serves, replaced as a whole by a swap.
+pub(crate) type ServedRegistry<B> = Arc<ArcSwap<RwLock<ObservabilityRegistry<B>>>>;
+
 /// Builder for creating a standalone server.
 pub struct StandaloneServerBuilder<B: MetricBackend> {
     config: ServerConfig,
-    _marker: std::marker::PhantomData<B>,
+    registry: Option<SharedRegistry<B>>,
 }
 
 impl<B: MetricBackend> Default for StandaloneServerBuilder<B> {
     fn default() -> Self {
         Self {
             config: ServerConfig::default(),
-            _marker: std::marker::PhantomData,
+            registry: None,
         }
     }
 }
@@ -101,6 +213,15 @@ impl<B: MetricBackend> StandaloneServerBuilder<B> {
         self
     }
 
+    /// Serve over a Unix domain socket at `path` instead of TCP.
+    ///
+    /// A stale socket file left at `path` by a previous run is replaced.
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.listen_on = ListenOn::Unix(path.into());
+        self
+    }
+
     /// Set the metrics endpoint path.
     pub fn metrics_path(mut self, path: impl Into<String>) -> Self {
         self.config.metrics_path = path.into();
@@ -119,28 +240,159 @@ impl<B: MetricBackend> StandaloneServerBuilder<B> {
         self
     }
 
+    /// Limit how many scrapes are rendered at once.
+    ///
+    /// Scrapes beyond the limit are answered with `503 Service Unavailable`
+    /// straight away instead of queueing behind a slow render.
+    pub fn max_concurrent_scrapes(mut self, limit: usize) -> Self {
+        self.config.max_concurrent_scrapes = Some(limit);
+        self
+    }
+
+    /// Serve or omit the metrics endpoint.
+    ///
+    /// Turning it off gives a server with only the health and readiness
+    /// endpoints, e.g. for a public port next to an internal metrics port.
+    pub fn serve_metrics(mut self, enabled: bool) -> Self {
+        self.config.serve_metrics = enabled;
+        self
+    }
+
+    /// Stream the metrics as Server-Sent Events, pushing a snapshot every `interval`.
+    ///
+    /// Each event is named [`SNAPSHOT_EVENT`](super::sse::SNAPSHOT_EVENT)
+    /// and carries the full exposition. Rendering pauses while no client is
+    /// connected.
+    #[cfg(feature = "sse")]
+    pub fn metrics_stream(mut self, interval: Duration) -> Self {
+        self.config.metrics_stream_interval = Some(interval);
+        self
+    }
+
+    /// Set the metrics stream path.
+    #[cfg(feature = "sse")]
+    pub fn metrics_stream_path(mut self, path: impl Into<String>) -> Self {
+        self.config.metrics_stream_path = path.into();
+        self
+    }
+
+    /// Set the HTML dashboard path.
+    ///
+    /// The dashboard is served alongside the metrics endpoint, and left out
+    /// when that is turned off.
+    #[cfg(feature = "dashboard")]
+    pub fn dashboard_path(mut self, path: impl Into<String>) -> Self {
+        self.config.dashboard_path = path.into();
+        self
+    }
+
+    /// Serve an existing registry instead of creating a new one.
+    ///
+    /// Servers built with the same registry serve the same metrics, so one
+    /// process can expose them on several ports. A [swap](RegistryHandle::swap)
+    /// only changes what the server it was made through serves.
+    pub fn registry(mut self, registry: SharedRegistry<B>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
     /// Build the standalone server.
     pub fn build(self) -> StandaloneServer<B> {
         StandaloneServer {
             config: self.config,
-            registry: Arc::new(RwLock::new(ObservabilityRegistry::<B>::new())),
+            registry: Arc::new(ArcSwap::new(self.registry.unwrap_or_else(|| {
+                Arc::new(RwLock::new(ObservabilityRegistry::<B>::new()))
+            }))),
+            #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+            reload: ConfigReload::default(),
         }
     }
 }
 
 /// Shared state for the HTTP handlers.
 struct AppState<B: MetricBackend> {
-    registry: Arc<RwLock<ObservabilityRegistry<B>>>,
+    registry: ServedRegistry<B>,
+    /// Permits for in-flight scrapes, if their number is limited
+    scrape_permits: Option<Arc<Semaphore>>,
 }
 
 impl<B: MetricBackend> Clone for AppState<B> {
     fn clone(&self) -> Self {
         Self {
             registry: Arc::clone(&self.registry),
+            scrape_permits: self.scrape_permits.clone(),
         }
     }
 }
 
+/// A cloneable handle to the registry served by a [`StandaloneServer`].
+///
+/// Each scrape loads the registry served at that moment, and
+/// [`swap`](Self::swap) replaces it atomically: the swap doesn't wait for
+/// in-flight scrapes, which finish rendering the old registry, and scrapes
+/// never wait for a swap.
+///
+/// # Example
+/// ```ignore
+/// let handle = server.handle();
+///
+/// // Later, e.g. to serve a registry built from scratch
+/// let fresh = ConfiguredRegistry::<PrometheusBackend>::from_config(&config)?.into_registry();
+/// handle.swap(fresh);
+/// ```
+pub struct RegistryHandle<B: MetricBackend> {
+    registry: ServedRegistry<B>,
+    /// Shared by every handle of the server
+    #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+    pub(super) reload: ConfigReload,
+}
+
+impl<B: MetricBackend> Clone for RegistryHandle<B> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: Arc::clone(&self.registry),
+            #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+            reload: self.reload.clone(),
+        }
+    }
+}
+
+impl<B: MetricBackend> RegistryHandle<B> {
+    /// Replace the served registry, returning the previous one.
+    ///
+    /// The previous registry is [retired](ObservabilityRegistry::retire), so
+    /// metric handles taken from it drop their updates from then on. Metrics
+    /// earlier reloads loaded from config stay with it.
+    pub fn swap(&self, registry: ObservabilityRegistry<B>) -> SharedRegistry<B> {
+        let registry = Arc::new(RwLock::new(registry));
+        #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+        let previous = self.reload.swap(|| self.registry.swap(registry));
+        #[cfg(not(any(feature = "json-config", feature = "yaml-config")))]
+        let previous = self.registry.swap(registry);
+        previous
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retire();
+        previous
+    }
+
+    /// Get the registry served right now.
+    pub fn registry(&self) -> SharedRegistry<B> {
+        self.registry.load_full()
+    }
+
+    /// Get a handle that can swap the served registry while the server runs.
+    pub fn handle(&self) -> RegistryHandle<B> {
+        self.clone()
+    }
+
+    /// Metrics counting the reloads made through this handle and its clones.
+    #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+    pub fn reload_metrics(&self) -> &ReloadMetrics {
+        self.reload.metrics()
+    }
+}
+
 /// A standalone HTTP server for exposing metrics.
 ///
 /// The server is generic over the metric backend, allowing you to use
@@ -157,7 +409,7 @@ impl<B: MetricBackend> Clone for AppState<B> {
 ///
 /// // Get a handle to create metrics
 /// let registry = server.registry();
-/// let counter = registry.write().await.counter("my_counter", "A counter")?;
+/// let counter = registry.write().unwrap().counter("my_counter", "A counter")?;
 /// counter.inc();
 ///
 /// // Run the server
@@ -165,7 +417,9 @@ impl<B: MetricBackend> Clone for AppState<B> {
 /// ```
 pub struct StandaloneServer<B: MetricBackend> {
     config: ServerConfig,
-    registry: Arc<RwLock<ObservabilityRegistry<B>>>,
+    registry: ServedRegistry<B>,
+    #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+    reload: ConfigReload,
 }
 
 impl<B: MetricBackend> StandaloneServer<B> {
@@ -179,18 +433,27 @@ impl<B: MetricBackend> StandaloneServer<B> {
         &self.config
     }
 
-    /// Get a handle to the metrics registry.
+    /// Get a handle to the metrics registry served right now.
     ///
     /// Use this to create metrics that will be exposed on the `/metrics` endpoint.
     ///
     /// # Example
     /// ```ignore
     /// let registry = server.registry();
-    /// let counter = registry.write().await.counter("requests_total", "Total requests")?;
+    /// let counter = registry.write().unwrap().counter("requests_total", "Total requests")?;
     /// counter.inc();
     /// ```
-    pub fn registry(&self) -> Arc<RwLock<ObservabilityRegistry<B>>> {
-        Arc::clone(&self.registry)
+    pub fn registry(&self) -> SharedRegistry<B> {
+        self.registry.load_full()
+    }
+
+    /// Get a handle that can swap the served registry while the server runs.
+    pub fn handle(&self) -> RegistryHandle<B> {
+        RegistryHandle {
+            registry: Arc::clone(&self.registry),
+            #[cfg(any(feature = "json-config", feature = "yaml-config"))]
+            reload: self.reload.clone(),
+        }
     }
 
     /// Run the server (blocking).
@@ -198,25 +461,53 @@ impl<B: MetricBackend> StandaloneServer<B> {
     where
         B::Registry: MetricsRenderer<Error = std::fmt::Error>,
     {
+        self.config.validate()?;
+
         let state = AppState {
             registry: Arc::clone(&self.registry),
+            scrape_permits: self
+                .config
+                .max_concurrent_scrapes
+                .map(|limit| Arc::new(Semaphore::new(limit))),
         };
 
         let app = self.create_router(state);
-        let addr = format!("{}:{}", self.config.host, self.config.port);
+        #[cfg(feature = "sse")]
+        let (app, _renderer) = self.with_metrics_stream(app);
 
-        let listener = TcpListener::bind(&addr)
-            .await
-            .map_err(|e| ServerError::BindError(e.to_string()))?;
+        match &self.config.listen_on {
+            ListenOn::Tcp => {
+                let addr = format!("{}:{}", self.config.host, self.config.port);
+                let listener = TcpListener::bind(&addr)
+                    .await
+                    .map_err(ServerError::BindFailed)?;
+                let local_addr = listener.local_addr().map_err(ServerError::BindFailed)?;
 
-        println!(
-            "Observability server listening on http://{}",
-            listener.local_addr().unwrap()
-        );
+                log::info!("Observability server listening on http://{}", local_addr);
 
-        axum::serve(listener, app)
-            .await
-            .map_err(|e| ServerError::ServeError(e.to_string()))?;
+                axum::serve(listener, app)
+                    .await
+                    .map_err(|e| ServerError::ServeError(e.to_string()))?;
+            }
+            #[cfg(unix)]
+            ListenOn::Unix(path) => {
+                remove_stale_socket(path)?;
+
+                let listener = tokio::net::UnixListener::bind(path).map_err(|e| {
+                    ServerError::BindFailed(std::io::Error::new(
+                        e.kind(),
+                        format!("{}: {}", path.display(), e),
+                    ))
+                })?;
+                let _socket_file = SocketFileGuard(path.clone());
+
+                log::info!("Observability server listening on unix:{}", path.display());
+
+                axum::serve(listener, app)
+                    .await
+                    .map_err(|e| ServerError::ServeError(e.to_string()))?;
+            }
+        }
 
         Ok(())
     }
@@ -226,11 +517,93 @@ impl<B: MetricBackend> StandaloneServer<B> {
     where
         B::Registry: MetricsRenderer<Error = std::fmt::Error>,
     {
-        Router::new()
-            .route(&self.config.metrics_path, get(metrics_handler::<B>))
+        let router = Router::new()
             .route(&self.config.health_path, get(health_handler))
-            .route(&self.config.ready_path, get(ready_handler))
-            .with_state(state)
+            .route(&self.config.ready_path, get(ready_handler));
+
+        let router = if self.config.serve_metrics {
+            router.route(&self.config.metrics_path, get(metrics_handler::<B>))
+        } else {
+            router
+        };
+        #[cfg(feature = "dashboard")]
+        let router = if self.config.serve_metrics {
+            router.route(&self.config.dashboard_path, get(dashboard_handler::<B>))
+        } else {
+            router
+        };
+
+        router.with_state(state)
+    }
+
+    /// Add the metrics stream to `router` if configured, starting its renderer.
+    ///
+    /// The renderer is stopped when the returned guard is dropped.
+    #[cfg(feature = "sse")]
+    fn with_metrics_stream(&self, router: Router) -> (Router, Option<AbortOnDrop>)
+    where
+        B::Registry: MetricsRenderer<Error = std::fmt::Error>,
+    {
+        let Some(interval) = self
+            .config
+            .metrics_stream_interval
+            .filter(|_| self.config.serves_metrics_stream())
+        else {
+            return (router, None);
+        };
+
+        let feed = SnapshotFeed::new();
+        let renderer = tokio::spawn(Arc::clone(&feed).run(Arc::clone(&self.registry), interval));
+        let router = router.route(
+            &self.config.metrics_stream_path,
+            get(metrics_stream_handler).with_state(feed),
+        );
+        (router, Some(AbortOnDrop(renderer)))
+    }
+}
+
+/// Remove a socket file at `path` left behind by a previous run.
+///
+/// A socket something still accepts connections on is in use, and any other
+/// kind of file is not ours to remove: both fail instead.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> Result<(), ServerError> {
+    use std::io::{Error, ErrorKind};
+    use std::os::unix::fs::FileTypeExt;
+
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !meta.file_type().is_socket() {
+        return Err(ServerError::BindFailed(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )));
+    }
+
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(ServerError::BindFailed(Error::new(
+            ErrorKind::AddrInUse,
+            format!("{} is in use by another server", path.display()),
+        ))),
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            log::info!("Removing stale socket {}", path.display());
+            let _ = std::fs::remove_file(path);
+            Ok(())
+        }
+        // Let bind report whatever is wrong with the path
+        Err(_) => Ok(()),
+    }
+}
+
+/// Removes a Unix socket file when the server stops, including on cancellation.
+#[cfg(unix)]
+struct SocketFileGuard(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFileGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
     }
 }
 
@@ -238,11 +611,73 @@ impl<B: MetricBackend> StandaloneServer<B> {
 #[derive(Debug, thiserror::Error)]
 pub enum ServerError {
     #[error("Failed to bind to address: {0}")]
-    BindError(String),
+    BindFailed(#[source] std::io::Error),
+    #[error("Invalid TLS configuration: {0}")]
+    TlsConfig(String),
+    #[error("Invalid server configuration: {0}")]
+    InvalidConfig(String),
     #[error("Server error: {0}")]
     ServeError(String),
 }
 
+/// Why a supervised server task ended badly.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Server(#[from] ServerError),
+    #[error("Server task panicked or was cancelled: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// Every failure collected by [`join_servers`].
+#[derive(Debug, thiserror::Error)]
+#[error("{} server task(s) failed: {}", .0.len(), summarize(.0))]
+pub struct ServerErrors(pub Vec<AppError>);
+
+fn summarize(errors: &[AppError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Wait for every server task in `servers`, collecting all failures.
+///
+/// The set is drained even after a task fails, so one crashing server can't
+/// hide why another one stopped. Returns `Ok` only if every task finished
+/// with `Ok`.
+///
+/// # Example
+/// ```ignore
+/// let mut servers = JoinSet::new();
+/// servers.spawn(async move { internal.run().await });
+/// servers.spawn(async move { public.run().await });
+///
+/// if let Err(errors) = join_servers(servers).await {
+///     eprintln!("{errors}");
+/// }
+/// ```
+pub async fn join_servers(
+    mut servers: tokio::task::JoinSet<Result<(), ServerError>>,
+) -> Result<(), ServerErrors> {
+    let mut errors = Vec::new();
+
+    while let Some(task_return) = servers.join_next().await {
+        match task_return {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => errors.push(AppError::Server(e)),
+            Err(e) => errors.push(AppError::Task(e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ServerErrors(errors))
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HTTP Handlers
 // ═══════════════════════════════════════════════════════════════════════════
@@ -251,24 +686,55 @@ async fn metrics_handler<B: MetricBackend>(State(state): State<AppState<B>>) ->
 where
     B::Registry: MetricsRenderer<Error = std::fmt::Error>,
 {
-    let registry = state.registry.read().await;
-
-    match registry.render() {
-        Ok(rendered) => {
-            let content_type = rendered.content_type.clone();
-            (
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, content_type)],
-                rendered.into_bytes(),
-            )
-                .into_response()
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to render metrics: {}", e),
-        )
-            .into_response(),
-    }
+    // Held until the response is built, so it bounds concurrent renders
+    let _permit = match &state.scrape_permits {
+        Some(permits) => match permits.try_acquire() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Too many concurrent scrapes",
+                )
+                    .into_response()
+            }
+        },
+        None => None,
+    };
+
+    let response = scrape(
+        &state
+            .registry
+            .load()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+    );
+
+    (
+        StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        [(header::CONTENT_TYPE, response.content_type)],
+        response.body,
+    )
+        .into_response()
+}
+
+#[cfg(feature = "dashboard")]
+async fn dashboard_handler<B: MetricBackend>(
+    State(state): State<AppState<B>>,
+) -> axum::response::Html<String> {
+    axum::response::Html(super::dashboard::render_dashboard(
+        &state
+            .registry
+            .load()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+    ))
+}
+
+#[cfg(feature = "sse")]
+async fn metrics_stream_handler(State(feed): State<Arc<SnapshotFeed>>) -> impl IntoResponse {
+    use axum::response::sse::{KeepAlive, Sse};
+
+    Sse::new(feed.subscribe()).keep_alive(KeepAlive::default())
 }
 
 async fn health_handler() -> (StatusCode, &'static str) {
@@ -287,14 +753,27 @@ async fn ready_handler() -> (StatusCode, &'static str) {
 mod tests {
     use super::*;
 
+    /// Render the server's `/metrics` response without binding a port.
+    #[cfg(feature = "prometheus")]
+    async fn scrape(state: AppState<crate::backends::prometheus::PrometheusBackend>) -> String {
+        let response = metrics_handler(State(state)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
     #[test]
     fn test_server_config_defaults() {
         let config = ServerConfig::default();
+        assert_eq!(config.listen_on, ListenOn::Tcp);
         assert_eq!(config.port, 9090);
         assert_eq!(config.host, "0.0.0.0");
         assert_eq!(config.metrics_path, "/metrics");
         assert_eq!(config.health_path, "/health");
         assert_eq!(config.ready_path, "/ready");
+        assert_eq!(config.max_concurrent_scrapes, None);
+        assert!(config.serve_metrics);
     }
 
     #[cfg(feature = "prometheus")]
@@ -312,4 +791,544 @@ mod tests {
         assert_eq!(server.config().host, "127.0.0.1");
         assert_eq!(server.config().metrics_path, "/prometheus");
     }
+
+    #[test]
+    fn test_server_config_validate() {
+        assert!(ServerConfig::default().validate().is_ok());
+
+        let clashing = ServerConfig {
+            ready_path: "/health".into(),
+            ..Default::default()
+        };
+        let relative = ServerConfig {
+            metrics_path: "metrics".into(),
+            ..Default::default()
+        };
+        let no_scrapes = ServerConfig {
+            max_concurrent_scrapes: Some(0),
+            ..Default::default()
+        };
+        for config in [clashing, relative, no_scrapes] {
+            assert!(matches!(
+                config.validate(),
+                Err(ServerError::InvalidConfig(_))
+            ));
+        }
+
+        // The metrics path is free to reuse when metrics aren't served
+        let health_only = ServerConfig {
+            metrics_path: "/health".into(),
+            serve_metrics: false,
+            ..Default::default()
+        };
+        assert!(health_only.validate().is_ok());
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_run_reports_port_in_use() {
+        use crate::backends::prometheus::PrometheusBackend;
+
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let server = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(port)
+            .build();
+        match server.run().await {
+            Err(ServerError::BindFailed(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::AddrInUse)
+            }
+            other => panic!("expected BindFailed, got {:?}", other),
+        }
+    }
+
+    #[cfg(all(unix, feature = "prometheus"))]
+    #[tokio::test]
+    async fn test_serves_metrics_over_unix_socket() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path =
+            std::env::temp_dir().join(format!("observability-kit-uds-{}.sock", std::process::id()));
+        let server = StandaloneServer::<PrometheusBackend>::builder()
+            .unix_socket(&path)
+            .build();
+        server
+            .registry()
+            .write()
+            .unwrap()
+            .counter("requests_total", "Requests")
+            .unwrap();
+
+        let task = tokio::spawn(async move { server.run().await });
+
+        let mut stream = None;
+        for _ in 0..100 {
+            if let Ok(connected) = tokio::net::UnixStream::connect(&path).await {
+                stream = Some(connected);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let mut stream = stream.expect("server did not bind the socket");
+
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("requests_total"));
+
+        task.abort();
+        let _ = task.await;
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_stale_socket() {
+        use std::io::ErrorKind;
+        use std::os::unix::net::UnixListener;
+
+        let dir = std::env::temp_dir().join(format!(
+            "observability-kit-stale-socket-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A socket nothing listens on any more is removed
+        let stale = dir.join("stale.sock");
+        drop(UnixListener::bind(&stale).unwrap());
+        assert!(stale.exists());
+        remove_stale_socket(&stale).unwrap();
+        assert!(!stale.exists());
+
+        // A socket still in use is left alone
+        let live = dir.join("live.sock");
+        let _listener = UnixListener::bind(&live).unwrap();
+        match remove_stale_socket(&live) {
+            Err(ServerError::BindFailed(e)) => assert_eq!(e.kind(), ErrorKind::AddrInUse),
+            other => panic!("expected BindFailed, got {:?}", other),
+        }
+        assert!(live.exists());
+
+        // Any other file is never removed
+        let file = dir.join("metrics.sock");
+        std::fs::write(&file, "not a socket").unwrap();
+        match remove_stale_socket(&file) {
+            Err(ServerError::BindFailed(e)) => assert_eq!(e.kind(), ErrorKind::AlreadyExists),
+            other => panic!("expected BindFailed, got {:?}", other),
+        }
+        assert!(file.exists());
+
+        remove_stale_socket(&dir.join("missing.sock")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_scrapes_over_limit_get_503() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use prometheus_client::encoding::{EncodeMetric, MetricEncoder};
+        use prometheus_client::metrics::MetricType;
+        use std::time::Duration;
+
+        /// A gauge that keeps each scrape busy for a while.
+        #[derive(Debug)]
+        struct SlowGauge;
+
+        impl EncodeMetric for SlowGauge {
+            fn encode(&self, mut encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+                std::thread::sleep(Duration::from_millis(200));
+                encoder.encode_gauge(&1i64)
+            }
+
+            fn metric_type(&self) -> MetricType {
+                MetricType::Gauge
+            }
+        }
+
+        let mut registry = ObservabilityRegistry::<PrometheusBackend>::new();
+        registry
+            .inner_mut()
+            .register("slow_value", "Slow to compute", SlowGauge);
+        let state = AppState {
+            registry: Arc::new(ArcSwap::from_pointee(RwLock::new(registry))),
+            scrape_permits: Some(Arc::new(Semaphore::new(1))),
+        };
+
+        let scrapes: Vec<_> = (0..4)
+            .map(|_| {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    metrics_handler::<PrometheusBackend>(State(state))
+                        .await
+                        .into_response()
+                        .status()
+                })
+            })
+            .collect();
+
+        let mut statuses = Vec::new();
+        for scrape in scrapes {
+            statuses.push(scrape.await.unwrap());
+        }
+
+        assert!(statuses.contains(&StatusCode::OK));
+        assert!(statuses.contains(&StatusCode::SERVICE_UNAVAILABLE));
+        assert!(statuses
+            .iter()
+            .all(|s| *s == StatusCode::OK || *s == StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[cfg(all(feature = "sse", feature = "prometheus"))]
+    #[tokio::test]
+    async fn test_metrics_stream_pushes_snapshots() {
+        use crate::backends::prometheus::PrometheusBackend;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(port)
+            .metrics_stream(Duration::from_millis(20))
+            .build();
+        let jobs = server
+            .registry()
+            .write()
+            .unwrap()
+            .counter("jobs", "Jobs")
+            .unwrap();
+        let task = tokio::spawn(async move { server.run().await });
+
+        let url = format!("http://127.0.0.1:{}/metrics/stream", port);
+        let mut response = None;
+        for _ in 0..100 {
+            if let Ok(connected) = reqwest::get(&url).await {
+                response = Some(connected);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mut response = response.expect("server did not start");
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()[reqwest::header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        let mut received = String::new();
+        while received.matches("event: metrics\n").count() < 3 {
+            jobs.inc();
+            let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+                .await
+                .expect("no event within 5s")
+                .unwrap()
+                .expect("stream ended");
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(received.contains("data: # TYPE jobs counter\n"));
+
+        task.abort();
+    }
+
+    #[cfg(all(feature = "dashboard", feature = "prometheus"))]
+    #[tokio::test]
+    async fn test_dashboard_served_at_root() {
+        use crate::backends::prometheus::PrometheusBackend;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(port)
+            .build();
+        server
+            .registry()
+            .write()
+            .unwrap()
+            .gauge("queue_depth", "Queued jobs")
+            .unwrap()
+            .set(12);
+        let task = tokio::spawn(async move { server.run().await });
+
+        let url = format!("http://127.0.0.1:{}/", port);
+        let mut response = None;
+        for _ in 0..100 {
+            if let Ok(connected) = reqwest::get(&url).await {
+                response = Some(connected);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let response = response.expect("server did not start");
+        assert_eq!(response.status(), 200);
+        assert!(response.headers()[reqwest::header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+
+        let page = response.text().await.unwrap();
+        assert!(page.contains("<td>queue_depth</td>"));
+        assert!(page.contains("<td class=\"value\">12</td>"));
+
+        task.abort();
+    }
+
+    #[cfg(feature = "sse")]
+    #[test]
+    fn test_metrics_stream_config_validate() {
+        let zero_interval = ServerConfig {
+            metrics_stream_interval: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let clashing = ServerConfig {
+            metrics_stream_path: "/metrics".into(),
+            metrics_stream_interval: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        for config in [zero_interval, clashing] {
+            assert!(matches!(
+                config.validate(),
+                Err(ServerError::InvalidConfig(_))
+            ));
+        }
+
+        // An unserved stream's path is free to reuse
+        let unserved = ServerConfig {
+            metrics_stream_path: "/metrics".into(),
+            ..Default::default()
+        };
+        assert!(unserved.validate().is_ok());
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_servers_on_two_ports_share_a_registry() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use tokio::task::JoinSet;
+
+        fn free_port() -> u16 {
+            std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port()
+        }
+
+        let (internal_port, public_port) = (free_port(), free_port());
+        let internal = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(internal_port)
+            .build();
+        let public = StandaloneServer::<PrometheusBackend>::builder()
+            .host("127.0.0.1")
+            .port(public_port)
+            .serve_metrics(false)
+            .registry(internal.registry())
+            .build();
+        assert!(Arc::ptr_eq(&internal.registry(), &public.registry()));
+
+        public
+            .registry()
+            .write()
+            .unwrap()
+            .counter("requests_total", "Requests")
+            .unwrap();
+
+        let mut servers = JoinSet::new();
+        servers.spawn(async move { internal.run().await });
+        servers.spawn(async move { public.run().await });
+
+        let get = |port: u16, path: &str| {
+            let url = format!("http://127.0.0.1:{}{}", port, path);
+            async move {
+                for _ in 0..100 {
+                    if let Ok(response) = reqwest::get(&url).await {
+                        return response;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+                panic!("no response from {url}");
+            }
+        };
+
+        let metrics = get(internal_port, "/metrics").await;
+        assert_eq!(metrics.status(), 200);
+        assert!(metrics.text().await.unwrap().contains("requests_total"));
+        assert_eq!(get(public_port, "/health").await.status(), 200);
+        assert_eq!(get(public_port, "/ready").await.status(), 200);
+        assert_eq!(get(public_port, "/metrics").await.status(), 404);
+
+        servers.abort_all();
+        while let Some(result) = servers.join_next().await {
+            assert!(result.unwrap_err().is_cancelled());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_join_servers_records_every_failure() {
+        use tokio::task::JoinSet;
+
+        let mut servers = JoinSet::new();
+        servers.spawn(async {
+            Err(ServerError::BindFailed(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                "port 9090 in use",
+            )))
+        });
+        servers.spawn(async {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            Ok(())
+        });
+        servers.spawn(async {
+            tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+            Err(ServerError::ServeError("connection reset".into()))
+        });
+
+        let ServerErrors(errors) = join_servers(servers).await.unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, AppError::Server(ServerError::BindFailed(_)))));
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, AppError::Server(ServerError::ServeError(_)))));
+
+        let message = ServerErrors(errors).to_string();
+        assert!(message.starts_with("2 server task(s) failed"));
+        assert!(message.contains("port 9090 in use") && message.contains("connection reset"));
+
+        assert!(join_servers(JoinSet::new()).await.is_ok());
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_registry_handle_swap_changes_scrape_output() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use crate::core::metrics::MetricError;
+
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let old_requests = server
+            .registry()
+            .write()
+            .unwrap()
+            .counter("old_requests_total", "Before reload")
+            .unwrap();
+
+        let state = AppState {
+            registry: Arc::clone(&server.registry),
+            scrape_permits: None,
+        };
+        assert!(scrape(state.clone()).await.contains("old_requests_total"));
+
+        // An in-flight scrape of the old registry doesn't hold up the swap
+        let previous = {
+            let in_flight = server.registry();
+            let rendering = in_flight.read().unwrap();
+
+            let mut fresh = ObservabilityRegistry::<PrometheusBackend>::new();
+            fresh.counter("new_requests_total", "After reload").unwrap();
+            let previous = server.handle().swap(fresh);
+            assert!(rendering.find_counter("old_requests_total").is_some());
+            previous
+        };
+
+        {
+            let previous = previous.read().unwrap();
+            assert!(previous.find_counter("old_requests_total").is_some());
+            assert!(old_requests.is_orphaned());
+            assert_eq!(
+                old_requests.try_inc(),
+                Err(MetricError::OrphanedHandle("old_requests_total".into()))
+            );
+            // Dropped with a logged warning rather than reaching a registry nobody serves
+            old_requests.inc();
+            assert_eq!(old_requests.get_counter(), 0);
+            assert_eq!(
+                previous
+                    .find_counter("old_requests_total")
+                    .unwrap()
+                    .get_counter(),
+                0
+            );
+        }
+
+        let output = scrape(state).await;
+        assert!(output.contains("new_requests_total"));
+        assert!(!output.contains("old_requests_total"));
+    }
+
+    #[cfg(all(feature = "prometheus", feature = "global"))]
+    #[tokio::test]
+    async fn test_serves_the_global_registry() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use crate::core::global::global_registry;
+
+        let server = StandaloneServer::<PrometheusBackend>::builder()
+            .registry(Arc::clone(global_registry()))
+            .build();
+        global_registry()
+            .write()
+            .unwrap()
+            .counter("served_global_jobs", "Jobs")
+            .unwrap()
+            .inc();
+
+        let state = AppState {
+            registry: Arc::clone(&server.registry),
+            scrape_permits: None,
+        };
+        assert!(scrape(state).await.contains("served_global_jobs_total 1"));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_lazy_metric_registers_in_the_served_registry() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use crate::core::lazy::LazyMetric;
+
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let retries =
+            LazyMetric::counter(&server.registry(), "retries", "Retried requests").unwrap();
+        let state = AppState {
+            registry: Arc::clone(&server.registry),
+            scrape_permits: None,
+        };
+        assert!(!scrape(state.clone()).await.contains("retries"));
+
+        retries.inc();
+        assert!(scrape(state).await.contains("retries_total 1"));
+    }
+
+    #[cfg(all(feature = "prometheus", feature = "metrics-facade"))]
+    #[tokio::test]
+    async fn test_metrics_recorder_records_into_the_served_registry() {
+        use crate::backends::prometheus::PrometheusBackend;
+        use crate::core::facade::MetricsRecorder;
+
+        let server = StandaloneServer::<PrometheusBackend>::builder().build();
+        let recorder = MetricsRecorder::new(&server.registry()).unwrap();
+        ::metrics::with_local_recorder(&recorder, || {
+            ::metrics::counter!("served.jobs").increment(2);
+        });
+
+        let state = AppState {
+            registry: Arc::clone(&server.registry),
+            scrape_permits: None,
+        };
+        assert!(scrape(state).await.contains("served_jobs_total 2"));
+    }
 }
@@ -60,7 +60,15 @@
 //! | `prometheus` | Prometheus metrics backend | ✓ |
 //! | `otlp` | OpenTelemetry/OTLP backend | |
 //! | `standalone` | Standalone HTTP server | ✓ |
+//! | `blocking-server` | Minimal std-only `/metrics` server on a background thread | |
+//! | `sse` | Server-Sent Events stream of the metrics on the standalone server | |
 //! | `axum-integration` | Axum middleware integration | |
+//! | `actix` | Actix-web metrics endpoint | |
+//! | `tokio` | Async helpers such as non-blocking config loading | |
+//! | `global` | Lazily created process-wide registry via `global_registry()` | |
+//! | `redaction` | Redact label values matching a regex at render time | |
+//! | `derive` | `#[derive(Metrics)]` for structs of metrics | |
+//! | `metrics-facade` | Recorder routing the `metrics` crate's macros into a registry | |
 //! | `mock` | Mock backend for testing | |
 //! | `json-config` | JSON configuration support | |
 //! | `yaml-config` | YAML configuration support | |
@@ -71,17 +79,33 @@ pub mod core;
 // Feature-gated modules
 pub mod backends;
 
-#[cfg(feature = "standalone")]
+#[cfg(any(
+    feature = "standalone",
+    feature = "blocking-server",
+    feature = "actix",
+    feature = "axum-integration"
+))]
 pub mod http;
 
+#[cfg(any(feature = "json-config", feature = "yaml-config"))]
+pub mod config;
+
+#[cfg(feature = "derive")]
+pub use observability_kit_derive::Metrics;
+
 // Prelude for convenient imports
 pub mod prelude {
-    pub use crate::core::metrics::{CounterTrait, GaugeTrait, HistogramTrait, Metric};
+    pub use crate::core::metrics::{
+        CounterTrait, FloatCounterTrait, FloatGaugeTrait, GaugeTrait, HistogramStatsTrait,
+        HistogramTrait, InflightGuard, Metric, MetricError, MetricKind, ObserveCounts,
+    };
 
     #[cfg(feature = "prometheus")]
     pub use crate::backends::prometheus::{
         // Basic metrics
         counter,
+        float_counter,
+        float_gauge,
         gauge,
         histogram,
         histogram_for_bytes,
@@ -93,14 +117,18 @@ pub mod prelude {
         labeled_histogram,
         labeled_histogram_for_bytes,
         labeled_histogram_for_latency,
+        DynamicLabels,
         EncodeLabelSet,
         Family,
+        InitLabels,
         LabeledCounter,
         LabeledGauge,
         LabeledHistogram,
         // Types
         PrometheusBackend,
         PrometheusCounter,
+        PrometheusFloatCounter,
+        PrometheusFloatGauge,
         PrometheusGauge,
         PrometheusHistogram,
         PrometheusRegistry,
@@ -110,12 +138,17 @@ pub mod prelude {
         DEFAULT_SIZE_BUCKETS,
     };
 
+    #[cfg(feature = "derive")]
+    pub use crate::Metrics;
+
     #[cfg(feature = "mock")]
     pub use crate::backends::mock::{
-        test_counter, test_gauge, test_histogram, MockCounter, MockGauge, MockHistogram,
-        TestCounter, TestGauge, TestHistogram,
+        test_counter, test_float_gauge, test_gauge, test_histogram, MockCounter, MockFloatGauge,
+        MockGauge, MockHistogram, TestCounter, TestFloatGauge, TestGauge, TestHistogram,
     };
 
     #[cfg(feature = "standalone")]
-    pub use crate::http::standalone::{ServerConfig, StandaloneServer, StandaloneServerBuilder};
+    pub use crate::http::standalone::{
+        ListenOn, RegistryHandle, ServerConfig, StandaloneServer, StandaloneServerBuilder,
+    };
 }
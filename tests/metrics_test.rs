@@ -142,3 +142,43 @@ mod mock_tests {
         assert_eq!(counter1.get(), 6);
     }
 }
+
+#[cfg(all(feature = "derive", feature = "prometheus"))]
+mod derive_tests {
+    use observability_kit::backends::prometheus::{
+        PrometheusCounter, PrometheusGauge, PrometheusRegistry,
+    };
+    use observability_kit::Metrics;
+
+    #[derive(Metrics)]
+    struct ServerMetrics {
+        #[metric(name = "requests_total", help = "Requests served")]
+        requests: PrometheusCounter,
+        #[metric(help = "Open connections")]
+        connections: PrometheusGauge,
+    }
+
+    #[test]
+    fn test_derived_register_populates_registry() {
+        let mut registry = PrometheusRegistry::new();
+        let metrics = ServerMetrics::register(&mut registry).unwrap();
+
+        metrics.requests.inc();
+        metrics.connections.set(3);
+
+        assert_eq!(
+            registry
+                .find_counter("requests_total")
+                .unwrap()
+                .get_counter(),
+            1
+        );
+        assert_eq!(registry.find_gauge("connections").unwrap().get_gauge(), 3);
+
+        let output = registry.render().unwrap();
+        assert!(output
+            .as_str()
+            .unwrap()
+            .contains("# HELP connections Open connections."));
+    }
+}